@@ -0,0 +1,151 @@
+use gdal::Dataset;
+use gdal::raster::{Buffer, GdalType};
+use gdal_sys::GDALDataType;
+
+use std::error::Error;
+
+/// mask value for pixels where either dataset is invalid
+const CHANGE_MASK_NO_DATA: u8 = 255;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeMethod {
+    /// b - a
+    Difference,
+    /// b / a
+    Ratio,
+    /// (b - a) / (b + a)
+    NormalizedDifference,
+}
+
+impl ChangeMethod {
+    fn compute(&self, a: f64, b: f64) -> f64 {
+        match self {
+            ChangeMethod::Difference => b - a,
+            ChangeMethod::Ratio => b / a,
+            ChangeMethod::NormalizedDifference => (b - a) / (b + a),
+        }
+    }
+
+    fn magnitude(&self, value: f64) -> f64 {
+        // ratios are symmetric about 1, e.g. 0.5 and 2 are equal change
+        match self {
+            ChangeMethod::Ratio => value.max(1.0 / value),
+            _ => value.abs(),
+        }
+    }
+}
+
+pub fn change(dataset_a: &Dataset, dataset_b: &Dataset,
+        method: ChangeMethod) -> Result<Dataset, Box<dyn Error>> {
+    let (width, height) = dataset_a.raster_size();
+    let change_dataset = crate::DatasetBuilder::new(width, height)
+        .bands(dataset_a.raster_count())
+        .gdal_type(GDALDataType::GDT_Float32).no_data(f64::NAN)
+        .fill(false).like(dataset_a)?.create_mem()?;
+
+    // invalid results become nodata
+    _change::<f32, _>(dataset_a, dataset_b, method, &change_dataset,
+        |x| if x.is_finite() { x as f32 } else { f32::NAN })?;
+
+    Ok(change_dataset)
+}
+
+pub fn change_mask(dataset_a: &Dataset, dataset_b: &Dataset,
+        method: ChangeMethod, threshold: f64)
+        -> Result<Dataset, Box<dyn Error>> {
+    let (width, height) = dataset_a.raster_size();
+    let mask_dataset = crate::DatasetBuilder::new(width, height)
+        .bands(dataset_a.raster_count())
+        .no_data(CHANGE_MASK_NO_DATA as f64)
+        .fill(false).like(dataset_a)?.create_mem()?;
+
+    // changed pixels are 1, unchanged pixels are 0
+    _change::<u8, _>(dataset_a, dataset_b, method, &mask_dataset, |x| {
+        match x.is_finite() {
+            true => (method.magnitude(x) >= threshold) as u8,
+            false => CHANGE_MASK_NO_DATA,
+        }
+    })?;
+
+    Ok(mask_dataset)
+}
+
+fn _change<T: Copy + GdalType + Send, F: Fn(f64) -> T + Sync>(
+        dataset_a: &Dataset, dataset_b: &Dataset, method: ChangeMethod,
+        output: &Dataset, f: F) -> Result<(), Box<dyn Error>> {
+    // validate datasets are co-registered
+    if dataset_a.raster_size() != dataset_b.raster_size()
+            || dataset_a.raster_count() != dataset_b.raster_count() {
+        return Err("change datasets dimensions differ".into());
+    }
+
+    let (width, _) = dataset_a.raster_size();
+    let windows = crate::parallel::block_windows(dataset_a, width * 16)?;
+    for i in 0..dataset_a.raster_count() {
+        let rasterband_a = dataset_a.rasterband(i+1)?;
+        let rasterband_b = dataset_b.rasterband(i+1)?;
+
+        // read both rasterbands with nodata as nan
+        let blocks = crate::parallel::par_process_blocks(&windows,
+                |_, window| {
+            let mut buffers = Vec::new();
+            for rasterband in [&rasterband_a, &rasterband_b].iter() {
+                let no_data_value = rasterband.no_data_value();
+                let mut values = rasterband
+                    .read_as::<f64>(window.0, window.1, window.1)?.data;
+                for value in values.iter_mut() {
+                    if Some(*value) == no_data_value {
+                        *value = f64::NAN;
+                    }
+                }
+
+                buffers.push(values);
+            }
+
+            Ok(buffers)
+        }, |_, buffers| {
+            buffers[0].iter().zip(buffers[1].iter())
+                .map(|(a, b)| f(method.compute(*a, *b)))
+                .collect::<Vec<T>>()
+        })?;
+
+        let rasterband = output.rasterband(i+1)?;
+        for (window, data) in windows.iter().zip(blocks.into_iter()) {
+            let buffer = Buffer::new(window.1, data);
+            rasterband.write::<T>(window.0, window.1, &buffer)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChangeMethod;
+
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn change() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // identical datasets show no change over valid pixels
+        let change = super::change(&dataset, &dataset,
+            ChangeMethod::Difference).expect("compute change");
+        let values = change.rasterband(1).expect("get rasterband")
+            .read_band_as::<f32>().expect("read rasterband").data;
+        assert!(values.iter().all(|x| x.is_nan() || *x == 0.0));
+
+        let mask = super::change_mask(&dataset, &dataset,
+            ChangeMethod::Ratio, 1.5).expect("compute change mask");
+        let values = mask.rasterband(1).expect("get rasterband")
+            .read_band_as::<u8>().expect("read rasterband").data;
+        assert!(values.iter().all(|x| *x == 0 || *x == 255));
+
+        assert_eq!(ChangeMethod::Ratio.magnitude(0.5), 2.0);
+        assert_eq!(ChangeMethod::NormalizedDifference.compute(1.0, 3.0), 0.5);
+    }
+}
@@ -0,0 +1,207 @@
+use gdal::{Dataset, Driver};
+use st_image::coordinate::Geocode;
+
+use std::error::Error;
+use std::path::Path;
+
+static USAGE: &str = "usage: satmod <command> [options] <args>
+
+commands:
+    split --geocode <geohash|quadtile> --precision <n> <input> <directory>
+    merge <output> <input>...
+    fill <output> <input>...
+    coverage <input>...";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}\n\n{}", e, USAGE);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => return Err("missing command".into()),
+    };
+
+    match command {
+        "split" => split(args),
+        "merge" => merge(args),
+        "fill" => fill(args),
+        "coverage" => coverage(args),
+        _ => Err(format!("unknown command '{}'", command).into()),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct SplitArgs<'a> {
+    geocode: Geocode,
+    precision: usize,
+    input: &'a str,
+    directory: &'a str,
+}
+
+fn parse_split(args: &[String]) -> Result<SplitArgs, Box<dyn Error>> {
+    // parse options and positional arguments
+    let (mut geocode, mut precision) = (Geocode::Geohash, None);
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--geocode" => geocode = match iter.next().map(|x| x.as_str()) {
+                Some("geohash") => Geocode::Geohash,
+                Some("quadtile") => Geocode::QuadTile,
+                Some(value) => return Err(format!(
+                    "unknown geocode '{}'", value).into()),
+                None => return Err("missing geocode value".into()),
+            },
+            "--precision" => precision = match iter.next() {
+                Some(value) => Some(value.parse::<usize>()?),
+                None => return Err("missing precision value".into()),
+            },
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let precision = match precision {
+        Some(precision) => precision,
+        None => return Err("split requires '--precision'".into()),
+    };
+
+    if positional.len() != 2 {
+        return Err("split requires an input and a directory".into());
+    }
+
+    Ok(SplitArgs {
+        geocode,
+        precision,
+        input: positional[0],
+        directory: positional[1],
+    })
+}
+
+fn split(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let args = parse_split(args)?;
+
+    // split dataset and write each tile
+    let dataset = Dataset::open(Path::new(args.input))?;
+    let directory = Path::new(args.directory);
+    std::fs::create_dir_all(directory)?;
+
+    let driver = Driver::get("GTiff")?;
+    let mut count = 0;
+    for result in st_image::transform::split_iter(
+            &dataset, args.geocode, args.precision)? {
+        let result = result?;
+        let path = directory.join(format!("{}.tif", result.geocode));
+        result.dataset.create_copy(&driver,
+            path.to_str().ok_or("invalid output path")?)?;
+        count += 1;
+    }
+
+    println!("wrote {} tiles to {}", count, directory.display());
+    Ok(())
+}
+
+fn merge(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (output, datasets) = open_inputs(args)?;
+    let dataset = st_image::transform::merge(&datasets)?;
+    write_gtiff(&dataset, output)
+}
+
+fn fill(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (output, datasets) = open_inputs(args)?;
+    let dataset = st_image::fill(&datasets)?;
+    write_gtiff(&dataset, output)
+}
+
+fn coverage(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.is_empty() {
+        return Err("coverage requires at least one input".into());
+    }
+
+    for path in args.iter() {
+        let dataset = Dataset::open(Path::new(path))?;
+        println!("{}\t{:.6}", path, st_image::get_coverage(&dataset)?);
+    }
+
+    Ok(())
+}
+
+fn open_inputs(args: &[String])
+        -> Result<(&str, Vec<Dataset>), Box<dyn Error>> {
+    if args.len() < 2 {
+        return Err("command requires an output and inputs".into());
+    }
+
+    let mut datasets = Vec::new();
+    for path in args[1..].iter() {
+        datasets.push(Dataset::open(Path::new(path))?);
+    }
+
+    Ok((&args[0], datasets))
+}
+
+fn write_gtiff(dataset: &Dataset, path: &str) -> Result<(), Box<dyn Error>> {
+    let driver = Driver::get("GTiff")?;
+    dataset.create_copy(&driver, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use st_image::coordinate::Geocode;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_split() {
+        let split_args = args(&["--geocode", "quadtile",
+            "--precision", "8", "input.tif", "tiles"]);
+        assert_eq!(super::parse_split(&split_args).expect("parse split"),
+            super::SplitArgs {
+                geocode: Geocode::QuadTile,
+                precision: 8,
+                input: "input.tif",
+                directory: "tiles",
+            });
+
+        // geohash is the default geocode
+        let split_args = args(&["input.tif", "--precision", "4", "tiles"]);
+        assert_eq!(super::parse_split(&split_args).expect("parse split")
+            .geocode, Geocode::Geohash);
+    }
+
+    #[test]
+    fn parse_split_errors() {
+        let error = |values: &[&str]| {
+            match super::parse_split(&args(values)) {
+                Ok(_) => panic!("parsed invalid split arguments"),
+                Err(e) => e.to_string(),
+            }
+        };
+
+        assert_eq!(error(&["input.tif", "tiles"]),
+            "split requires '--precision'");
+        assert_eq!(error(&["--geocode", "s2", "--precision", "4",
+            "input.tif", "tiles"]), "unknown geocode 's2'");
+        assert_eq!(error(&["--precision", "4", "input.tif"]),
+            "split requires an input and a directory");
+        assert_eq!(error(&["--precision", "4", "a.tif", "b.tif", "tiles"]),
+            "split requires an input and a directory");
+        assert_eq!(error(&["--precision"]), "missing precision value");
+        assert!(super::parse_split(&args(&["--precision", "four",
+            "input.tif", "tiles"])).is_err());
+    }
+
+    #[test]
+    fn run() {
+        assert!(super::run(&args(&[])).is_err());
+        assert!(super::run(&args(&["tile"])).is_err());
+        assert!(super::run(&args(&["coverage"])).is_err());
+    }
+}
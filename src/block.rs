@@ -0,0 +1,104 @@
+use gdal::Dataset;
+use gdal::raster::{Buffer, GdalType, RasterBand};
+
+use std::error::Error;
+use std::marker::PhantomData;
+
+pub struct BlockIterator<'a, T: Copy + GdalType> {
+    rasterband: RasterBand<'a>,
+    size: (usize, usize),
+    block_size: (usize, usize),
+    counts: (usize, usize),
+    index: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<'a, T: Copy + GdalType> BlockIterator<'a, T> {
+    pub fn new(dataset: &'a Dataset, index: isize)
+            -> Result<Self, Box<dyn Error>> {
+        let rasterband = dataset.rasterband(index)?;
+        let block_size = rasterband.block_size();
+        Self::with_block_size(dataset, index, block_size)
+    }
+
+    pub fn with_block_size(dataset: &'a Dataset, index: isize,
+            block_size: (usize, usize)) -> Result<Self, Box<dyn Error>> {
+        if block_size.0 == 0 || block_size.1 == 0 {
+            return Err("block dimensions must be positive".into());
+        }
+
+        let size = dataset.raster_size();
+        let counts = ((size.0 + block_size.0 - 1) / block_size.0,
+            (size.1 + block_size.1 - 1) / block_size.1);
+
+        Ok(BlockIterator {
+            rasterband: dataset.rasterband(index)?,
+            size,
+            block_size,
+            counts,
+            index: 0,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<'a, T: Copy + GdalType> Iterator for BlockIterator<'a, T> {
+    type Item = Result<((isize, isize), (usize, usize), Buffer<T>),
+        Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.counts.0 * self.counts.1 {
+            return None;
+        }
+
+        // iterate over blocks in row-major order
+        let x = (self.index % self.counts.0) * self.block_size.0;
+        let y = (self.index / self.counts.0) * self.block_size.1;
+        self.index += 1;
+
+        // edge blocks are truncated to the raster extent
+        let offset = (x as isize, y as isize);
+        let size = (self.block_size.0.min(self.size.0 - x),
+            self.block_size.1.min(self.size.1 - y));
+
+        Some(self.rasterband.read_as::<T>(offset, size, size)
+            .map(|buffer| (offset, size, buffer))
+            .map_err(|e| e.into()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.counts.0 * self.counts.1 - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: Copy + GdalType> ExactSizeIterator for BlockIterator<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn block_iterator() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let blocks = super::BlockIterator::<u8>::with_block_size(
+            &dataset, 1, (100, 100)).expect("initialize iterator");
+        assert_eq!(blocks.len(), 9);
+
+        // blocks tile the raster exactly
+        let mut pixels = 0;
+        for block in blocks {
+            let (offset, size, buffer) = block.expect("read block");
+            assert!(offset.0 as usize + size.0 <= 256);
+            assert!(offset.1 as usize + size.1 <= 256);
+            assert_eq!(buffer.data.len(), size.0 * size.1);
+            pixels += buffer.data.len();
+        }
+
+        assert_eq!(pixels, 256 * 256);
+    }
+}
@@ -0,0 +1,407 @@
+use gdal::{Dataset, Driver};
+use gdal::raster::Buffer;
+use gdal_sys::GDALDataType;
+
+use crate::parallel::Window;
+
+use std::collections::HashMap;
+use std::error::Error;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Band(isize),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub(crate) fn bands(&self, indices: &mut Vec<isize>) {
+        match self {
+            Expr::Number(_) => {},
+            Expr::Band(index) => {
+                if !indices.contains(index) {
+                    indices.push(*index);
+                }
+            },
+            Expr::Negate(expr) => expr.bands(indices),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.bands(indices);
+                rhs.bands(indices);
+            },
+        }
+    }
+
+    pub(crate) fn evaluate(&self, bands: &HashMap<isize, Vec<f64>>,
+            size: usize) -> Vec<f64> {
+        match self {
+            Expr::Number(value) => vec![*value; size],
+            Expr::Band(index) => bands[index].clone(),
+            Expr::Negate(expr) => expr.evaluate(bands, size)
+                .into_iter().map(|x| -x).collect(),
+            Expr::Binary(op, lhs, rhs) => {
+                let mut values = lhs.evaluate(bands, size);
+                let rhs_values = rhs.evaluate(bands, size);
+                for (x, y) in values.iter_mut().zip(rhs_values.iter()) {
+                    *x = match op {
+                        '+' => *x + y,
+                        '-' => *x - y,
+                        '*' => *x * y,
+                        '/' => *x / y,
+                        _ => unreachable!(),
+                    };
+                }
+
+                values
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+    Open,
+    Close,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            // consume a numeric literal
+            let start = i;
+            while i < chars.len()
+                    && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+
+            let literal: String = chars[start..i].iter().collect();
+            tokens.push(Token::Number(literal.parse::<f64>()
+                .map_err(|_| format!("invalid number '{}'", literal))?));
+        } else if c.is_alphabetic() || c == '_' {
+            // consume a band identifier
+            let start = i;
+            while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+
+            tokens.push(Token::Identifier(
+                chars[start..i].iter().collect()));
+        } else {
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' => Token::Operator(c),
+                '(' => Token::Open,
+                ')' => Token::Close,
+                _ => return Err(format!(
+                    "unexpected character '{}'", c).into()),
+            });
+            i += 1;
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    index: usize,
+    descriptions: &'a [String],
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn expression(&mut self) -> Result<Expr, Box<dyn Error>> {
+        // additive operators bind loosest
+        let mut expr = self.term()?;
+        while let Some(Token::Operator(op)) = self.peek().cloned() {
+            if op != '+' && op != '-' {
+                break;
+            }
+
+            self.index += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, Box<dyn Error>> {
+        let mut expr = self.factor()?;
+        while let Some(Token::Operator(op)) = self.peek().cloned() {
+            if op != '*' && op != '/' {
+                break;
+            }
+
+            self.index += 1;
+            expr = Expr::Binary(op,
+                Box::new(expr), Box::new(self.factor()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, Box<dyn Error>> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Identifier(name)) => self.band(&name),
+            Some(Token::Operator('-')) =>
+                Ok(Expr::Negate(Box::new(self.factor()?))),
+            Some(Token::Open) => {
+                let expr = self.expression()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("missing closing parenthesis".into()),
+                }
+            },
+            Some(token) => Err(format!("unexpected token {:?}",
+                token).into()),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+
+    fn band(&self, name: &str) -> Result<Expr, Box<dyn Error>> {
+        // match band descriptions before numbered references
+        if let Some(i) = self.descriptions.iter().position(|x| x == name) {
+            return Ok(Expr::Band(i as isize + 1));
+        }
+
+        let index = match name.strip_prefix('B') {
+            Some(index) => index.parse::<isize>().ok(),
+            None => None,
+        };
+
+        match index {
+            Some(index) if index >= 1
+                    && index as usize <= self.descriptions.len() =>
+                Ok(Expr::Band(index)),
+            _ => Err(format!("unknown band '{}'", name).into()),
+        }
+    }
+}
+
+pub fn parse(expression: &str, descriptions: &[String])
+        -> Result<Expr, Box<dyn Error>> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        index: 0,
+        descriptions,
+    };
+
+    let expr = parser.expression()?;
+    if parser.index != parser.tokens.len() {
+        return Err(format!("unexpected trailing input in '{}'",
+            expression).into());
+    }
+
+    Ok(expr)
+}
+
+pub fn evaluate(dataset: &Dataset, expression: &str)
+        -> Result<Dataset, Box<dyn Error>> {
+    let descriptions = crate::band_descriptions(dataset)?;
+    let expr = parse(expression, &descriptions)?;
+    evaluate_expr(dataset, &expr)
+}
+
+pub fn evaluate_expr(dataset: &Dataset, expr: &Expr)
+        -> Result<Dataset, Box<dyn Error>> {
+    let mut indices = Vec::new();
+    expr.bands(&mut indices);
+
+    let (width, height) = dataset.raster_size();
+    let calc_dataset = crate::DatasetBuilder::new(width, height)
+        .gdal_type(GDALDataType::GDT_Float32).no_data(f64::NAN)
+        .fill(false).like(dataset)?.create_mem()?;
+
+    // read referenced rasterbands with nodata as nan
+    // with two intermediate rasters held while evaluating
+    let row_size = width * (indices.len() + 2) * 8;
+    let windows = crate::parallel::block_windows(dataset, row_size)?;
+    let blocks = crate::parallel::par_process_blocks(&windows,
+        |_, window| read_bands(dataset, &indices, window),
+        |window, bands| evaluate_bands(expr, &bands, window))?;
+
+    let rasterband = calc_dataset.rasterband(1)?;
+    for (window, data) in windows.iter().zip(blocks.into_iter()) {
+        let buffer = Buffer::new(window.1, data);
+        rasterband.write::<f32>(window.0, window.1, &buffer)?;
+    }
+
+    Ok(calc_dataset)
+}
+
+pub(crate) fn read_bands(dataset: &Dataset, indices: &[isize],
+        window: &Window) -> Result<HashMap<isize, Vec<f64>>, Box<dyn Error>> {
+    let mut bands = HashMap::new();
+    for index in indices.iter() {
+        let rasterband = dataset.rasterband(*index)?;
+        let no_data_value = rasterband.no_data_value();
+
+        // undefined scale and offset default to 1 and 0
+        let (scale, offset) = unsafe {
+            let c_rasterband = rasterband.c_rasterband();
+            (gdal_sys::GDALGetRasterScale(c_rasterband,
+                    std::ptr::null_mut()),
+                gdal_sys::GDALGetRasterOffset(c_rasterband,
+                    std::ptr::null_mut()))
+        };
+
+        // convert stored values to physical values
+        let mut values = rasterband
+            .read_as::<f64>(window.0, window.1, window.1)?.data;
+        for value in values.iter_mut() {
+            *value = match Some(*value) == no_data_value {
+                true => f64::NAN,
+                false => *value * scale + offset,
+            };
+        }
+
+        bands.insert(*index, values);
+    }
+
+    Ok(bands)
+}
+
+pub(crate) fn evaluate_bands(expr: &Expr, bands: &HashMap<isize, Vec<f64>>,
+        window: &Window) -> Vec<f32> {
+    // evaluate expression, invalid results become nodata
+    expr.evaluate(bands, (window.1).0 * (window.1).1).iter()
+        .map(|x| if x.is_finite() { *x as f32 } else { f32::NAN })
+        .collect()
+}
+
+pub fn histogram_match(source: &Dataset, reference: &Dataset)
+        -> Result<Dataset, Box<dyn Error>> {
+    if source.raster_count() != reference.raster_count() {
+        return Err("source and reference rasterband counts differ".into());
+    }
+
+    let driver = Driver::get("Mem")?;
+    let match_dataset = source.create_copy(&driver, "unreachable")?;
+    for i in 0..source.raster_count() {
+        // read sorted valid reference values
+        let rasterband = reference.rasterband(i+1)?;
+        let no_data_value = rasterband.no_data_value();
+        let mut reference_values: Vec<f64> = rasterband
+            .read_band_as::<f64>()?.data.into_iter()
+            .filter(|x| is_valid(*x, no_data_value)).collect();
+        reference_values.sort_by(|a, b| a.partial_cmp(b)
+            .unwrap_or(std::cmp::Ordering::Equal));
+
+        // map source values onto the reference distribution
+        let rasterband = match_dataset.rasterband(i+1)?;
+        let mut buffer = rasterband.read_band_as::<f64>()?;
+        match_values(&mut buffer.data, &reference_values,
+            rasterband.no_data_value());
+
+        let size = match_dataset.raster_size();
+        rasterband.write::<f64>((0, 0), size, &buffer)?;
+    }
+
+    Ok(match_dataset)
+}
+
+fn match_values(values: &mut [f64], reference: &[f64],
+        no_data_value: Option<f64>) {
+    let mut sorted: Vec<f64> = values.iter().cloned()
+        .filter(|x| is_valid(*x, no_data_value)).collect();
+    if sorted.is_empty() || reference.is_empty() {
+        return;
+    }
+
+    sorted.sort_by(|a, b| a.partial_cmp(b)
+        .unwrap_or(std::cmp::Ordering::Equal));
+
+    // map each distinct value at the midpoint of its cumulative range
+    let mut mapping = Vec::new();
+    let mut start = 0;
+    while start < sorted.len() {
+        let mut end = start;
+        while end < sorted.len() && sorted[end] == sorted[start] {
+            end += 1;
+        }
+
+        let quantile = (start + end - 1) as f64
+            / (2 * (sorted.len() - 1).max(1)) as f64;
+        let index = (quantile * (reference.len() - 1) as f64).round();
+        mapping.push((sorted[start], reference[index as usize]));
+        start = end;
+    }
+
+    for value in values.iter_mut() {
+        if !is_valid(*value, no_data_value) {
+            continue;
+        }
+
+        if let Ok(i) = mapping.binary_search_by(|x| x.0.partial_cmp(value)
+                .unwrap_or(std::cmp::Ordering::Equal)) {
+            *value = mapping[i].1;
+        }
+    }
+}
+
+fn is_valid(value: f64, no_data_value: Option<f64>) -> bool {
+    Some(value) != no_data_value && !value.is_nan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Expr;
+
+    use std::collections::HashMap;
+
+    #[test]
+    fn parse() {
+        let descriptions = vec!["red".to_string(), "nir".to_string()];
+        let expr = super::parse("(B2 - red) / (nir + B1)", &descriptions)
+            .expect("parse expression");
+
+        let mut bands = HashMap::new();
+        bands.insert(1, vec![1.0, 2.0, f64::NAN]);
+        bands.insert(2, vec![3.0, 2.0, 4.0]);
+        let values = expr.evaluate(&bands, 3);
+        assert_eq!(values[0], 0.5);
+        assert_eq!(values[1], 0.0);
+        assert!(values[2].is_nan());
+
+        assert_eq!(super::parse("-2 * 3 + 1", &descriptions)
+            .expect("parse expression").evaluate(&bands, 1), vec![-5.0]);
+        assert_eq!(super::parse("B2", &descriptions)
+            .expect("parse expression"), Expr::Band(2));
+
+        assert!(super::parse("B3", &descriptions).is_err());
+        assert!(super::parse("(B1 + B2", &descriptions).is_err());
+        assert!(super::parse("B1 B2", &descriptions).is_err());
+    }
+
+    #[test]
+    fn match_values() {
+        let reference = vec![10.0, 20.0, 30.0, 40.0];
+        let mut values = vec![4.0, 1.0, 0.0, 3.0, 2.0];
+        super::match_values(&mut values, &reference, Some(0.0));
+        assert_eq!(values, vec![40.0, 10.0, 0.0, 30.0, 20.0]);
+
+        // identical distributions are unchanged
+        let mut values = vec![1.0, 1.0, 2.0, 5.0];
+        super::match_values(&mut values, &[1.0, 1.0, 2.0, 5.0], None);
+        assert_eq!(values, vec![1.0, 1.0, 2.0, 5.0]);
+    }
+}
@@ -0,0 +1,222 @@
+use gdal::Dataset;
+use gdal_sys::GDALDataType;
+use serde_json::{json, Value};
+
+use crate::coordinate::{self, Target};
+
+use std::error::Error;
+use std::os::raw::c_int;
+
+static STAC_VERSION: &str = "1.0.0";
+static STAC_EXTENSIONS: [&str; 3] = [
+    "https://stac-extensions.github.io/eo/v1.1.0/schema.json",
+    "https://stac-extensions.github.io/projection/v1.1.0/schema.json",
+    "https://stac-extensions.github.io/raster/v1.1.0/schema.json",
+];
+
+// timestamp is the acquisition time in seconds since the unix epoch
+pub fn stac_item(dataset: &Dataset, id: &str, asset_paths: &[(&str, &str)],
+        timestamp: i64) -> Result<Value, Box<dyn Error>> {
+    // compute geographic bounds and the reprojected dataset outline
+    let (min_x, max_x, min_y, max_y) = coordinate::get_bounds(dataset, 4326)?;
+    let outline = coordinate::get_outline(dataset, &Target::Epsg(4326))?;
+    let footprint = json!({
+        "type": "Polygon",
+        "coordinates": [outline.iter().map(|(x, y)| [*x, *y])
+            .collect::<Vec<[f64; 2]>>()],
+    });
+
+    // compute projection extension fields
+    let (width, height) = dataset.raster_size();
+    let transform = dataset.geo_transform()?;
+    let crs = coordinate::identify_crs(&dataset.projection())?;
+    let projection = match crs.epsg_code {
+        Some(epsg_code) => json!({ "proj:epsg": epsg_code }),
+        None => json!({ "proj:epsg": null, "proj:wkt2": crs.wkt }),
+    };
+
+    let mut properties = json!({
+        "datetime": format_datetime(timestamp),
+        "proj:shape": [height, width],
+        "proj:transform": [transform[1], transform[2], transform[0],
+            transform[4], transform[5], transform[3]],
+    });
+
+    if let (Some(properties), Some(projection)) =
+            (properties.as_object_mut(), projection.as_object()) {
+        properties.extend(projection.clone());
+    }
+
+    // describe rasterbands on each asset
+    let descriptions = crate::band_descriptions(dataset)?;
+    let mut eo_bands = Vec::new();
+    let mut raster_bands = Vec::new();
+    for (i, description) in descriptions.iter().enumerate() {
+        let rasterband = dataset.rasterband((i + 1) as isize)?;
+        eo_bands.push(match description.is_empty() {
+            true => json!({ "name": format!("B{}", i + 1) }),
+            false => json!({ "name": description }),
+        });
+
+        let mut raster_band = json!({
+            "data_type": data_type_name(rasterband.band_type()),
+        });
+        if let Some(no_data_value) = rasterband.no_data_value() {
+            raster_band["nodata"] = nodata_value(no_data_value);
+        }
+
+        // record scale and offset where defined
+        let (scale, offset) = unsafe {
+            let c_rasterband = rasterband.c_rasterband();
+            let (mut scale_success, mut offset_success): (c_int, c_int)
+                = (0, 0);
+            let scale = gdal_sys::GDALGetRasterScale(c_rasterband,
+                &mut scale_success);
+            let offset = gdal_sys::GDALGetRasterOffset(c_rasterband,
+                &mut offset_success);
+            (if scale_success != 0 { Some(scale) } else { None },
+                if offset_success != 0 { Some(offset) } else { None })
+        };
+
+        if let Some(scale) = scale {
+            raster_band["scale"] = json!(scale);
+        }
+        if let Some(offset) = offset {
+            raster_band["offset"] = json!(offset);
+        }
+
+        raster_bands.push(raster_band);
+    }
+
+    let mut assets = serde_json::Map::new();
+    for (key, href) in asset_paths.iter() {
+        let mut asset = json!({
+            "href": href,
+            "roles": ["data"],
+            "eo:bands": eo_bands,
+            "raster:bands": raster_bands,
+        });
+        if let Some(media_type) = media_type(href) {
+            asset["type"] = json!(media_type);
+        }
+
+        assets.insert(key.to_string(), asset);
+    }
+
+    Ok(json!({
+        "type": "Feature",
+        "stac_version": STAC_VERSION,
+        "stac_extensions": STAC_EXTENSIONS,
+        "id": id,
+        "bbox": [min_x, min_y, max_x, max_y],
+        "geometry": footprint,
+        "properties": properties,
+        "links": [],
+        "assets": assets,
+    }))
+}
+
+fn data_type_name(gdal_type: GDALDataType::Type) -> &'static str {
+    match gdal_type {
+        GDALDataType::GDT_Byte => "uint8",
+        GDALDataType::GDT_Int16 => "int16",
+        GDALDataType::GDT_UInt16 => "uint16",
+        GDALDataType::GDT_Int32 => "int32",
+        GDALDataType::GDT_UInt32 => "uint32",
+        GDALDataType::GDT_Float32 => "float32",
+        GDALDataType::GDT_Float64 => "float64",
+        _ => "other",
+    }
+}
+
+fn nodata_value(value: f64) -> Value {
+    // stac encodes non-finite nodata values as strings
+    if value.is_nan() {
+        json!("nan")
+    } else if value.is_infinite() {
+        json!(if value > 0.0 { "inf" } else { "-inf" })
+    } else {
+        json!(value)
+    }
+}
+
+fn media_type(href: &str) -> Option<&'static str> {
+    let extension = href.rsplit('.').next()?.to_lowercase();
+    match extension.as_str() {
+        "tif" | "tiff" => Some("image/tiff; application=geotiff"),
+        "json" => Some("application/json"),
+        "parquet" => Some("application/vnd.apache.parquet"),
+        "bin" => Some("application/octet-stream"),
+        _ => None,
+    }
+}
+
+fn format_datetime(timestamp: i64) -> String {
+    // convert days since the unix epoch to a civil date
+    let days = timestamp.div_euclid(86400);
+    let seconds = timestamp.rem_euclid(86400);
+
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day,
+        seconds / 3600, (seconds / 60) % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn format_datetime() {
+        assert_eq!(super::format_datetime(0), "1970-01-01T00:00:00Z");
+        assert_eq!(super::format_datetime(951782400),
+            "2000-02-29T00:00:00Z");
+        assert_eq!(super::format_datetime(1700000000),
+            "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn stac_item() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let item = super::stac_item(&dataset, "h10v04",
+            &[("data", "h10v04.tif")], 0).expect("stac item");
+        assert_eq!(item["id"], "h10v04");
+        assert_eq!(item["properties"]["datetime"], "1970-01-01T00:00:00Z");
+
+        // the footprint is a closed ring within the bbox
+        let ring = item["geometry"]["coordinates"][0].as_array()
+            .expect("footprint ring");
+        assert_eq!(ring.len(), 81);
+        assert_eq!(ring.first(), ring.last());
+        let bbox: Vec<f64> = item["bbox"].as_array().expect("bbox").iter()
+            .map(|x| x.as_f64().expect("bbox value")).collect();
+        for point in ring.iter() {
+            let (x, y) = (point[0].as_f64().expect("x"),
+                point[1].as_f64().expect("y"));
+            assert!(x >= bbox[0] && x <= bbox[2]);
+            assert!(y >= bbox[1] && y <= bbox[3]);
+        }
+
+        let raster_band = &item["assets"]["data"]["raster:bands"][0];
+        assert_eq!(raster_band["scale"], 3.0);
+        assert_eq!(raster_band["offset"], 0.0);
+        assert_eq!(item["properties"]["proj:shape"][0], 256);
+        assert_eq!(item["bbox"].as_array().map(|x| x.len()), Some(4));
+        assert_eq!(item["assets"]["data"]["type"],
+            "image/tiff; application=geotiff");
+        assert_eq!(item["assets"]["data"]["eo:bands"].as_array()
+            .map(|x| x.len()), Some(dataset.raster_count() as usize));
+    }
+}
@@ -0,0 +1,303 @@
+use gdal::{Dataset, Driver};
+use gdal_sys::GDALDataType;
+
+use crate::transform::Chip;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Clone, Debug)]
+pub struct ChipOptions {
+    /// chip width and height in pixels
+    pub chip_size: usize,
+    /// pixels between consecutive chip origins
+    pub stride: usize,
+    /// minimum fraction of valid image pixels for a chip to be emitted
+    pub min_coverage: f64,
+    /// numeric feature field burned into labels, otherwise every
+    /// feature burns 1
+    pub label_field: Option<String>,
+    /// skip chips whose labels contain no features
+    pub skip_empty: bool,
+}
+
+impl Default for ChipOptions {
+    fn default() -> Self {
+        ChipOptions {
+            chip_size: 256,
+            stride: 256,
+            min_coverage: 0.0,
+            label_field: None,
+            skip_empty: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChipFormat {
+    /// geotiff image and label files
+    GTiff,
+    /// files in the crate serialization format
+    Serialized,
+}
+
+pub struct LabeledChip {
+    /// (x, y) pixel offset of the chip within the source dataset
+    pub offset: (isize, isize),
+    pub image: Dataset,
+    /// UInt16 label raster, 0 where no feature is present
+    pub label: Dataset,
+}
+
+impl LabeledChip {
+    pub fn to_payloads(&self) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>> {
+        let (mut image, mut label) = (Vec::new(), Vec::new());
+        crate::serialize::write(&self.image, &mut image)?;
+        crate::serialize::write(&self.label, &mut label)?;
+        Ok((image, label))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde",
+    derive(serde::Serialize, serde::Deserialize))]
+pub struct ManifestEntry {
+    pub id: String,
+    /// (x, y) pixel offset of the chip within the source dataset
+    pub offset: (isize, isize),
+    /// (min_x, max_x, min_y, max_y) in the dataset projection
+    pub bounds: (f64, f64, f64, f64),
+    /// image filename relative to the output directory
+    pub image: String,
+    /// label filename relative to the output directory
+    pub label: String,
+}
+
+pub fn rasterize_labels(dataset: &Dataset, features: &Dataset,
+        layer_index: isize, label_field: Option<&str>)
+        -> Result<Dataset, Box<dyn Error>> {
+    let geometries = crate::transform::layer_geometries(features,
+        layer_index, label_field, &dataset.projection())?;
+
+    // burn label field values, otherwise 1 for every feature
+    let mut burn_values = Vec::new();
+    for (value, _) in geometries.iter() {
+        burn_values.push(match label_field {
+            Some(field) => value.trim().parse::<f64>().map_err(|_|
+                format!("label field '{}' value '{}' is not numeric",
+                    field, value))?,
+            None => 1.0,
+        });
+    }
+
+    let handles: Vec<_> = geometries.iter().map(|x| (x.1).0).collect();
+    crate::mask::rasterize(dataset, &handles,
+        &burn_values, GDALDataType::GDT_UInt16)
+}
+
+pub fn generate(dataset: &Dataset, features: &Dataset, layer_index: isize,
+        options: &ChipOptions) -> Result<Vec<LabeledChip>, Box<dyn Error>> {
+    let label_dataset = rasterize_labels(dataset, features,
+        layer_index, options.label_field.as_deref())?;
+
+    let chips = crate::transform::split_chips(dataset, options.chip_size,
+        options.stride, options.min_coverage)?;
+
+    // copy label rasters covering each image chip
+    let size = (options.chip_size, options.chip_size);
+    let mut labeled_chips = Vec::new();
+    for Chip { offset, dataset: image } in chips.into_iter() {
+        let label = crate::DatasetBuilder::new(size.0, size.1)
+            .gdal_type(GDALDataType::GDT_UInt16)
+            .fill(false).like(&image)?.create_mem()?;
+        crate::copy_raster(&label_dataset, 1, offset, size,
+            &label, 1, (0, 0), size)?;
+
+        if options.skip_empty && label.rasterband(1)?
+                .read_band_as::<u16>()?.data.iter().all(|x| *x == 0) {
+            continue;
+        }
+
+        labeled_chips.push(LabeledChip { offset, image, label });
+    }
+
+    Ok(labeled_chips)
+}
+
+pub fn write_chips(chips: &[LabeledChip], directory: &Path,
+        format: ChipFormat) -> Result<Vec<ManifestEntry>, Box<dyn Error>> {
+    std::fs::create_dir_all(directory)?;
+    let driver = Driver::get("GTiff")?;
+    let extension = match format {
+        ChipFormat::GTiff => "tif",
+        ChipFormat::Serialized => "bin",
+    };
+
+    let mut manifest = Vec::new();
+    for chip in chips.iter() {
+        let id = format!("{}_{}", chip.offset.0, chip.offset.1);
+        let image = format!("{}_image.{}", id, extension);
+        let label = format!("{}_label.{}", id, extension);
+
+        for (dataset, filename) in
+                [(&chip.image, &image), (&chip.label, &label)].iter() {
+            let path = directory.join(filename);
+            match format {
+                ChipFormat::GTiff => {
+                    dataset.create_copy(&driver,
+                        path.to_str().ok_or("invalid chip path")?)?;
+                },
+                ChipFormat::Serialized => {
+                    let mut writer = BufWriter::new(File::create(&path)?);
+                    crate::serialize::write(dataset, &mut writer)?;
+                    writer.flush()?;
+                },
+            }
+        }
+
+        manifest.push(ManifestEntry {
+            id,
+            offset: chip.offset,
+            bounds: chip_bounds(&chip.image)?,
+            image,
+            label,
+        });
+    }
+
+    // write manifest alongside the chips
+    let path = directory.join("manifest.csv");
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "id,x,y,min_x,max_x,min_y,max_y,image,label")?;
+    for entry in manifest.iter() {
+        let (min_x, max_x, min_y, max_y) = entry.bounds;
+        writeln!(writer, "{},{},{},{},{},{},{},{},{}", entry.id,
+            entry.offset.0, entry.offset.1, min_x, max_x, min_y, max_y,
+            entry.image, entry.label)?;
+    }
+
+    writer.flush()?;
+    Ok(manifest)
+}
+
+fn chip_bounds(dataset: &Dataset)
+        -> Result<(f64, f64, f64, f64), Box<dyn Error>> {
+    let transform = dataset.geo_transform()?;
+    let (width, height) = dataset.raster_size();
+
+    // compute extent of the georeferenced corners
+    let (mut min_x, mut max_x) = (f64::MAX, f64::MIN);
+    let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+    for (px, py) in [(0, 0), (width, 0), (0, height), (width, height)]
+            .iter() {
+        let (px, py) = (*px as f64, *py as f64);
+        let x = transform[0] + px * transform[1] + py * transform[2];
+        let y = transform[3] + px * transform[4] + py * transform[5];
+
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+
+    Ok((min_x, max_x, min_y, max_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn chip_bounds() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let chips = crate::transform::split_chips(&dataset, 128, 128, 0.0)
+            .expect("split chips");
+        assert_eq!(chips.len(), 4);
+
+        let transform = dataset.geo_transform().expect("get transform");
+        let bounds = super::chip_bounds(&chips[1].dataset)
+            .expect("chip bounds");
+        assert_eq!(chips[1].offset, (128, 0));
+        assert!((bounds.0 - (transform[0] + 128.0 * transform[1])).abs()
+            < 1e-6);
+        assert!((bounds.1 - (transform[0] + 256.0 * transform[1])).abs()
+            < 1e-6);
+        assert!((bounds.3 - transform[3]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn generate() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // labels within the first and last of four chips
+        let polygons: Vec<String> = [(10, 50, 20, 60), (140, 200, 150, 220)]
+            .iter().map(|x| crate::testing::pixel_polygon(&dataset, *x)
+                .expect("pixel polygon")).collect();
+        let features = crate::testing::feature_layer(&dataset.projection(),
+                &[("3", &polygons[0][..]), ("7", &polygons[1][..])])
+            .expect("feature layer");
+
+        let mut options = super::ChipOptions {
+            chip_size: 128,
+            stride: 128,
+            label_field: Some("id".to_string()),
+            ..Default::default()
+        };
+        let chips = super::generate(&dataset, &features, 0, &options)
+            .expect("generate chips");
+        assert_eq!(chips.len(), 4);
+
+        options.skip_empty = true;
+        let chips = super::generate(&dataset, &features, 0, &options)
+            .expect("generate chips");
+        let offsets: Vec<_> = chips.iter().map(|x| x.offset).collect();
+        assert_eq!(offsets, vec![(0, 0), (128, 128)]);
+
+        // labels align with the chip image pixels
+        let label = chips[1].label.rasterband(1).expect("get rasterband")
+            .read_band_as::<u16>().expect("read label").data;
+        assert_eq!(label[(150 - 128) * 128 + (140 - 128)], 7);
+        assert_eq!(label[(219 - 128) * 128 + (199 - 128)], 7);
+        assert_eq!(label[(149 - 128) * 128 + (140 - 128)], 0);
+        assert_eq!(label[(150 - 128) * 128 + (200 - 128)], 0);
+        assert_eq!(label.iter().filter(|x| **x != 0).count(), 60 * 70);
+
+        let image = chips[1].image.rasterband(1).expect("get rasterband")
+            .read_band_as::<u8>().expect("read image").data;
+        let expected = dataset.rasterband(1).expect("get rasterband")
+            .read_as::<u8>((128, 128), (128, 128), (128, 128))
+            .expect("read window").data;
+        assert_eq!(image, expected);
+
+        // serialized chips and manifest round trip
+        let directory = std::env::temp_dir().join("satmod_chips_test");
+        let manifest = super::write_chips(&chips, &directory,
+            super::ChipFormat::Serialized).expect("write chips");
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest[1].id, "128_128");
+        assert_eq!(manifest[1].label, "128_128_label.bin");
+        assert_eq!(manifest[1].bounds, super::chip_bounds(&chips[1].image)
+            .expect("chip bounds"));
+
+        let mut file = std::fs::File::open(directory.join(&manifest[1].label))
+            .expect("open label");
+        let label_dataset = crate::serialize::read(&mut file)
+            .expect("read label");
+        assert_eq!(label_dataset.rasterband(1).expect("get rasterband")
+            .read_band_as::<u16>().expect("read label").data, label);
+
+        let csv = std::fs::read_to_string(directory.join("manifest.csv"))
+            .expect("read manifest");
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.lines().nth(2).expect("manifest entry")
+            .starts_with("128_128,128,128,"));
+
+        std::fs::remove_dir_all(&directory).expect("remove directory");
+    }
+}
@@ -0,0 +1,128 @@
+use gdal::{Dataset, Driver};
+use gdal::raster::{Buffer, GdalType};
+use gdal_sys::GDALDataType;
+
+use crate::FromPrimitive;
+
+use std::error::Error;
+
+pub fn median(datasets: &[Dataset]) -> Result<Dataset, Box<dyn Error>> {
+    quantile(datasets, 0.5)
+}
+
+pub fn percentile(datasets: &[Dataset], percentile: f64)
+        -> Result<Dataset, Box<dyn Error>> {
+    quantile(datasets, percentile / 100.0)
+}
+
+pub fn quantile(datasets: &[Dataset], quantile: f64)
+        -> Result<Dataset, Box<dyn Error>> {
+    if datasets.is_empty() {
+        return Err("no datasets to composite".into());
+    } else if !(0.0..=1.0).contains(&quantile) {
+        return Err(format!("quantile '{}' not in [0, 1]", quantile).into());
+    }
+
+    let rasterband = datasets[0].rasterband(1)?;
+    let no_data_value = rasterband.no_data_value();
+
+    match rasterband.band_type() {
+        GDALDataType::GDT_Byte =>
+            _quantile::<u8>(datasets, quantile, no_data_value),
+        GDALDataType::GDT_Int16 =>
+            _quantile::<i16>(datasets, quantile, no_data_value),
+        GDALDataType::GDT_UInt16 =>
+            _quantile::<u16>(datasets, quantile, no_data_value),
+        GDALDataType::GDT_Float32 =>
+            _quantile::<f32>(datasets, quantile, no_data_value),
+        _ => unimplemented!(),
+    }
+}
+
+fn _quantile<T: Copy + FromPrimitive + GdalType + PartialOrd>(
+        datasets: &[Dataset], quantile: f64, no_data_option: Option<f64>)
+        -> Result<Dataset, Box<dyn Error>> {
+    let no_data_value = T::from_f64(no_data_option.unwrap_or(0.0));
+    let dataset = &datasets[0];
+    let (width, height) = dataset.raster_size();
+
+    // validate datasets share dimensions
+    for composite_dataset in datasets.iter().skip(1) {
+        if composite_dataset.raster_size() != (width, height)
+                || composite_dataset.raster_count()
+                    != dataset.raster_count() {
+            return Err("composite datasets dimensions differ".into());
+        }
+    }
+
+    // open memory dataset
+    let driver = Driver::get("Mem")?;
+    let mem_dataset = crate::init_dataset_with_fill(&driver,
+        "unreachable", T::gdal_type(), width as isize, height as isize,
+        dataset.raster_count(), no_data_option, false)?;
+
+    mem_dataset.set_geo_transform(&dataset.geo_transform()?)?;
+    mem_dataset.set_projection(&dataset.projection())?;
+
+    // iterate over rasterbands
+    let mut values = Vec::with_capacity(datasets.len());
+    for i in 0..dataset.raster_count() {
+        // read rasterband from each dataset
+        let mut rasters = Vec::new();
+        for composite_dataset in datasets.iter() {
+            let raster = composite_dataset.rasterband(i+1)?
+                .read_band_as::<T>()?;
+            rasters.push(raster);
+        }
+
+        // select quantile of valid values for each pixel
+        let mut data = vec![no_data_value; width * height];
+        for (j, pixel) in data.iter_mut().enumerate() {
+            values.clear();
+            for raster in rasters.iter() {
+                if raster.data[j] != no_data_value {
+                    values.push(raster.data[j]);
+                }
+            }
+
+            if let Some(value) = select_quantile(&mut values, quantile) {
+                *pixel = value;
+            }
+        }
+
+        let buffer = Buffer::new((width, height), data);
+        mem_dataset.rasterband(i+1)?.write::<T>((0, 0),
+            (width, height), &buffer)?;
+    }
+
+    Ok(mem_dataset)
+}
+
+fn select_quantile<T: Copy + PartialOrd>(values: &mut [T],
+        quantile: f64) -> Option<T> {
+    if values.is_empty() {
+        return None;
+    }
+
+    // sort values and choose nearest rank
+    values.sort_by(|a, b| a.partial_cmp(b)
+        .unwrap_or(std::cmp::Ordering::Equal));
+    let index = (quantile * (values.len() - 1) as f64).round() as usize;
+
+    Some(values[index])
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn select_quantile() {
+        let mut values = vec![7u16, 3, 9, 1, 5];
+        assert_eq!(super::select_quantile(&mut values, 0.0), Some(1));
+        assert_eq!(super::select_quantile(&mut values, 0.25), Some(3));
+        assert_eq!(super::select_quantile(&mut values, 0.5), Some(5));
+        assert_eq!(super::select_quantile(&mut values, 1.0), Some(9));
+
+        let mut values: Vec<u16> = Vec::new();
+        assert_eq!(super::select_quantile(&mut values, 0.5), None);
+    }
+}
@@ -1,41 +1,880 @@
 use gdal::Dataset;
+use gdal::raster::Buffer;
 use gdal::spatial_ref::{CoordTransform, SpatialRef};
 
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
 use std::ffi::CStr;
+use std::path::Path;
 
 pub type WindowBounds = (Vec<f64>, Vec<f64>, Vec<f64>);
 
+static GEOHASH_BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+pub const WEB_MERCATOR_EXTENT: f64 = 20037508.342789244;
+
+// tile indices at larger zooms overflow u32
+pub const MAX_ZOOM: usize = 31;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Geocode {
+    Geohash,
+    QuadTile,
+}
+
+impl Geocode {
+    pub fn get_epsg_code(&self) -> u32 {
+        match self {
+            Geocode::Geohash => 4326,
+            Geocode::QuadTile => 3857,
+        }
+    }
+
+    pub fn get_extent(&self) -> (f64, f64, f64, f64) {
+        match self {
+            Geocode::Geohash => (-180.0, 180.0, -90.0, 90.0),
+            Geocode::QuadTile => (-WEB_MERCATOR_EXTENT, WEB_MERCATOR_EXTENT,
+                -WEB_MERCATOR_EXTENT, WEB_MERCATOR_EXTENT),
+        }
+    }
+
+    pub fn clamp_bounds(&self, bounds: (f64, f64, f64, f64))
+            -> (f64, f64, f64, f64) {
+        // cap bounds to the geocode extent, polar datasets may
+        // transform beyond valid latitudes
+        let (min_x, max_x, min_y, max_y) = self.get_extent();
+        (bounds.0.max(min_x), bounds.1.min(max_x),
+            bounds.2.max(min_y), bounds.3.min(max_y))
+    }
+
+    pub fn get_intervals(&self, precision: usize) -> (f64, f64) {
+        match self {
+            Geocode::Geohash => {
+                // longitude receives the extra bit on odd bit counts
+                let bits = precision * 5;
+                let x_bits = (bits + 1) / 2;
+                let y_bits = bits / 2;
+
+                (360.0 / (1u64 << x_bits) as f64,
+                    180.0 / (1u64 << y_bits) as f64)
+            },
+            Geocode::QuadTile => {
+                // precision is the zoom level of square tiles
+                let interval = 2.0 * WEB_MERCATOR_EXTENT
+                    / (1u64 << precision) as f64;
+                (interval, interval)
+            },
+        }
+    }
+
+    pub fn encode(&self, x: f64, y: f64, precision: usize)
+            -> Result<String, Box<dyn Error>> {
+        match self {
+            Geocode::Geohash => geohash_encode(x, y, precision),
+            Geocode::QuadTile => quadkey_encode(x, y, precision),
+        }
+    }
+
+    pub fn decode(&self, code: &str)
+            -> Result<(f64, f64, f64, f64), Box<dyn Error>> {
+        match self {
+            Geocode::Geohash => geohash_decode(code),
+            Geocode::QuadTile => quadkey_decode(code),
+        }
+    }
+
+    pub fn get_max_precision(&self) -> usize {
+        match self {
+            Geocode::Geohash => 12,
+            Geocode::QuadTile => 24,
+        }
+    }
+
+    pub fn check_precision(&self, precision: usize)
+            -> Result<(), Box<dyn Error>> {
+        // larger precisions overflow interval computations
+        match precision > self.get_max_precision() {
+            true => Err(format!("{:?} precision {} exceeds maximum {}",
+                self, precision, self.get_max_precision()).into()),
+            false => Ok(()),
+        }
+    }
+
+    pub fn cell_area_m2(&self, code: &str) -> Result<f64, Box<dyn Error>> {
+        let (min_x, max_x, min_y, max_y) = self.decode(code)?;
+        match self {
+            Geocode::Geohash =>
+                Ok(bounds_area_m2(min_x, max_x, min_y, max_y)),
+            Geocode::QuadTile => {
+                // invert spherical mercator to geographic bounds
+                let longitude = |x: f64|
+                    (x / WGS84_SEMI_MAJOR_AXIS).to_degrees();
+                let latitude = |y: f64| (2.0 * (y / WGS84_SEMI_MAJOR_AXIS)
+                    .exp().atan() - std::f64::consts::FRAC_PI_2).to_degrees();
+
+                Ok(bounds_area_m2(longitude(min_x), longitude(max_x),
+                    latitude(min_y), latitude(max_y)))
+            },
+        }
+    }
+
+    pub fn neighbors(&self, code: &str)
+            -> Result<Vec<String>, Box<dyn Error>> {
+        let (min_x, max_x, min_y, max_y) = self.decode(code)?;
+        let (extent_min_x, extent_max_x, extent_min_y, extent_max_y) =
+            self.get_extent();
+        let (mid_x, mid_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let (width, height) = (max_x - min_x, max_y - min_y);
+
+        // encode window centers offset in each direction
+        let mut neighbors = Vec::with_capacity(8);
+        for y_offset in [1.0, 0.0, -1.0].iter() {
+            for x_offset in [-1.0, 0.0, 1.0].iter() {
+                if *x_offset == 0.0 && *y_offset == 0.0 {
+                    continue;
+                }
+
+                // windows beyond the poles have no neighbor
+                let y = mid_y + y_offset * height;
+                if y < extent_min_y || y > extent_max_y {
+                    continue;
+                }
+
+                // wrap longitudes across the antimeridian
+                let extent_width = extent_max_x - extent_min_x;
+                let x = (mid_x + x_offset * width - extent_min_x)
+                    .rem_euclid(extent_width) + extent_min_x;
+
+                neighbors.push(self.encode(x, y, code.len())?);
+            }
+        }
+
+        Ok(neighbors)
+    }
+}
+
+fn geohash_encode(x: f64, y: f64, precision: usize)
+        -> Result<String, Box<dyn Error>> {
+    if x < -180.0 || x > 180.0 || y < -90.0 || y > 90.0 {
+        return Err(format!("coordinate ({}, {}) out of bounds", x, y).into());
+    }
+
+    let (mut min_x, mut max_x) = (-180.0, 180.0);
+    let (mut min_y, mut max_y) = (-90.0, 90.0);
+
+    // interleave longitude and latitude bits, starting with longitude
+    let mut geohash = String::with_capacity(precision);
+    let mut even = true;
+    for _ in 0..precision {
+        let mut index = 0;
+        for _ in 0..5 {
+            index <<= 1;
+            if even {
+                let mid_x = (min_x + max_x) / 2.0;
+                if x >= mid_x {
+                    index |= 1;
+                    min_x = mid_x;
+                } else {
+                    max_x = mid_x;
+                }
+            } else {
+                let mid_y = (min_y + max_y) / 2.0;
+                if y >= mid_y {
+                    index |= 1;
+                    min_y = mid_y;
+                } else {
+                    max_y = mid_y;
+                }
+            }
+
+            even = !even;
+        }
+
+        geohash.push(GEOHASH_BASE32[index] as char);
+    }
+
+    Ok(geohash)
+}
+
+fn geohash_decode(geohash: &str)
+        -> Result<(f64, f64, f64, f64), Box<dyn Error>> {
+    let (mut min_x, mut max_x) = (-180.0, 180.0);
+    let (mut min_y, mut max_y) = (-90.0, 90.0);
+
+    // narrow longitude and latitude ranges bit by bit
+    let mut even = true;
+    for c in geohash.bytes() {
+        let index = match GEOHASH_BASE32.iter().position(|x| *x == c) {
+            Some(index) => index,
+            None => return Err(format!("invalid geohash '{}'",
+                geohash).into()),
+        };
+
+        for i in (0..5).rev() {
+            let bit = (index >> i) & 1 == 1;
+            if even {
+                let mid_x = (min_x + max_x) / 2.0;
+                if bit {
+                    min_x = mid_x;
+                } else {
+                    max_x = mid_x;
+                }
+            } else {
+                let mid_y = (min_y + max_y) / 2.0;
+                if bit {
+                    min_y = mid_y;
+                } else {
+                    max_y = mid_y;
+                }
+            }
+
+            even = !even;
+        }
+    }
+
+    Ok((min_x, max_x, min_y, max_y))
+}
+
+pub fn quadkey(x: u32, y: u32, zoom: u32) -> String {
+    // interleave tile index bits from the most significant
+    let mut quadkey = String::with_capacity(zoom as usize);
+    for i in (0..zoom).rev() {
+        let mask = 1 << i;
+        let mut digit = b'0';
+        if x & mask != 0 {
+            digit += 1;
+        }
+
+        if y & mask != 0 {
+            digit += 2;
+        }
+
+        quadkey.push(digit as char);
+    }
+
+    quadkey
+}
+
+fn quadkey_encode(x: f64, y: f64, zoom: usize)
+        -> Result<String, Box<dyn Error>> {
+    if x < -WEB_MERCATOR_EXTENT || x > WEB_MERCATOR_EXTENT
+            || y < -WEB_MERCATOR_EXTENT || y > WEB_MERCATOR_EXTENT {
+        return Err(format!("coordinate ({}, {}) out of bounds", x, y).into());
+    } else if zoom > MAX_ZOOM {
+        return Err(format!("zoom {} too large", zoom).into());
+    }
+
+    // compute tile indices, clamping the maximum extent edges
+    let tile_count = 1u64 << zoom;
+    let tile_extent = 2.0 * WEB_MERCATOR_EXTENT / tile_count as f64;
+    let tile_x = (((x + WEB_MERCATOR_EXTENT) / tile_extent) as u64)
+        .min(tile_count - 1);
+    let tile_y = (((WEB_MERCATOR_EXTENT - y) / tile_extent) as u64)
+        .min(tile_count - 1);
+
+    Ok(quadkey(tile_x as u32, tile_y as u32, zoom as u32))
+}
+
+pub fn quadkey_decode(quadkey: &str)
+        -> Result<(f64, f64, f64, f64), Box<dyn Error>> {
+    if quadkey.len() > MAX_ZOOM {
+        return Err(format!("quadkey '{}' too long", quadkey).into());
+    }
+
+    // recover tile indices from quadkey digits
+    let (mut x, mut y) = (0u32, 0u32);
+    for c in quadkey.chars() {
+        let digit = match c.to_digit(4) {
+            Some(digit) => digit,
+            None => return Err(format!("invalid quadkey '{}'",
+                quadkey).into()),
+        };
+
+        x = (x << 1) | (digit & 1);
+        y = (y << 1) | (digit >> 1);
+    }
+
+    // compute web mercator bounds of the tile
+    let tile_extent = 2.0 * WEB_MERCATOR_EXTENT
+        / (1u64 << quadkey.len()) as f64;
+    let min_x = -WEB_MERCATOR_EXTENT + x as f64 * tile_extent;
+    let max_y = WEB_MERCATOR_EXTENT - y as f64 * tile_extent;
+
+    Ok((min_x, min_x + tile_extent, max_y - tile_extent, max_y))
+}
+
+pub fn compute_bounds(min_x: f64, max_x: f64, min_y: f64, max_y: f64,
+        geocode: Geocode, precision: usize)
+        -> Result<Vec<(String, (f64, f64, f64, f64))>, Box<dyn Error>> {
+    geocode.check_precision(precision)?;
+    let (min_x, max_x, min_y, max_y) =
+        geocode.clamp_bounds((min_x, max_x, min_y, max_y));
+    let (x_interval, y_interval) = geocode.get_intervals(precision);
+
+    // encode the center of each window intersecting the bounds
+    let mut codes = Vec::new();
+    for window in get_windows(min_x, max_x,
+            min_y, max_y, x_interval, y_interval) {
+        let code = geocode.encode((window.0 + window.1) / 2.0,
+            (window.2 + window.3) / 2.0, precision)?;
+        codes.push((code, window));
+    }
+
+    Ok(codes)
+}
+
+pub fn compute_covering(points: &[(f64, f64)], geocode: Geocode,
+        precision: usize) -> Result<Vec<String>, Box<dyn Error>> {
+    if points.len() < 3 {
+        return Err("polygon requires at least three points".into());
+    }
+
+    // compute polygon bounding box
+    let (mut min_x, mut max_x) = (f64::MAX, f64::MIN);
+    let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+    for (x, y) in points.iter() {
+        min_x = min_x.min(*x);
+        max_x = max_x.max(*x);
+        min_y = min_y.min(*y);
+        max_y = max_y.max(*y);
+    }
+
+    // retain bounding box geocodes which intersect the polygon
+    let mut codes = Vec::new();
+    for (code, bounds) in compute_bounds(min_x, max_x,
+            min_y, max_y, geocode, precision)? {
+        if polygon_intersects(points, bounds) {
+            codes.push(code);
+        }
+    }
+
+    Ok(codes)
+}
+
+fn polygon_intersects(points: &[(f64, f64)],
+        bounds: (f64, f64, f64, f64)) -> bool {
+    let (min_x, max_x, min_y, max_y) = bounds;
+    let corners = [(min_x, min_y), (max_x, min_y),
+        (max_x, max_y), (min_x, max_y)];
+
+    // check for containment in either direction
+    if points.iter().any(|(x, y)| *x >= min_x && *x <= max_x
+            && *y >= min_y && *y <= max_y) {
+        return true;
+    }
+
+    if corners.iter().any(|corner| point_in_polygon(*corner, points)) {
+        return true;
+    }
+
+    // check for crossing edges
+    for i in 0..points.len() {
+        let edge = (points[i], points[(i + 1) % points.len()]);
+        for j in 0..corners.len() {
+            if segments_intersect(edge,
+                    (corners[j], corners[(j + 1) % corners.len()])) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+fn point_in_polygon(point: (f64, f64), points: &[(f64, f64)]) -> bool {
+    // even-odd ray casting along the positive x axis
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let ((x1, y1), (x2, y2)) = (points[i], points[j]);
+        if (y1 > point.1) != (y2 > point.1)
+                && point.0 < (x2 - x1) * (point.1 - y1) / (y2 - y1) + x1 {
+            inside = !inside;
+        }
+
+        j = i;
+    }
+
+    inside
+}
+
+fn segments_intersect(a: ((f64, f64), (f64, f64)),
+        b: ((f64, f64), (f64, f64))) -> bool {
+    let orientation = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+
+    let d1 = orientation(b.0, b.1, a.0);
+    let d2 = orientation(b.0, b.1, a.1);
+    let d3 = orientation(a.0, a.1, b.0);
+    let d4 = orientation(a.0, a.1, b.1);
+
+    ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+}
+
+#[derive(Debug)]
+pub struct CompletenessReport {
+    pub expected: Vec<String>,
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+}
+
+pub fn completeness(min_x: f64, max_x: f64, min_y: f64, max_y: f64,
+        geocode: Geocode, precision: usize, present: &[String])
+        -> Result<CompletenessReport, Box<dyn Error>> {
+    // compute expected geocodes covering the bounds
+    let expected: BTreeSet<String> = compute_bounds(min_x, max_x,
+            min_y, max_y, geocode, precision)?
+        .into_iter().map(|(code, _)| code).collect();
+
+    // compare with present geocodes
+    let present: BTreeSet<String> = present.iter().cloned().collect();
+    let missing = expected.difference(&present).cloned().collect();
+    let extra = present.difference(&expected).cloned().collect();
+
+    Ok(CompletenessReport {
+        expected: expected.into_iter().collect(),
+        missing,
+        extra,
+    })
+}
+
+pub fn completeness_from_dir(min_x: f64, max_x: f64, min_y: f64,
+        max_y: f64, geocode: Geocode, precision: usize, directory: &Path)
+        -> Result<CompletenessReport, Box<dyn Error>> {
+    // identify geocodes from directory entry names
+    let mut present = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if let Some(stem) = path.file_stem().and_then(|x| x.to_str()) {
+            present.push(stem.to_string());
+        }
+    }
+
+    completeness(min_x, max_x, min_y, max_y, geocode, precision, &present)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Target {
+    Epsg(u32),
+    Proj4(String),
+    Wkt(String),
+}
+
+impl Target {
+    pub fn to_spatial_ref(&self) -> Result<SpatialRef, Box<dyn Error>> {
+        match self {
+            Target::Epsg(epsg_code) =>
+                Ok(SpatialRef::from_epsg(*epsg_code)?),
+            Target::Proj4(proj4) => Ok(SpatialRef::from_proj4(proj4)?),
+            Target::Wkt(wkt) => Ok(SpatialRef::from_wkt(wkt)?),
+        }
+    }
+}
+
+impl From<u32> for Target {
+    fn from(epsg_code: u32) -> Self {
+        Target::Epsg(epsg_code)
+    }
+}
+
+const EDGE_SAMPLES: usize = 21;
+
 pub fn get_bounds(dataset: &Dataset, epsg_code: u32)
         -> Result<(f64, f64, f64, f64), Box<dyn Error>> {
-    // initialize transform array and CoordTransform's from dataset
-    let (transform, _, src_spatial_ref, dst_spatial_ref) =
-        get_transform_refs(dataset, epsg_code)?;
-    let coord_transform = CoordTransform::new(
-        &src_spatial_ref, &dst_spatial_ref)?;
+    get_bounds_with_target(dataset, &Target::Epsg(epsg_code))
+}
+
+pub fn get_bounds_with_target(dataset: &Dataset, target: &Target)
+        -> Result<(f64, f64, f64, f64), Box<dyn Error>> {
+    get_bounds_with_density(dataset, target, EDGE_SAMPLES)
+}
+
+pub fn get_bounds_with_density(dataset: &Dataset, target: &Target,
+        edge_samples: usize) -> Result<(f64, f64, f64, f64), Box<dyn Error>> {
+    let (transform, projection) = get_transform_projection(dataset)?;
 
-    // compute minimum and maximum x and y coordinates
+    // sample pixels along each edge, curved reprojections may place
+    // extremes between the corners
     let (src_width, src_height) = dataset.raster_size();
-    let corner_pixels = vec![
-        (0, 0, 0),
-        (src_width as isize, 0, 0),
-        (0, src_height as isize, 0),
-        (src_width as isize, src_height as isize, 0)
-    ];
+    let (width, height) = (src_width as isize, src_height as isize);
+    let edge_samples = edge_samples.max(2) as isize;
+
+    let mut edge_pixels = Vec::new();
+    for i in 0..edge_samples {
+        let x = i * width / (edge_samples - 1);
+        let y = i * height / (edge_samples - 1);
+
+        edge_pixels.push((x, 0, 0));
+        edge_pixels.push((x, height, 0));
+        edge_pixels.push((0, y, 0));
+        edge_pixels.push((width, y, 0));
+    }
 
-    let (xs, ys, _) = transform_pixels(&corner_pixels,
-        &transform, &coord_transform)?;
+    let (xs, ys, _) = TransformCache::with(|cache| {
+        transform_pixels(&edge_pixels, &transform,
+            cache.get(&projection, target)?)
+    })?;
 
-    let min_cx = xs.iter().cloned().fold(1./0., f64::min);
-    let max_cx = xs.iter().cloned().fold(f64::NAN, f64::max);
-    let min_cy = ys.iter().cloned().fold(1./0., f64::min);
-    let max_cy = ys.iter().cloned().fold(f64::NAN, f64::max);
+    let mut min_cx = xs.iter().cloned().fold(1./0., f64::min);
+    let mut max_cx = xs.iter().cloned().fold(f64::NAN, f64::max);
+    let mut min_cy = ys.iter().cloned().fold(1./0., f64::min);
+    let mut max_cy = ys.iter().cloned().fold(f64::NAN, f64::max);
+
+    // edges of datasets containing a pole don't bound it, extend
+    // geographic bounds over every longitude
+    if *target == Target::Epsg(4326) {
+        let (src_spatial_ref, dst_spatial_ref) =
+            get_spatial_refs(&projection, target)?;
+        let reverse_transform = CoordTransform::new(
+            &dst_spatial_ref, &src_spatial_ref)?;
+        for latitude in [90.0, -90.0].iter() {
+            let (x, y, _) = match transform_coord(0.0,
+                    *latitude, 0.0, &reverse_transform) {
+                Ok(coordinate) => coordinate,
+                Err(_) => continue,
+            };
+
+            let (px, py) = coord_to_pixel(x, y, &transform)?;
+            if px >= 0.0 && px <= src_width as f64
+                    && py >= 0.0 && py <= src_height as f64 {
+                min_cx = -180.0;
+                max_cx = 180.0;
+                min_cy = min_cy.min(*latitude);
+                max_cy = max_cy.max(*latitude);
+            }
+        }
+    }
 
     Ok((min_cx, max_cx, min_cy, max_cy))
 }
 
+pub fn get_outline(dataset: &Dataset, target: &Target)
+        -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
+    let (transform, projection) = get_transform_projection(dataset)?;
+
+    // walk the pixel boundary counter-clockwise from the origin, sampling
+    // each edge so curved reprojections are followed
+    let (src_width, src_height) = dataset.raster_size();
+    let (width, height) = (src_width as isize, src_height as isize);
+    let samples = EDGE_SAMPLES as isize - 1;
+
+    let mut pixels = Vec::new();
+    for i in 0..samples {
+        pixels.push((0, i * height / samples, 0));
+    }
+    for i in 0..samples {
+        pixels.push((i * width / samples, height, 0));
+    }
+    for i in 0..samples {
+        pixels.push((width, height - i * height / samples, 0));
+    }
+    for i in 0..samples {
+        pixels.push((width - i * width / samples, 0, 0));
+    }
+    pixels.push((0, 0, 0));
+
+    let (xs, ys, _) = TransformCache::with(|cache| {
+        transform_pixels(&pixels, &transform,
+            cache.get(&projection, target)?)
+    })?;
+
+    Ok(xs.into_iter().zip(ys.into_iter()).collect())
+}
+
+fn transform_pixel_centers<F>(dataset: &Dataset, epsg_code: u32,
+        mut callback: F) -> Result<(), Box<dyn Error>>
+        where F: FnMut(&[f64], &[f64]) -> Result<(), Box<dyn Error>> {
+    let (transform, projection) = get_transform_projection(dataset)?;
+
+    // transform pixel centers a row at a time
+    let (width, height) = dataset.raster_size();
+    let (mut xs, mut ys, mut zs) = (Vec::with_capacity(width),
+        Vec::with_capacity(width), vec![0.0; width]);
+    for y in 0..height {
+        let y_pixel = y as f64 + 0.5;
+        xs.clear();
+        ys.clear();
+        for x in 0..width {
+            let x_pixel = x as f64 + 0.5;
+            xs.push(transform[0] + x_pixel * transform[1]
+                + y_pixel * transform[2]);
+            ys.push(transform[3] + x_pixel * transform[4]
+                + y_pixel * transform[5]);
+        }
+
+        TransformCache::with(|cache| -> Result<(), Box<dyn Error>> {
+            cache.get(&projection, &Target::Epsg(epsg_code))?
+                .transform_coords(&mut xs, &mut ys, &mut zs)?;
+            Ok(())
+        })?;
+
+        callback(&xs, &ys)?;
+    }
+
+    Ok(())
+}
+
+pub fn pixel_geocodes(dataset: &Dataset, geocode: Geocode,
+        precision: usize) -> Result<Vec<String>, Box<dyn Error>> {
+    let (width, height) = dataset.raster_size();
+    let mut geocodes = Vec::with_capacity(width * height);
+    transform_pixel_centers(dataset, geocode.get_epsg_code(), |xs, ys| {
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            geocodes.push(geocode.encode(*x, *y, precision)?);
+        }
+
+        Ok(())
+    })?;
+
+    Ok(geocodes)
+}
+
+pub fn geocode_mask(dataset: &Dataset, geocode: Geocode, code: &str)
+        -> Result<Dataset, Box<dyn Error>> {
+    let (min_cx, max_cx, min_cy, max_cy) = geocode.decode(code)?;
+
+    // flag pixel centers within the geocode cell
+    let (width, height) = dataset.raster_size();
+    let mut mask = Vec::with_capacity(width * height);
+    transform_pixel_centers(dataset, geocode.get_epsg_code(), |xs, ys| {
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let inside = *x >= min_cx && *x < max_cx
+                && *y >= min_cy && *y < max_cy;
+            mask.push(inside as u8);
+        }
+
+        Ok(())
+    })?;
+
+    // write mask into a byte dataset
+    let (transform, projection) = get_transform_projection(dataset)?;
+    let mask_dataset = crate::DatasetBuilder::new(width, height)
+        .fill(false).projection(&projection).geo_transform(transform)
+        .create_mem()?;
+
+    let buffer = Buffer::new((width, height), mask);
+    mask_dataset.rasterband(1)?
+        .write::<u8>((0, 0), (width, height), &buffer)?;
+
+    Ok(mask_dataset)
+}
+
+#[derive(Clone, Debug)]
+pub struct Crs {
+    /// identified epsg code, if any
+    pub epsg_code: Option<u32>,
+    /// wkt regenerated from the epsg code where identified
+    pub wkt: String,
+}
+
+impl PartialEq for Crs {
+    fn eq(&self, other: &Crs) -> bool {
+        match (self.epsg_code, other.epsg_code) {
+            (Some(a), Some(b)) => a == b,
+            // compare definitions rather than their wkt formatting
+            _ => match (SpatialRef::from_wkt(&self.wkt),
+                    SpatialRef::from_wkt(&other.wkt)) {
+                (Ok(a), Ok(b)) => unsafe {
+                    gdal_sys::OSRIsSame(a.to_c_hsrs(), b.to_c_hsrs()) == 1
+                },
+                _ => self.wkt == other.wkt,
+            },
+        }
+    }
+}
+
+pub fn identify_crs(projection: &str) -> Result<Crs, Box<dyn Error>> {
+    let spatial_ref = SpatialRef::from_wkt(projection)?;
+
+    // attempt to identify an epsg authority code
+    let epsg_code = unsafe {
+        let c_spatial_ref = spatial_ref.to_c_hsrs();
+        gdal_sys::OSRAutoIdentifyEPSG(c_spatial_ref);
+
+        let c_name = gdal_sys::OSRGetAuthorityName(c_spatial_ref,
+            std::ptr::null());
+        let c_code = gdal_sys::OSRGetAuthorityCode(c_spatial_ref,
+            std::ptr::null());
+        if c_name.is_null() || c_code.is_null()
+                || CStr::from_ptr(c_name).to_string_lossy() != "EPSG" {
+            None
+        } else {
+            CStr::from_ptr(c_code).to_string_lossy().parse::<u32>().ok()
+        }
+    };
+
+    let wkt = match epsg_code {
+        Some(epsg_code) => SpatialRef::from_epsg(epsg_code)?.to_wkt()?,
+        None => spatial_ref.to_wkt()?,
+    };
+
+    Ok(Crs { epsg_code, wkt })
+}
+
+pub(crate) const WGS84_SEMI_MAJOR_AXIS: f64 = 6378137.0;
+pub(crate) const WGS84_FLATTENING: f64 = 1.0 / 298.257223563;
+
+pub fn area_m2(dataset: &Dataset) -> Result<f64, Box<dyn Error>> {
+    let (transform, projection) = get_transform_projection(dataset)?;
+
+    // trace the dataset boundary clockwise from the origin
+    let (width, height) = dataset.raster_size();
+    let (width, height) = (width as isize, height as isize);
+    let samples = EDGE_SAMPLES as isize - 1;
+
+    let mut boundary_pixels = Vec::new();
+    for i in 0..samples {
+        boundary_pixels.push((i * width / samples, 0, 0));
+    }
+    for i in 0..samples {
+        boundary_pixels.push((width, i * height / samples, 0));
+    }
+    for i in (1..=samples).rev() {
+        boundary_pixels.push((i * width / samples, height, 0));
+    }
+    for i in (1..=samples).rev() {
+        boundary_pixels.push((0, i * height / samples, 0));
+    }
+
+    let (xs, ys, _) = TransformCache::with(|cache| {
+        transform_pixels(&boundary_pixels, &transform,
+            cache.get(&projection, &Target::Epsg(4326))?)
+    })?;
+
+    let coordinates: Vec<(f64, f64)> = xs.into_iter()
+        .zip(ys.into_iter()).collect();
+    Ok(polygon_area_m2(&coordinates))
+}
+
+pub fn bounds_area_m2(min_x: f64, max_x: f64, min_y: f64, max_y: f64)
+        -> f64 {
+    (max_x - min_x).to_radians().abs()
+        * (authalic_y(max_y) - authalic_y(min_y)).abs()
+}
+
+pub fn polygon_area_m2(coordinates: &[(f64, f64)]) -> f64 {
+    // shoelace area in the ellipsoidal cylindrical equal-area projection,
+    // edges are treated as straight lines in that projection
+    let mut sum = 0.0;
+    for i in 0..coordinates.len() {
+        let (x1, y1) = coordinates[i];
+        let (x2, y2) = coordinates[(i + 1) % coordinates.len()];
+        sum += (x2 - x1).to_radians() * (authalic_y(y1) + authalic_y(y2));
+    }
+
+    (sum / 2.0).abs()
+}
+
+pub fn resolution_m(dataset: &Dataset)
+        -> Result<(f64, f64), Box<dyn Error>> {
+    let (transform, projection) = get_transform_projection(dataset)?;
+    let spatial_ref = SpatialRef::from_wkt(&projection)?;
+
+    // pixel sizes along each axis in srs units
+    let x_size = transform[1].hypot(transform[4]);
+    let y_size = transform[2].hypot(transform[5]);
+
+    let geographic = unsafe {
+        gdal_sys::OSRIsGeographic(spatial_ref.to_c_hsrs()) != 0
+    };
+
+    if !geographic {
+        let meters = unsafe {
+            gdal_sys::OSRGetLinearUnits(spatial_ref.to_c_hsrs(),
+                std::ptr::null_mut())
+        };
+
+        return Ok((x_size * meters, y_size * meters));
+    }
+
+    // scale degrees by the ellipsoid radii of curvature at the center
+    let (width, height) = dataset.raster_size();
+    let latitude = (transform[3] + width as f64 / 2.0 * transform[4]
+        + height as f64 / 2.0 * transform[5]).to_radians();
+
+    let e2 = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+    let w = 1.0 - e2 * latitude.sin().powi(2);
+    let meridional = WGS84_SEMI_MAJOR_AXIS * (1.0 - e2) / w.powf(1.5);
+    let prime_vertical = WGS84_SEMI_MAJOR_AXIS / w.sqrt();
+
+    Ok((prime_vertical * latitude.cos() * x_size.to_radians(),
+        meridional * y_size.to_radians()))
+}
+
+pub fn select_precision(dataset: &Dataset, geocode: Geocode,
+        target_pixels: f64) -> Result<usize, Box<dyn Error>> {
+    if target_pixels <= 0.0 {
+        return Err("target pixel count must be positive".into());
+    }
+
+    let (x_resolution, y_resolution) = resolution_m(dataset)?;
+    let pixel_area = x_resolution * y_resolution;
+
+    // sample geocode cells at the dataset center
+    let (min_x, max_x, min_y, max_y) = geocode.clamp_bounds(
+        get_bounds(dataset, geocode.get_epsg_code())?);
+    let (mid_x, mid_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+
+    // choose the precision nearest the target on a log scale
+    let mut precision = 1;
+    let mut min_difference = f64::MAX;
+    for i in 1..=geocode.get_max_precision() {
+        let code = geocode.encode(mid_x, mid_y, i)?;
+        let pixels = geocode.cell_area_m2(&code)? / pixel_area;
+
+        let difference = (pixels / target_pixels).ln().abs();
+        if difference < min_difference {
+            precision = i;
+            min_difference = difference;
+        }
+    }
+
+    Ok(precision)
+}
+
+pub fn select_windows(dataset: &Dataset, geocode: Geocode,
+        target_pixels: f64) -> Result<(usize, Vec<(f64, f64, f64, f64)>),
+            Box<dyn Error>> {
+    let precision = select_precision(dataset, geocode, target_pixels)?;
+
+    // compute windows at the selected precision
+    let (min_x, max_x, min_y, max_y) = geocode.clamp_bounds(
+        get_bounds(dataset, geocode.get_epsg_code())?);
+    let (x_interval, y_interval) = geocode.get_intervals(precision);
+
+    Ok((precision, get_windows(min_x, max_x,
+        min_y, max_y, x_interval, y_interval)))
+}
+
+fn authalic_y(latitude: f64) -> f64 {
+    // integral of the ellipsoid area element from the equator
+    let b = WGS84_SEMI_MAJOR_AXIS * (1.0 - WGS84_FLATTENING);
+    let e = (WGS84_FLATTENING * (2.0 - WGS84_FLATTENING)).sqrt();
+    let sin = latitude.to_radians().sin();
+
+    b * b * (sin / (2.0 * (1.0 - e * e * sin * sin))
+        + ((1.0 + e * sin) / (1.0 - e * sin)).ln() / (4.0 * e))
+}
+
 pub fn get_transform_refs(dataset: &Dataset, epsg_code: u32) 
         -> Result<([f64; 6], String, SpatialRef, SpatialRef), Box<dyn Error>> {
+    get_transform_refs_with_target(dataset, &Target::Epsg(epsg_code))
+}
+
+pub fn get_transform_refs_with_target(dataset: &Dataset, target: &Target)
+        -> Result<([f64; 6], String, SpatialRef, SpatialRef), Box<dyn Error>> {
+    let (transform, projection) = get_transform_projection(dataset)?;
+    let (src_spatial_ref, dst_spatial_ref) =
+        get_spatial_refs(&projection, target)?;
+
+    Ok((transform, projection, src_spatial_ref, dst_spatial_ref))
+}
+
+pub fn get_transform_projection(dataset: &Dataset)
+        -> Result<([f64; 6], String), Box<dyn Error>> {
     // identify transform array and projection from dataset
     let (transform, projection) = match dataset.geo_transform() {
         Ok(transform) => (transform, dataset.projection()),
@@ -70,9 +909,13 @@ pub fn get_transform_refs(dataset: &Dataset, epsg_code: u32)
         },
     };
 
-    // initialize transform array and CoordTransform's from dataset
-    let src_spatial_ref = SpatialRef::from_wkt(&projection)?;
-    let dst_spatial_ref = SpatialRef::from_epsg(epsg_code)?;
+    Ok((transform, projection))
+}
+
+pub fn get_spatial_refs(projection: &str, target: &Target)
+        -> Result<(SpatialRef, SpatialRef), Box<dyn Error>> {
+    let src_spatial_ref = SpatialRef::from_wkt(projection)?;
+    let dst_spatial_ref = target.to_spatial_ref()?;
 
     #[cfg(major_ge_3)]
     {
@@ -83,42 +926,155 @@ pub fn get_transform_refs(dataset: &Dataset, epsg_code: u32)
             OSRAxisMappingStrategy::OAMS_TRADITIONAL_GIS_ORDER);
     }
 
-    Ok((transform, projection, src_spatial_ref, dst_spatial_ref))
+    Ok((src_spatial_ref, dst_spatial_ref))
+}
+
+thread_local! {
+    static TRANSFORM_CACHE: RefCell<TransformCache> =
+        RefCell::new(TransformCache::new());
+}
+
+// CoordTransforms are not thread-safe, so each thread reuses its own
+pub struct TransformCache {
+    transforms: HashMap<(String, Target), CoordTransform>,
+}
+
+impl TransformCache {
+    pub fn new() -> TransformCache {
+        TransformCache {
+            transforms: HashMap::new(),
+        }
+    }
+
+    pub fn with<F, R>(f: F) -> R
+            where F: FnOnce(&mut TransformCache) -> R {
+        TRANSFORM_CACHE.with(|cache| f(&mut cache.borrow_mut()))
+    }
+
+    pub fn get(&mut self, projection: &str, target: &Target)
+            -> Result<&CoordTransform, Box<dyn Error>> {
+        let key = (projection.to_string(), target.clone());
+        if !self.transforms.contains_key(&key) {
+            let (src_spatial_ref, dst_spatial_ref) =
+                get_spatial_refs(projection, target)?;
+            let coord_transform = CoordTransform::new(
+                &src_spatial_ref, &dst_spatial_ref)?;
+            self.transforms.insert(key.clone(), coord_transform);
+        }
+
+        Ok(&self.transforms[&key])
+    }
+
+    pub fn clear(&mut self) {
+        self.transforms.clear();
+    }
+}
+
+impl Default for TransformCache {
+    fn default() -> Self {
+        TransformCache::new()
+    }
+}
+
+pub fn snap_to_grid(bounds: (f64, f64, f64, f64), origin: (f64, f64),
+        pixel_size: (f64, f64)) -> (f64, f64, f64, f64) {
+    // expand bounds outward to the nearest grid lines
+    let (x_size, y_size) = (pixel_size.0.abs(), pixel_size.1.abs());
+    let snap = |value: f64, origin: f64, size: f64,
+            round: fn(f64) -> f64| {
+        origin + round((value - origin) / size) * size
+    };
+
+    (snap(bounds.0, origin.0, x_size, f64::floor),
+        snap(bounds.1, origin.0, x_size, f64::ceil),
+        snap(bounds.2, origin.1, y_size, f64::floor),
+        snap(bounds.3, origin.1, y_size, f64::ceil))
 }
 
 pub fn get_windows(min_x: f64, max_x: f64, min_y: f64, max_y: f64,
         x_interval: f64, y_interval: f64) -> Vec<(f64, f64, f64, f64)> {
+    get_windows_with_options(min_x, max_x, min_y, max_y,
+        x_interval, y_interval, false)
+}
+
+pub fn get_windows_with_options(min_x: f64, max_x: f64, min_y: f64,
+        max_y: f64, x_interval: f64, y_interval: f64, clip: bool)
+        -> Vec<(f64, f64, f64, f64)> {
+    get_windows_iter(min_x, max_x, min_y, max_y,
+        x_interval, y_interval, clip).collect()
+}
+
+pub fn get_windows_iter(min_x: f64, max_x: f64, min_y: f64, max_y: f64,
+        x_interval: f64, y_interval: f64, clip: bool) -> WindowIter {
     // compute indices for minimum and maximum coordinates
-    let min_x_index = (min_x / x_interval).floor() as i32;
-    let max_x_index = (max_x / x_interval).ceil() as i32;
+    let min_x_index = (min_x / x_interval).floor() as i64;
+    let max_x_index = (max_x / x_interval).ceil() as i64;
 
-    let min_y_index = (min_y / y_interval).floor() as i32;
-    let max_y_index = (max_y / y_interval).ceil() as i32;
+    let min_y_index = (min_y / y_interval).floor() as i64;
+    let max_y_index = (max_y / y_interval).ceil() as i64;
 
-    // compute all window bounds
-    let mut window_bounds = Vec::new();
-    for x_index in min_x_index..max_x_index {
-        let x_index = x_index as f64;
+    WindowIter {
+        bounds: (min_x, max_x, min_y, max_y),
+        intervals: (x_interval, y_interval),
+        min_indices: (min_x_index, min_y_index),
+        x_count: (max_x_index - min_x_index).max(0) as usize,
+        y_count: (max_y_index - min_y_index).max(0) as usize,
+        index: 0,
+        clip,
+    }
+}
+
+pub struct WindowIter {
+    bounds: (f64, f64, f64, f64),
+    intervals: (f64, f64),
+    min_indices: (i64, i64),
+    x_count: usize,
+    y_count: usize,
+    index: usize,
+    clip: bool,
+}
+
+impl Iterator for WindowIter {
+    type Item = (f64, f64, f64, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.x_count * self.y_count {
+            return None;
+        }
 
-        for y_index in min_y_index..max_y_index {
-            let y_index = y_index as f64;
+        // iterate over y indices within each x index
+        let x_index = (self.min_indices.0
+            + (self.index / self.y_count) as i64) as f64;
+        let y_index = (self.min_indices.1
+            + (self.index % self.y_count) as i64) as f64;
+        self.index += 1;
 
-            // compute window x and y bounds
-            let window_x_min = x_index * x_interval;
-            let window_x_max = (x_index + 1.0) * x_interval;
+        // compute window x and y bounds
+        let (x_interval, y_interval) = self.intervals;
+        let window_x_min = x_index * x_interval;
+        let window_x_max = (x_index + 1.0) * x_interval;
 
-            let window_y_min = y_index * y_interval;
-            let window_y_max = (y_index + 1.0) * y_interval;
+        let window_y_min = y_index * y_interval;
+        let window_y_max = (y_index + 1.0) * y_interval;
 
-            // add to window bounds
-            window_bounds.push((window_x_min,
-                window_x_max, window_y_min, window_y_max));
+        // intersect window with the extent if clipping
+        let (min_x, max_x, min_y, max_y) = self.bounds;
+        match self.clip {
+            true => Some((window_x_min.max(min_x), window_x_max.min(max_x),
+                window_y_min.max(min_y), window_y_max.min(max_y))),
+            false => Some((window_x_min, window_x_max,
+                window_y_min, window_y_max)),
         }
     }
 
-    window_bounds
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.x_count * self.y_count - self.index;
+        (remaining, Some(remaining))
+    }
 }
 
+impl ExactSizeIterator for WindowIter {}
+
 pub fn transform_pixel(x: isize, y: isize, z: isize,
         transform: &[f64; 6], coord_transform: &CoordTransform)
         -> Result<(f64, f64, f64), Box<dyn Error>> {
@@ -153,6 +1109,84 @@ pub fn transform_pixels(pixels: &[(isize, isize, isize)],
     Ok((xs, ys, zs))
 }
 
+pub fn transform_pixels_chunked<F>(pixels: &[(isize, isize, isize)],
+        transform: &[f64; 6], coord_transform: &CoordTransform,
+        chunk_size: usize, mut callback: F) -> Result<(), Box<dyn Error>>
+        where F: FnMut(usize, WindowBounds) -> Result<(), Box<dyn Error>> {
+    // transform fixed-size batches, passing each with its pixel offset
+    let chunk_size = chunk_size.max(1);
+    for (i, chunk) in pixels.chunks(chunk_size).enumerate() {
+        let bounds = transform_pixels(chunk, transform, coord_transform)?;
+        callback(i * chunk_size, bounds)?;
+    }
+
+    Ok(())
+}
+
+pub fn transform_pixels_parallel<F>(pixels: &[(isize, isize, isize)],
+        transform: &[f64; 6], projection: &str, target: &Target,
+        chunk_size: usize, threads: usize, mut callback: F)
+        -> Result<(), Box<dyn Error>>
+        where F: FnMut(usize, WindowBounds) -> Result<(), Box<dyn Error>> {
+    let chunk_size = chunk_size.max(1);
+    let threads = threads.max(1);
+
+    // bound in-flight batches to the thread count
+    let (sender, receiver) = std::sync::mpsc::sync_channel(threads);
+    std::thread::scope(|scope| {
+        for t in 0..threads {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                let chunks = pixels.chunks(chunk_size).enumerate()
+                    .skip(t).step_by(threads);
+                for (i, chunk) in chunks {
+                    let result = TransformCache::with(|cache| {
+                        transform_pixels(chunk, transform,
+                            cache.get(projection, target)?)
+                    }).map_err(|e| e.to_string());
+
+                    if sender.send((i * chunk_size, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        drop(sender);
+
+        // invoke callback on the calling thread as batches complete,
+        // dropping the receiver on error stops the workers
+        for (offset, result) in receiver {
+            callback(offset, result?)?;
+        }
+
+        Ok(())
+    })
+}
+
+pub fn coord_to_pixel(x: f64, y: f64, transform: &[f64; 6])
+        -> Result<(f64, f64), Box<dyn Error>> {
+    Ok(coords_to_pixels(&[x], &[y], transform)?[0])
+}
+
+pub fn coords_to_pixels(xs: &[f64], ys: &[f64], transform: &[f64; 6])
+        -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
+    // invert the 2x2 transform matrix, supporting rotated datasets
+    let determinant = transform[1] * transform[5]
+        - transform[2] * transform[4];
+    if determinant == 0.0 {
+        return Err("geo transform is not invertible".into());
+    }
+
+    let pixels = xs.iter().zip(ys.iter()).map(|(x, y)| {
+        let (dx, dy) = (x - transform[0], y - transform[3]);
+        ((transform[5] * dx - transform[2] * dy) / determinant,
+            (transform[1] * dy - transform[4] * dx) / determinant)
+    }).collect();
+
+    Ok(pixels)
+}
+
 pub fn transform_coord(x: f64, y: f64, z: f64,
         coord_transform: &CoordTransform)
         -> Result<(f64, f64, f64), Box<dyn Error>> {
@@ -170,13 +1204,232 @@ pub fn transform_coord(x: f64, y: f64, z: f64,
 
 #[cfg(test)]
 mod tests {
+    use gdal::Dataset;
     use gdal::spatial_ref::{CoordTransform, SpatialRef};
 
+    use std::path::Path;
+
     const APPLETON_LAT_LONG: (f64, f64) = (-88.4, 44.266667);
     const APPLETON_MERCATOR: (f64, f64) = (-9840642.99, 5506802.68);
     const FORT_COLLINS_LAT_LONG: (f64, f64) = (-105.078056, 40.559167);
     const FORT_COLLINS_MERCATOR: (f64, f64) = (-11697235.69, 4947534.74);
 
+    #[test]
+    fn geohash_encode() {
+        let geocode = super::Geocode::Geohash;
+        assert_eq!(geocode.encode(-5.6, 42.6, 5).expect("encode"), "ezs42");
+        assert_eq!(geocode.encode(APPLETON_LAT_LONG.0,
+            APPLETON_LAT_LONG.1, 4).expect("encode"), "dpc5");
+        assert!(geocode.encode(-190.0, 0.0, 4).is_err());
+    }
+
+    #[test]
+    fn area_m2() {
+        let area = super::bounds_area_m2(-180.0, 180.0, -90.0, 90.0);
+        assert!((area - 510065621724088.56).abs() < 1.0e3);
+
+        let area = super::bounds_area_m2(0.0, 1.0, 0.0, 1.0);
+        let polygon_area = super::polygon_area_m2(
+            &[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)]);
+        assert!((area - 12308463893.98).abs() < 1.0);
+        assert!((area - polygon_area).abs() < 1.0);
+    }
+
+    #[test]
+    fn select_precision() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let geocode = super::Geocode::Geohash;
+        assert_eq!(super::select_precision(&dataset, geocode, 1000.0)
+            .expect("select precision"), 3);
+        assert!(super::select_precision(&dataset, geocode, 100000.0)
+            .expect("select precision") < 3);
+
+        let (precision, windows) = super::select_windows(&dataset,
+            geocode, 1000.0).expect("select windows");
+        assert_eq!(precision, 3);
+        assert!(!windows.is_empty());
+    }
+
+    #[test]
+    fn geocode_mask() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // mask the geohash at the dataset center
+        let geocode = super::Geocode::Geohash;
+        let (min_x, max_x, min_y, max_y) =
+            super::get_bounds(&dataset, 4326).expect("get bounds");
+        let code = geocode.encode((min_x + max_x) / 2.0,
+            (min_y + max_y) / 2.0, 3).expect("encode");
+
+        let mask_dataset = super::geocode_mask(&dataset, geocode, &code)
+            .expect("geocode mask");
+        let mask = mask_dataset.rasterband(1).expect("get rasterband")
+            .read_band_as::<u8>().expect("read rasterband");
+
+        let count = mask.data.iter().filter(|x| **x == 1).count();
+        assert!(count > 0 && count < mask.data.len());
+
+        // masked pixels are exactly those encoding to the geocode
+        let geocodes = super::pixel_geocodes(&dataset, geocode, 3)
+            .expect("pixel geocodes");
+        for (value, pixel_code) in mask.data.iter().zip(geocodes.iter()) {
+            assert_eq!(*value == 1, *pixel_code == code);
+        }
+    }
+
+    #[test]
+    fn identify_crs() {
+        let wkt = SpatialRef::from_epsg(32613).expect("initialize SpatialRef")
+            .to_wkt().expect("export wkt");
+        let crs = super::identify_crs(&wkt).expect("identify crs");
+        assert_eq!(crs.epsg_code, Some(32613));
+
+        // unidentified definitions compare equal regardless of formatting
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let crs = super::identify_crs(&dataset.projection())
+            .expect("identify crs");
+        assert_eq!(crs.epsg_code, None);
+
+        let pretty_wkt = SpatialRef::from_wkt(&crs.wkt)
+            .expect("initialize SpatialRef")
+            .to_pretty_wkt().expect("export pretty wkt");
+        assert_ne!(pretty_wkt, crs.wkt);
+        assert_eq!(crs, super::Crs { epsg_code: None, wkt: pretty_wkt });
+        assert_ne!(crs, super::identify_crs(&SpatialRef::from_epsg(4326)
+            .expect("initialize SpatialRef").to_wkt().expect("export wkt"))
+            .expect("identify crs"));
+    }
+
+    #[test]
+    fn get_intervals() {
+        let geocode = super::Geocode::Geohash;
+        assert_eq!(geocode.get_intervals(1), (45.0, 45.0));
+        assert_eq!(geocode.get_intervals(2), (11.25, 5.625));
+
+        // intervals match decoded geohash bounds
+        let (min_x, max_x, min_y, max_y) =
+            geocode.decode("9xj").expect("decode");
+        assert_eq!(geocode.get_intervals(3), (max_x - min_x, max_y - min_y));
+    }
+
+    #[test]
+    fn geohash_decode() {
+        let geocode = super::Geocode::Geohash;
+        let (min_x, max_x, min_y, max_y) =
+            geocode.decode("ezs42").expect("decode");
+        assert!(min_x <= -5.6 && -5.6 < max_x);
+        assert!(min_y <= 42.6 && 42.6 < max_y);
+        assert!(geocode.decode("ezs4a").is_err());
+    }
+
+    #[test]
+    fn quadkey() {
+        assert_eq!(super::quadkey(3, 5, 3), "213");
+        assert_eq!(super::quadkey(0, 0, 1), "0");
+        assert_eq!(super::quadkey(0, 0, 0), "");
+    }
+
+    #[test]
+    fn quadkey_encode() {
+        let geocode = super::Geocode::QuadTile;
+        let extent = super::WEB_MERCATOR_EXTENT;
+        assert_eq!(geocode.encode(1.0, 1.0, 1).expect("encode"), "1");
+        assert_eq!(geocode.encode(extent, -extent, 2).expect("encode"),
+            "33");
+        assert_eq!(geocode.get_intervals(1), (extent, extent));
+        assert!(geocode.encode(-extent - 1.0, 0.0, 2).is_err());
+
+        // encoded tiles contain the coordinate
+        let code = geocode.encode(FORT_COLLINS_MERCATOR.0,
+            FORT_COLLINS_MERCATOR.1, 12).expect("encode");
+        let (min_x, max_x, min_y, max_y) =
+            geocode.decode(&code).expect("decode");
+        assert!(min_x <= FORT_COLLINS_MERCATOR.0
+            && FORT_COLLINS_MERCATOR.0 < max_x);
+        assert!(min_y < FORT_COLLINS_MERCATOR.1
+            && FORT_COLLINS_MERCATOR.1 <= max_y);
+    }
+
+    #[test]
+    fn quadkey_decode() {
+        let extent = super::WEB_MERCATOR_EXTENT;
+        assert_eq!(super::quadkey_decode("").expect("decode"),
+            (-extent, extent, -extent, extent));
+        assert_eq!(super::quadkey_decode("1").expect("decode"),
+            (0.0, extent, 0.0, extent));
+        assert!(super::quadkey_decode("14").is_err());
+    }
+
+    #[test]
+    fn quadkey_precision() {
+        // zooms overflowing quadtile intervals are rejected
+        let geocode = super::Geocode::QuadTile;
+        let extent = super::WEB_MERCATOR_EXTENT;
+        assert!(geocode.check_precision(24).is_ok());
+        assert!(super::compute_bounds(-extent, extent, -extent, extent,
+            geocode, 64).is_err());
+        assert!(geocode.encode(0.0, 0.0, super::MAX_ZOOM + 1).is_err());
+    }
+
+    #[test]
+    fn compute_bounds() {
+        let codes = super::compute_bounds(-105.0, -104.0, 40.0, 41.0,
+            super::Geocode::Geohash, 3).expect("compute bounds");
+        assert_eq!(codes.len(), 4);
+
+        let geocode = super::Geocode::Geohash;
+        for (code, bounds) in codes.iter() {
+            assert_eq!(geocode.decode(code).expect("decode"), *bounds);
+        }
+    }
+
+    #[test]
+    fn neighbors() {
+        let geocode = super::Geocode::Geohash;
+        let neighbors = geocode.neighbors("9xj").expect("neighbors");
+        assert_eq!(neighbors, vec!["9xk", "9xm", "9xq",
+            "9xh", "9xn", "9wu", "9wv", "9wy"]);
+
+        // antimeridian wraps and poles truncate
+        let neighbors = geocode.neighbors("b").expect("neighbors");
+        assert_eq!(neighbors.len(), 5);
+        assert!(neighbors.contains(&"z".to_string()));
+    }
+
+    #[test]
+    fn compute_covering() {
+        let triangle = [(-105.0, 40.0), (-104.0, 40.0), (-105.0, 41.0)];
+        let codes = super::compute_covering(&triangle,
+            super::Geocode::Geohash, 4).expect("compute covering");
+        let bounds = super::compute_bounds(-105.0, -104.0, 40.0, 41.0,
+            super::Geocode::Geohash, 4).expect("compute bounds");
+
+        assert!(!codes.is_empty());
+        assert!(codes.len() < bounds.len());
+    }
+
+    #[test]
+    fn completeness() {
+        let present = vec!["9xj".to_string(), "zzz".to_string()];
+        let report = super::completeness(-105.0, -104.0, 40.0, 41.0,
+            super::Geocode::Geohash, 3, &present).expect("completeness");
+
+        assert!(report.expected.contains(&"9xj".to_string()));
+        assert!(!report.missing.contains(&"9xj".to_string()));
+        assert_eq!(report.extra, vec!["zzz".to_string()]);
+        assert_eq!(report.missing.len(), report.expected.len() - 1);
+
+        // precisions overflowing geohash intervals are rejected
+        assert!(super::completeness(-105.0, -104.0, 40.0, 41.0,
+            super::Geocode::Geohash, 26, &present).is_err());
+        assert!(super::compute_bounds(-105.0, -104.0, 40.0, 41.0,
+            super::Geocode::Geohash, 13).is_err());
+    }
+
     #[test]
     fn transform_coord() {
         // initialize CoordTransform
@@ -215,11 +1468,105 @@ mod tests {
         assert!((coordinates.1 - FORT_COLLINS_MERCATOR.1).abs() < 0.01);
     }
 
+    #[test]
+    fn transform_cache() {
+        let wkt = SpatialRef::from_epsg(4326).expect("initialize SpatialRef")
+            .to_wkt().expect("export wkt");
+
+        let mut cache = super::TransformCache::new();
+        let target = super::Target::Epsg(3857);
+        cache.get(&wkt, &target).expect("cache transform");
+        cache.get(&wkt, &target).expect("reuse transform");
+        assert_eq!(cache.transforms.len(), 1);
+
+        let target = super::Target::Proj4("+proj=longlat +datum=WGS84 \
+            +no_defs".to_string());
+        cache.get(&wkt, &target).expect("cache transform");
+        assert_eq!(cache.transforms.len(), 2);
+    }
+
+    #[test]
+    fn coord_to_pixel() {
+        let transform = [100.0, 2.0, 0.5, 200.0, 0.25, -3.0];
+        let (px, py) = (7.0, 11.0);
+        let x = transform[0] + px * transform[1] + py * transform[2];
+        let y = transform[3] + px * transform[4] + py * transform[5];
+
+        let pixel = super::coord_to_pixel(x, y, &transform)
+            .expect("invert transform");
+        assert!((pixel.0 - px).abs() < 1.0e-9);
+        assert!((pixel.1 - py).abs() < 1.0e-9);
+
+        let transform = [0.0, 1.0, 1.0, 0.0, 1.0, 1.0];
+        assert!(super::coord_to_pixel(0.0, 0.0, &transform).is_err());
+    }
+
+    #[test]
+    fn snap_to_grid() {
+        let bounds = super::snap_to_grid((499983.0, 500017.0, 4400001.0,
+            4400029.0), (499980.0, 4400040.0), (10.0, -10.0));
+        assert_eq!(bounds, (499980.0, 500020.0, 4400000.0, 4400030.0));
+    }
+
     // TODO - transform pixel
 
     // TODO - transform pixels
 
+    #[test]
+    fn transform_pixels_parallel() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let (transform, projection, src_spatial_ref, dst_spatial_ref) =
+            super::get_transform_refs(&dataset, 4326)
+            .expect("transform refs");
+        let coord_transform = CoordTransform::new(&src_spatial_ref,
+            &dst_spatial_ref).expect("coord transform");
+
+        let pixels: Vec<(isize, isize, isize)> = (0..=256).step_by(16)
+            .flat_map(|y| (0..=256).step_by(16).map(move |x| (x, y, 0)))
+            .collect();
+
+        // write each batch into coordinates at its pixel offset
+        let collect = |coords: &mut Vec<(f64, f64)>, offset: usize,
+                (xs, ys, _): super::WindowBounds| {
+            for (i, (x, y)) in xs.into_iter().zip(ys).enumerate() {
+                coords[offset + i] = (x, y);
+            }
+        };
+
+        let mut chunked = vec![(0.0, 0.0); pixels.len()];
+        super::transform_pixels_chunked(&pixels, &transform,
+            &coord_transform, 10, |offset, bounds| {
+                collect(&mut chunked, offset, bounds);
+                Ok(())
+            }).expect("transform chunked");
+
+        let mut parallel = vec![(0.0, 0.0); pixels.len()];
+        super::transform_pixels_parallel(&pixels, &transform,
+            &projection, &super::Target::Epsg(4326), 10, 4,
+            |offset, bounds| {
+                collect(&mut parallel, offset, bounds);
+                Ok(())
+            }).expect("transform parallel");
+
+        assert_eq!(chunked, parallel);
+    }
+
     // TODO - test get_bounds
  
-    // TODO - test get_windows
+    #[test]
+    fn get_windows() {
+        let windows = super::get_windows(-0.5, 1.5, 0.25, 0.75, 1.0, 1.0);
+        assert_eq!(windows, vec![(-1.0, 0.0, 0.0, 1.0),
+            (0.0, 1.0, 0.0, 1.0), (1.0, 2.0, 0.0, 1.0)]);
+
+        let windows = super::get_windows_with_options(-0.5, 1.5,
+            0.25, 0.75, 1.0, 1.0, true);
+        assert_eq!(windows, vec![(-0.5, 0.0, 0.25, 0.75),
+            (0.0, 1.0, 0.25, 0.75), (1.0, 1.5, 0.25, 0.75)]);
+
+        let iter = super::get_windows_iter(-180.0, 180.0, -90.0, 90.0,
+            0.01, 0.01, false);
+        assert_eq!(iter.len(), 36000 * 18000);
+    }
 }
@@ -0,0 +1,314 @@
+use gdal::Dataset;
+use gdal::spatial_ref::SpatialRef;
+
+use crate::coordinate::{WGS84_FLATTENING, WGS84_SEMI_MAJOR_AXIS};
+
+use std::error::Error;
+
+const UTM_SCALE_FACTOR: f64 = 0.9996;
+const UTM_SOUTH_FALSE_NORTHING: f64 = 10000000.0;
+
+static MGRS_BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWX";
+static MGRS_COLUMNS: [&[u8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+static MGRS_ROWS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+
+// sentinel-2 tiles span 109.8 km from the north-west corner of their
+// 100 km mgrs square, snapped to a 60 m grid
+const SENTINEL2_TILE_EXTENT: f64 = 109800.0;
+const SENTINEL2_TILE_ALIGNMENT: f64 = 60.0;
+
+// modis tiles partition the sinusoidal projection into 36 x 18 tiles
+pub const MODIS_SINUSOIDAL_PROJ4: &str = "+proj=sinu +lon_0=0 +x_0=0 \
+    +y_0=0 +R=6371007.181 +units=m +no_defs";
+const MODIS_SPHERE_RADIUS: f64 = 6371007.181;
+const MODIS_TILE_EXTENT: f64 = 1111950.5196666666;
+const MODIS_MIN_X: f64 = -20015109.354;
+const MODIS_MAX_Y: f64 = 10007554.677;
+const MODIS_H_TILES: usize = 36;
+const MODIS_V_TILES: usize = 18;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridTile {
+    pub id: String,
+    /// projection wkt
+    pub projection: String,
+    /// (min_x, max_x, min_y, max_y) in the tile projection
+    pub bounds: (f64, f64, f64, f64),
+}
+
+pub fn sentinel2_tile(id: &str) -> Result<GridTile, Box<dyn Error>> {
+    let code = match id.strip_prefix('T') {
+        Some(code) if code.starts_with(|x: char| x.is_ascii_digit()) =>
+            code,
+        _ => id,
+    };
+
+    // parse zone number and latitude band, column and row letters
+    let digits = code.chars().take_while(|x| x.is_ascii_digit()).count();
+    let letters = code[digits..].as_bytes();
+    let zone: u32 = code[..digits].parse()?;
+    if zone < 1 || zone > 60 || letters.len() != 3 {
+        return Err(format!("invalid sentinel-2 tile '{}'", id).into());
+    }
+
+    let band = MGRS_BANDS.iter().position(|x| *x == letters[0]);
+    let column = MGRS_COLUMNS[(zone as usize - 1) % 3].iter()
+        .position(|x| *x == letters[1]);
+    let row = MGRS_ROWS.iter().position(|x| *x == letters[2]);
+
+    let (band, column, row) = match (band, column, row) {
+        (Some(band), Some(column), Some(row)) => (band, column, row),
+        _ => return Err(format!("invalid sentinel-2 tile '{}'",
+            id).into()),
+    };
+
+    // rows repeat every 2000 km, select the cycle reaching the band
+    let south = MGRS_BANDS[band] < b'N';
+    let band_min_northing = utm_northing(-80.0 + 8.0 * band as f64, south);
+    let row_offset = if zone % 2 == 0 { 5 } else { 0 };
+    let base = ((row + 20 - row_offset) % 20) as f64 * 100000.0;
+    let cycles = ((band_min_northing - 100000.0 - base) / 2000000.0)
+        .ceil().max(0.0);
+
+    let min_easting = (column + 1) as f64 * 100000.0;
+    let max_northing = base + cycles * 2000000.0 + 100000.0;
+    let (min_x, _, _, max_y) = crate::coordinate::snap_to_grid(
+        (min_easting, min_easting, max_northing, max_northing), (0.0, 0.0),
+        (SENTINEL2_TILE_ALIGNMENT, SENTINEL2_TILE_ALIGNMENT));
+
+    Ok(GridTile {
+        id: format!("T{:02}{}", zone, &code[digits..]),
+        projection: SpatialRef::from_epsg(utm_epsg_code(zone, south))?
+            .to_wkt()?,
+        bounds: (min_x, min_x + SENTINEL2_TILE_EXTENT,
+            max_y - SENTINEL2_TILE_EXTENT, max_y),
+    })
+}
+
+pub fn sentinel2_tiles(dataset: &Dataset)
+        -> Result<Vec<GridTile>, Box<dyn Error>> {
+    let (min_lon, max_lon, min_lat, max_lat) =
+        crate::coordinate::get_bounds(dataset, 4326)?;
+    let (min_lat, max_lat) = (min_lat.max(-80.0), max_lat.min(84.0));
+    if min_lat >= max_lat {
+        return Ok(Vec::new());
+    }
+
+    // utm zones spanned by the dataset, ignoring the norway and
+    // svalbard exceptions
+    let utm_zone = |longitude: f64| {
+        (((longitude + 180.0) / 6.0).floor() as i64 + 1).max(1).min(60)
+    };
+
+    let mut tiles = Vec::new();
+    for zone in utm_zone(min_lon)..=utm_zone(max_lon) {
+        let zone = zone as u32;
+        for south in [false, true].iter().cloned() {
+            if (south && min_lat >= 0.0) || (!south && max_lat < 0.0) {
+                continue;
+            }
+
+            // enumerate 100 km squares whose tiles intersect the dataset
+            let epsg_code = utm_epsg_code(zone, south);
+            let (min_x, max_x, min_y, max_y) =
+                crate::coordinate::get_bounds(dataset, epsg_code)?;
+
+            for column in 1..=8 {
+                let easting = column as f64 * 100000.0;
+                if easting >= max_x
+                        || easting + SENTINEL2_TILE_EXTENT <= min_x {
+                    continue;
+                }
+
+                let min_row = ((min_y / 100000.0).floor() as i64).max(0);
+                let max_row = (max_y + SENTINEL2_TILE_EXTENT - 100000.0)
+                    / 100000.0;
+                for row in min_row..=(max_row.floor() as i64) {
+                    let northing = row as f64 * 100000.0;
+                    if northing + 100000.0 - SENTINEL2_TILE_EXTENT >= max_y
+                            || northing + 100000.0 <= min_y {
+                        continue;
+                    }
+
+                    // identify band from the square center latitude
+                    let latitude = utm_latitude(northing + 50000.0, south);
+                    if latitude < -80.0 || latitude >= 84.0
+                            || (latitude < 0.0) != south {
+                        continue;
+                    }
+
+                    let band = (((latitude + 80.0) / 8.0).floor() as usize)
+                        .min(MGRS_BANDS.len() - 1);
+                    let row_offset = if zone % 2 == 0 { 5 } else { 0 };
+                    let id = format!("T{:02}{}{}{}", zone,
+                        MGRS_BANDS[band] as char,
+                        MGRS_COLUMNS[(zone as usize - 1) % 3][column - 1]
+                            as char,
+                        MGRS_ROWS[(row as usize + row_offset) % 20] as char);
+
+                    tiles.push(sentinel2_tile(&id)?);
+                }
+            }
+        }
+    }
+
+    Ok(tiles)
+}
+
+pub fn modis_tile(id: &str) -> Result<GridTile, Box<dyn Error>> {
+    // parse 'hHHvVV' tile indices
+    let indices = id.strip_prefix('h').and_then(|x| {
+        let mut split = x.splitn(2, 'v');
+        match (split.next(), split.next()) {
+            (Some(h), Some(v)) => Some((h.parse::<usize>().ok()?,
+                v.parse::<usize>().ok()?)),
+            _ => None,
+        }
+    });
+
+    let (h, v) = match indices {
+        Some((h, v)) if h < MODIS_H_TILES && v < MODIS_V_TILES => (h, v),
+        _ => return Err(format!("invalid modis tile '{}'", id).into()),
+    };
+
+    let min_x = MODIS_MIN_X + h as f64 * MODIS_TILE_EXTENT;
+    let max_y = MODIS_MAX_Y - v as f64 * MODIS_TILE_EXTENT;
+
+    Ok(GridTile {
+        id: format!("h{:02}v{:02}", h, v),
+        projection: SpatialRef::from_proj4(MODIS_SINUSOIDAL_PROJ4)?
+            .to_wkt()?,
+        bounds: (min_x, min_x + MODIS_TILE_EXTENT,
+            max_y - MODIS_TILE_EXTENT, max_y),
+    })
+}
+
+pub fn modis_tiles(dataset: &Dataset)
+        -> Result<Vec<GridTile>, Box<dyn Error>> {
+    let (min_lon, max_lon, min_lat, max_lat) =
+        crate::coordinate::get_bounds(dataset, 4326)?;
+
+    // sinusoidal x scales longitude by the cosine of latitude, so the
+    // widest extent lies at the latitude nearest the equator
+    let max_cos = match (min_lat > 0.0, max_lat < 0.0) {
+        (true, _) => min_lat.to_radians().cos(),
+        (_, true) => max_lat.to_radians().cos(),
+        _ => 1.0,
+    };
+    let min_cos = min_lat.to_radians().cos()
+        .min(max_lat.to_radians().cos());
+
+    let min_x = MODIS_SPHERE_RADIUS * min_lon.to_radians()
+        * if min_lon < 0.0 { max_cos } else { min_cos };
+    let max_x = MODIS_SPHERE_RADIUS * max_lon.to_radians()
+        * if max_lon < 0.0 { min_cos } else { max_cos };
+    let min_y = MODIS_SPHERE_RADIUS * min_lat.to_radians();
+    let max_y = MODIS_SPHERE_RADIUS * max_lat.to_radians();
+
+    // enumerate tiles intersecting the sinusoidal extent
+    let index = |value: f64, max: usize| {
+        (value / MODIS_TILE_EXTENT).floor().max(0.0).min(max as f64 - 1.0)
+            as usize
+    };
+
+    let mut tiles = Vec::new();
+    for v in index(MODIS_MAX_Y - max_y, MODIS_V_TILES)
+            ..=index(MODIS_MAX_Y - min_y, MODIS_V_TILES) {
+        for h in index(min_x - MODIS_MIN_X, MODIS_H_TILES)
+                ..=index(max_x - MODIS_MIN_X, MODIS_H_TILES) {
+            tiles.push(modis_tile(&format!("h{:02}v{:02}", h, v))?);
+        }
+    }
+
+    Ok(tiles)
+}
+
+fn utm_epsg_code(zone: u32, south: bool) -> u32 {
+    match south {
+        true => 32700 + zone,
+        false => 32600 + zone,
+    }
+}
+
+fn meridian_arc(latitude: f64) -> f64 {
+    let e2 = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+    let phi = latitude.to_radians();
+
+    WGS84_SEMI_MAJOR_AXIS * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0
+            - 5.0 * e6 / 256.0) * phi
+        - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0)
+            * (2.0 * phi).sin()
+        + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+        - (35.0 * e6 / 3072.0) * (6.0 * phi).sin())
+}
+
+fn utm_northing(latitude: f64, south: bool) -> f64 {
+    // northing along the zone central meridian
+    let northing = meridian_arc(latitude) * UTM_SCALE_FACTOR;
+    match south {
+        true => northing + UTM_SOUTH_FALSE_NORTHING,
+        false => northing,
+    }
+}
+
+fn utm_latitude(northing: f64, south: bool) -> f64 {
+    let northing = match south {
+        true => northing - UTM_SOUTH_FALSE_NORTHING,
+        false => northing,
+    };
+
+    // invert the meridian arc with newton iterations
+    let e2 = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+    let mut latitude = (northing
+        / (WGS84_SEMI_MAJOR_AXIS * UTM_SCALE_FACTOR)).to_degrees();
+    for _ in 0..8 {
+        let sin = latitude.to_radians().sin();
+        let derivative = WGS84_SEMI_MAJOR_AXIS * (1.0 - e2)
+            / (1.0 - e2 * sin * sin).powf(1.5) * UTM_SCALE_FACTOR;
+        let error = meridian_arc(latitude) * UTM_SCALE_FACTOR - northing;
+        latitude -= (error / derivative).to_degrees();
+    }
+
+    latitude
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn sentinel2_tile() {
+        let tile = super::sentinel2_tile("T13TDE").expect("parse tile");
+        let crs = crate::coordinate::identify_crs(&tile.projection)
+            .expect("identify crs");
+        assert_eq!(crs.epsg_code, Some(32613));
+        assert_eq!(tile.bounds, (399960.0, 509760.0, 4390200.0, 4500000.0));
+
+        let tile = super::sentinel2_tile("13TDE").expect("parse tile");
+        assert_eq!(tile.id, "T13TDE");
+        assert!(super::sentinel2_tile("T13TDI").is_err());
+    }
+
+    #[test]
+    fn modis_tiles() {
+        let tile = super::modis_tile("h10v04").expect("parse tile");
+        assert!((tile.bounds.0 + 8895604.157333334).abs() < 1e-6);
+        assert!((tile.bounds.3 - 5559752.598333334).abs() < 1e-6);
+        assert!(super::modis_tile("h36v00").is_err());
+
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let tiles = super::modis_tiles(&dataset).expect("modis tiles");
+        assert!(tiles.iter().any(|x| x.id == "h10v04"));
+    }
+
+    #[test]
+    fn utm_latitude() {
+        let northing = super::utm_northing(40.56, false);
+        assert!((northing - 4489914.757).abs() < 0.01);
+        assert!((super::utm_latitude(northing, false) - 40.56).abs() < 1e-9);
+    }
+}
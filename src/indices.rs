@@ -0,0 +1,148 @@
+use gdal::Dataset;
+
+use crate::calc::Expr;
+
+use std::error::Error;
+
+pub fn ndvi(dataset: &Dataset, red_band: isize, nir_band: isize)
+        -> Result<Dataset, Box<dyn Error>> {
+    normalized_difference(dataset, nir_band, red_band)
+}
+
+pub fn ndwi(dataset: &Dataset, green_band: isize, nir_band: isize)
+        -> Result<Dataset, Box<dyn Error>> {
+    normalized_difference(dataset, green_band, nir_band)
+}
+
+pub fn ndsi(dataset: &Dataset, green_band: isize, swir_band: isize)
+        -> Result<Dataset, Box<dyn Error>> {
+    normalized_difference(dataset, green_band, swir_band)
+}
+
+pub fn evi(dataset: &Dataset, blue_band: isize, red_band: isize,
+        nir_band: isize) -> Result<Dataset, Box<dyn Error>> {
+    validate_bands(dataset, &[blue_band, red_band, nir_band])?;
+
+    // 2.5 * (nir - red) / (nir + 6 * red - 7.5 * blue + 1), band scale
+    // and offset are applied so reflectance products yield [0, 1] inputs
+    let (blue, red, nir) = (band(blue_band), band(red_band), band(nir_band));
+    let numerator = binary('*', number(2.5), binary('-', nir.clone(),
+        red.clone()));
+    let denominator = binary('+', binary('-', binary('+', nir,
+            binary('*', number(6.0), red)),
+        binary('*', number(7.5), blue)), number(1.0));
+
+    crate::calc::evaluate_expr(dataset,
+        &binary('/', numerator, denominator))
+}
+
+pub fn normalized_difference(dataset: &Dataset, a_band: isize,
+        b_band: isize) -> Result<Dataset, Box<dyn Error>> {
+    validate_bands(dataset, &[a_band, b_band])?;
+
+    // (a - b) / (a + b)
+    let (a, b) = (band(a_band), band(b_band));
+    let expr = binary('/', binary('-', a.clone(), b.clone()),
+        binary('+', a, b));
+
+    crate::calc::evaluate_expr(dataset, &expr)
+}
+
+fn validate_bands(dataset: &Dataset, indices: &[isize])
+        -> Result<(), Box<dyn Error>> {
+    for index in indices.iter() {
+        if *index < 1 || *index > dataset.raster_count() {
+            return Err(format!("band {} out of range", index).into());
+        }
+    }
+
+    Ok(())
+}
+
+fn band(index: isize) -> Expr {
+    Expr::Band(index)
+}
+
+fn number(value: f64) -> Expr {
+    Expr::Number(value)
+}
+
+fn binary(op: char, lhs: Expr, rhs: Expr) -> Expr {
+    Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn ndvi() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let ndvi_dataset = super::ndvi(&dataset, 1, 2).expect("ndvi");
+        let values = ndvi_dataset.rasterband(1).expect("get rasterband")
+            .read_band_as::<f32>().expect("read rasterband").data;
+
+        assert_eq!(values.len(), 256 * 256);
+        assert!(values.iter().filter(|x| !x.is_nan())
+            .all(|x| *x >= -1.0 && *x <= 1.0));
+        assert!(super::ndvi(&dataset, 1, 99).is_err());
+    }
+
+    #[test]
+    fn evi() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let evi_dataset = super::evi(&dataset, 3, 1, 2).expect("evi");
+        let values = evi_dataset.rasterband(1).expect("get rasterband")
+            .read_band_as::<f32>().expect("read rasterband").data;
+        assert!(super::evi(&dataset, 3, 1, 99).is_err());
+
+        // the fixture scales stored values by 3
+        let band = |index| dataset.rasterband(index).expect("rasterband")
+            .read_band_as::<f64>().expect("read band").data;
+        let (blue, red, nir) = (band(3), band(1), band(2));
+        for i in (0..values.len()).step_by(997) {
+            let (b, r, n) = (blue[i] * 3.0, red[i] * 3.0, nir[i] * 3.0);
+            let expected = 2.5 * (n - r) / (n + 6.0 * r - 7.5 * b + 1.0);
+            match expected.is_finite() {
+                true => assert!((values[i] as f64 - expected).abs()
+                    < 1e-4 * expected.abs().max(1.0)),
+                false => assert!(values[i].is_nan()),
+            }
+        }
+    }
+
+    #[test]
+    fn ndwi_ndsi() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let band = |index| dataset.rasterband(index).expect("rasterband")
+            .read_band_as::<f64>().expect("read band").data;
+        let read = |index_dataset: Dataset| index_dataset.rasterband(1)
+            .expect("get rasterband").read_band_as::<f32>()
+            .expect("read rasterband").data;
+
+        let ndwi = read(super::ndwi(&dataset, 3, 2).expect("ndwi"));
+        let ndsi = read(super::ndsi(&dataset, 3, 1).expect("ndsi"));
+        assert!(super::ndwi(&dataset, 0, 2).is_err());
+        assert!(super::ndsi(&dataset, 3, 4).is_err());
+
+        for (values, a, b) in [(ndwi, band(3), band(2)),
+                (ndsi, band(3), band(1))].iter() {
+            for i in (0..values.len()).step_by(997) {
+                let expected = (a[i] - b[i]) / (a[i] + b[i]);
+                match expected.is_finite() {
+                    true => assert!((values[i] as f64 - expected).abs()
+                        < 1e-6),
+                    false => assert!(values[i].is_nan()),
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,80 @@
 use gdal::{Dataset, Driver};
-use gdal::raster::{Buffer, GdalType};
-use gdal_sys::GDALDataType;
+use gdal::raster::{Buffer, GdalType, RasterBand};
+use gdal_sys::{CPLErr, GDALDataType, GDALMajorObjectH,
+    GDALResampleAlg, GDALRIOResampleAlg, GDALRWFlag};
 
+use std::borrow::Borrow;
 use std::error::Error;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+pub mod analysis;
+pub mod block;
+pub mod calc;
+#[cfg(feature = "serde")]
+pub mod catalog;
+pub mod chips;
+pub mod composite;
 pub mod coordinate;
+pub mod grid;
+pub mod indices;
+pub mod mask;
+pub mod metrics;
+pub mod mosaic;
+pub mod parallel;
+pub mod pipeline;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod remote;
+pub mod render;
 pub mod serialize;
+pub mod stack;
+pub mod stats;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod transform;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resampling {
+    Nearest,
+    Bilinear,
+    Cubic,
+    Average,
+}
+
+impl Resampling {
+    pub fn to_gdal(&self) -> GDALRIOResampleAlg::Type {
+        match self {
+            Resampling::Nearest => GDALRIOResampleAlg::GRIORA_NearestNeighbour,
+            Resampling::Bilinear => GDALRIOResampleAlg::GRIORA_Bilinear,
+            Resampling::Cubic => GDALRIOResampleAlg::GRIORA_Cubic,
+            Resampling::Average => GDALRIOResampleAlg::GRIORA_Average,
+        }
+    }
+
+    pub fn to_gdal_warp(&self) -> GDALResampleAlg::Type {
+        match self {
+            Resampling::Nearest => GDALResampleAlg::GRA_NearestNeighbour,
+            Resampling::Bilinear => GDALResampleAlg::GRA_Bilinear,
+            Resampling::Cubic => GDALResampleAlg::GRA_Cubic,
+            Resampling::Average => GDALResampleAlg::GRA_Average,
+        }
+    }
+
+    pub fn to_gdal_name(&self) -> &'static str {
+        match self {
+            Resampling::Nearest => "NEAREST",
+            Resampling::Bilinear => "BILINEAR",
+            Resampling::Cubic => "CUBIC",
+            Resampling::Average => "AVERAGE",
+        }
+    }
+}
+
 pub trait FromPrimitive {
     fn from_f64(value: f64) -> Self;
 }
@@ -36,197 +103,761 @@ impl FromPrimitive for f32 {
     }
 }
 
+pub trait ToPrimitive {
+    fn to_f64(&self) -> f64;
+}
+
+impl ToPrimitive for u8 {
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+}
+
+impl ToPrimitive for u16 {
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+}
+
+impl ToPrimitive for i16 {
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+}
+
+impl ToPrimitive for f32 {
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+}
+
+// return false to request cancellation
+pub type ProgressCallback<'a> = dyn FnMut(f64, &str) -> bool + 'a;
+
+pub fn report_progress(progress: &mut ProgressCallback, fraction: f64,
+        stage: &str) -> Result<(), Box<dyn Error>> {
+    match progress(fraction, stage) {
+        true => Ok(()),
+        false => Err(Box::new(Cancelled { stage: stage.to_string() })),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Cancelled {
+    pub stage: String,
+}
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "cancelled during '{}'", self.stage)
+    }
+}
+
+impl Error for Cancelled {}
+
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn check(&self, stage: &str) -> Result<(), Box<dyn Error>> {
+        match self.is_cancelled() {
+            true => Err(Box::new(Cancelled { stage: stage.to_string() })),
+            false => Ok(()),
+        }
+    }
+}
+
+pub(crate) fn is_cancelled(token: &Option<CancellationToken>) -> bool {
+    token.as_ref().map_or(false, |x| x.is_cancelled())
+}
+
+#[derive(Clone, Debug)]
+pub struct CoverageOptions {
+    /// token checked between blocks to abort the computation
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for CoverageOptions {
+    fn default() -> Self {
+        CoverageOptions {
+            cancellation: None,
+        }
+    }
+}
+
 pub fn get_coverage(dataset: &Dataset) -> Result<f64, Box<dyn Error>> {
+    get_coverage_with_options(dataset, &CoverageOptions::default())
+}
+
+pub fn get_coverage_with_options(dataset: &Dataset,
+        options: &CoverageOptions) -> Result<f64, Box<dyn Error>> {
+    get_coverage_with_progress(dataset, options, &mut |_, _| true)
+}
+
+pub fn get_coverage_with_progress(dataset: &Dataset,
+        options: &CoverageOptions, progress: &mut ProgressCallback)
+        -> Result<f64, Box<dyn Error>> {
+    // cancellation is checked with each progress report
+    let mut progress = |fraction: f64, stage: &str| {
+        !is_cancelled(&options.cancellation) && progress(fraction, stage)
+    };
+    let progress: &mut ProgressCallback = &mut progress;
+
     let (width, height) = dataset.raster_size();
-    let mut invalid_pixels = vec![true; width * height];
-    
-    // iterate over rasterbands
+    let mut no_data_values = Vec::new();
     for i in 0..dataset.raster_count() {
+        // pixels hold nodata values converted to the rasterband type
         let rasterband = dataset.rasterband(i+1)?;
-        let no_data_value = rasterband.no_data_value().unwrap_or(0.0);
+        let value = rasterband.no_data_value().unwrap_or(0.0);
+        no_data_values.push(match rasterband.band_type() {
+            GDALDataType::GDT_Byte => u8::from_f64(value).to_f64(),
+            GDALDataType::GDT_Int16 => i16::from_f64(value).to_f64(),
+            GDALDataType::GDT_UInt16 => u16::from_f64(value).to_f64(),
+            GDALDataType::GDT_Float32 => f32::from_f64(value).to_f64(),
+            _ => value,
+        });
+    }
 
-        match rasterband.band_type() {
-            GDALDataType::GDT_Byte => _get_coverage::<u8>(dataset,
-                i+1, &mut invalid_pixels, no_data_value)?,
-            GDALDataType::GDT_Int16 => _get_coverage::<i16>(dataset,
-                i+1, &mut invalid_pixels, no_data_value)?,
-            GDALDataType::GDT_UInt16 => _get_coverage::<u16>(dataset,
-                i+1, &mut invalid_pixels, no_data_value)?,
-            GDALDataType::GDT_Float32 => _get_coverage::<f32>(dataset,
-                i+1, &mut invalid_pixels, no_data_value)?,
-            _ => unimplemented!(),
+    // count pixels where any rasterband is valid
+    let row_size = width * no_data_values.len() * 8;
+    let windows = parallel::block_windows(dataset, row_size)?;
+    let counts = parallel::par_process_blocks(&windows, |i, window| {
+        report_progress(progress,
+            i as f64 / windows.len() as f64, "coverage")?;
+
+        let mut buffers = Vec::new();
+        for j in 0..dataset.raster_count() {
+            buffers.push(dataset.rasterband(j+1)?
+                .read_as::<f64>(window.0, window.1, window.1)?.data);
         }
-    }
+
+        Ok(buffers)
+    }, |window, buffers| {
+        (0..(window.1).0 * (window.1).1).filter(|j| buffers.iter()
+            .zip(no_data_values.iter()).any(|(x, y)| x[*j] != *y)).count()
+    })?;
 
     // compute percentage of valid pixels
     let pixel_count = (width * height) as f64;
-    let invalid_count = invalid_pixels.iter()
-        .filter(|x| **x).count() as f64;
+    let valid_count = counts.iter().sum::<usize>() as f64;
 
-    Ok((pixel_count - invalid_count) / pixel_count)
+    Ok(valid_count / pixel_count)
 }
 
-fn _get_coverage<T: Copy + FromPrimitive + GdalType + PartialEq>(
-        dataset: &Dataset, index: isize, invalid_pixels: &mut Vec<bool>,
-        no_data_value: f64) -> Result<(), Box<dyn Error>> {
-    let no_data_value = T::from_f64(no_data_value);
-
-    // read rasterband data into buffer
-    let buffer = dataset.rasterband(index)?.read_band_as::<T>()?;
+#[derive(Clone, Debug)]
+pub struct FillOptions {
+    /// token checked between blocks to abort the fill
+    pub cancellation: Option<CancellationToken>,
+}
 
-    // iterate over pixels
-    for (i, pixel) in buffer.data.iter().enumerate() {
-        if *pixel != no_data_value {
-            invalid_pixels[i] = false;
+impl Default for FillOptions {
+    fn default() -> Self {
+        FillOptions {
+            cancellation: None,
         }
     }
+}
 
-    Ok(())
+pub fn fill<D: Borrow<Dataset>>(datasets: &[D])
+        -> Result<Dataset, Box<dyn Error>> {
+    fill_with_options(datasets, &FillOptions::default())
 }
 
-pub fn fill(datasets: &[Dataset]) -> Result<Dataset, Box<dyn Error>> {
+pub fn fill_with_options<D: Borrow<Dataset>>(datasets: &[D],
+        options: &FillOptions) -> Result<Dataset, Box<dyn Error>> {
+    fill_with_progress(datasets, options, &mut |_, _| true)
+}
+
+pub fn fill_with_progress<D: Borrow<Dataset>>(datasets: &[D],
+        options: &FillOptions, progress: &mut ProgressCallback)
+        -> Result<Dataset, Box<dyn Error>> {
+    // cancellation is checked with each progress report
+    let mut progress = |fraction: f64, stage: &str| {
+        !is_cancelled(&options.cancellation) && progress(fraction, stage)
+    };
+    let progress: &mut ProgressCallback = &mut progress;
+
+    // datasets may be owned or borrowed
+    let datasets: Vec<&Dataset> =
+        datasets.iter().map(|x| x.borrow()).collect();
     let rasterband = datasets[0].rasterband(1)?;
     let no_data_value = rasterband.no_data_value();
 
     match rasterband.band_type() {
-        GDALDataType::GDT_Byte => _fill::<u8>(datasets, no_data_value),
+        GDALDataType::GDT_Byte =>
+            _fill::<u8>(&datasets, no_data_value, progress),
         GDALDataType::GDT_Int16 => 
-            _fill::<i16>(datasets, no_data_value),
+            _fill::<i16>(&datasets, no_data_value, progress),
         GDALDataType::GDT_UInt16 =>
-            _fill::<u16>(datasets, no_data_value),
+            _fill::<u16>(&datasets, no_data_value, progress),
         _ => unimplemented!(),
     }
 }
 
-fn _fill<T: Copy + FromPrimitive + GdalType + PartialEq>(
-        datasets: &[Dataset], no_data_option: Option<f64>)
-        -> Result<Dataset, Box<dyn Error>> {
+fn _fill<T: Copy + FromPrimitive + GdalType + PartialEq + Send + Sync>(
+        datasets: &[&Dataset], no_data_option: Option<f64>,
+        progress: &mut ProgressCallback) -> Result<Dataset, Box<dyn Error>> {
     let no_data_value = T::from_f64(no_data_option.unwrap_or(0.0));
-    let dataset = &datasets[0];
-
-    // read first dataset rasters
-    let mut rasters = Vec::new();
-    for i in 0..dataset.raster_count() {
-        let raster = dataset.rasterband(i+1)?.read_band_as::<T>()?;
-        rasters.push(raster);
-    }
+    let dataset = datasets[0];
 
-    // fill with remaining datasets
+    // validate datasets share a spatial reference system and dimensions
+    let crs = coordinate::identify_crs(&dataset.projection())?;
     for fill_dataset in datasets.iter().skip(1) {
-        // read fill dataset rasterbands
-        let mut fill_rasters = Vec::new();
-        for j in 0..fill_dataset.raster_count() {
-            let fill_raster = fill_dataset.rasterband(j+1)?
-                .read_band_as::<T>()?;
-            fill_rasters.push(fill_raster);
-        }
-
-        // iterate over pixels
-        let size = rasters[0].data.len();
-        for j in 0..size {
-            if fill_rasters[0].data.len() <= j {
-                break;
-            }
+        if coordinate::identify_crs(&fill_dataset.projection())? != crs {
+            return Err("fill dataset spatial reference systems differ"
+                .into());
+        }
 
-            // check if rasterband pixel is valid
-            let mut valid = false;
-            for raster in rasters.iter() {
-                valid = valid || raster.data[j] != no_data_value;
-            }
+        if fill_dataset.raster_size() != dataset.raster_size() {
+            return Err("fill dataset dimensions differ".into());
+        }
 
-            // copy pixels from fill_raster bands
-            if !valid {
-                for k in 0..rasters.len() {
-                    rasters[k].data[j] = fill_rasters[k].data[j];
-                }
-            }
+        if fill_dataset.raster_count() != dataset.raster_count() {
+            return Err("fill dataset rasterband counts differ".into());
         }
     }
 
     // open memory dataset
     let (width, height) = dataset.raster_size();
     let driver = Driver::get("Mem")?;
-    let mem_dataset = crate::init_dataset(&driver, "unreachable",
-        T::gdal_type(), width as isize, height as isize,
-        rasters.len() as isize, no_data_option)?;
+    let mem_dataset = crate::init_dataset_with_fill(&driver,
+        "unreachable", T::gdal_type(), width as isize, height as isize,
+        dataset.raster_count(), no_data_option, false)?;
 
     mem_dataset.set_geo_transform(
         &dataset.geo_transform()?)?;
     mem_dataset.set_projection(
         &dataset.projection())?;
+    crate::copy_all_metadata(dataset, &mem_dataset)?;
+
+    // read blocks of every dataset rasterband
+    let band_count: isize = datasets.iter().map(|x| x.raster_count()).sum();
+    let row_size = width * band_count as usize * std::mem::size_of::<T>();
+    let windows = parallel::block_windows(dataset, row_size)?;
+    let blocks = parallel::par_process_blocks(&windows, |i, window| {
+        report_progress(progress,
+            i as f64 / windows.len() as f64, "fill")?;
+
+        let mut rasters = Vec::new();
+        for fill_dataset in datasets.iter() {
+            let mut fill_rasters = Vec::new();
+            for j in 0..fill_dataset.raster_count() {
+                fill_rasters.push(fill_dataset.rasterband(j+1)?
+                    .read_as::<T>(window.0, window.1, window.1)?.data);
+            }
+
+            rasters.push(fill_rasters);
+        }
+
+        Ok(rasters)
+    }, |_, mut rasters| {
+        // fill invalid pixels with remaining datasets
+        let mut iter = rasters.drain(..);
+        let mut data = iter.next().unwrap_or_default();
+        for fill_rasters in iter {
+            for j in 0..data.first().map_or(0, |x| x.len()) {
+                // check if rasterband pixel is valid
+                let mut valid = false;
+                for raster in data.iter() {
+                    valid = valid || raster[j] != no_data_value;
+                }
+
+                // copy pixels from fill_raster bands
+                if !valid {
+                    for k in 0..data.len() {
+                        data[k][j] = fill_rasters[k][j];
+                    }
+                }
+            }
+        }
+
+        data
+    })?;
 
     // set rasterbands
-    for (i, raster) in rasters.iter().enumerate() {
-        mem_dataset.rasterband((i+1) as isize)?.write::<T>((0, 0),
-            (width, height), &raster)?;
+    for (window, block) in windows.iter().zip(blocks.into_iter()) {
+        for (i, raster) in block.into_iter().enumerate() {
+            let buffer = Buffer::new(window.1, raster);
+            mem_dataset.rasterband((i+1) as isize)?
+                .write::<T>(window.0, window.1, &buffer)?;
+        }
     }
 
+    report_progress(progress, 1.0, "complete")?;
     Ok(mem_dataset)
 }
 
+#[derive(Clone, Debug)]
+pub struct DatasetBuilder {
+    width: usize,
+    height: usize,
+    rasterband_count: isize,
+    gdal_type: GDALDataType::Type,
+    no_data_value: Option<f64>,
+    fill: bool,
+    projection: Option<String>,
+    geo_transform: Option<[f64; 6]>,
+}
+
+impl DatasetBuilder {
+    pub fn new(width: usize, height: usize) -> Self {
+        DatasetBuilder {
+            width,
+            height,
+            rasterband_count: 1,
+            gdal_type: GDALDataType::GDT_Byte,
+            no_data_value: None,
+            fill: true,
+            projection: None,
+            geo_transform: None,
+        }
+    }
+
+    pub fn bands(mut self, rasterband_count: isize) -> Self {
+        self.rasterband_count = rasterband_count;
+        self
+    }
+
+    pub fn gdal_type(mut self, gdal_type: GDALDataType::Type) -> Self {
+        self.gdal_type = gdal_type;
+        self
+    }
+
+    pub fn no_data(mut self, no_data_value: f64) -> Self {
+        self.no_data_value = Some(no_data_value);
+        self
+    }
+
+    pub fn fill(mut self, fill: bool) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    pub fn projection(mut self, projection: &str) -> Self {
+        self.projection = Some(projection.to_string());
+        self
+    }
+
+    pub fn geo_transform(mut self, geo_transform: [f64; 6]) -> Self {
+        self.geo_transform = Some(geo_transform);
+        self
+    }
+
+    pub fn like(self, dataset: &Dataset) -> Result<Self, Box<dyn Error>> {
+        Ok(self.projection(&dataset.projection())
+            .geo_transform(dataset.geo_transform()?))
+    }
+
+    pub fn create_mem(&self) -> Result<Dataset, Box<dyn Error>> {
+        let driver = Driver::get("Mem")?;
+        let dataset = init_dataset_with_fill(&driver, "unreachable",
+            self.gdal_type, self.width as isize, self.height as isize,
+            self.rasterband_count, self.no_data_value, self.fill)?;
+
+        self.georeference(&dataset)?;
+        Ok(dataset)
+    }
+
+    pub fn create_file(&self, path: &str, driver: &Driver,
+            creation_options: &[(&str, &str)])
+            -> Result<Dataset, Box<dyn Error>> {
+        let dataset = create_dataset(driver, path, self.gdal_type,
+            self.width as isize, self.height as isize,
+            self.rasterband_count, self.no_data_value, creation_options)?;
+
+        self.georeference(&dataset)?;
+        Ok(dataset)
+    }
+
+    fn georeference(&self, dataset: &Dataset) -> Result<(), Box<dyn Error>> {
+        if let Some(geo_transform) = &self.geo_transform {
+            dataset.set_geo_transform(geo_transform)?;
+        }
+
+        if let Some(projection) = &self.projection {
+            dataset.set_projection(projection)?;
+        }
+
+        Ok(())
+    }
+}
+
 pub fn init_dataset(driver: &Driver, filename: &str,
         gdal_type: GDALDataType::Type, width: isize, height: isize,
         rasterband_count: isize, no_data_value: Option<f64>)
         -> Result<Dataset, Box<dyn Error>> {
+    init_dataset_with_fill(driver, filename, gdal_type, width,
+        height, rasterband_count, no_data_value, true)
+}
+
+pub fn init_dataset_with_fill(driver: &Driver, filename: &str,
+        gdal_type: GDALDataType::Type, width: isize, height: isize,
+        rasterband_count: isize, no_data_value: Option<f64>, fill: bool)
+        -> Result<Dataset, Box<dyn Error>> {
     match gdal_type {
-        GDALDataType::GDT_Byte => _init_dataset::<u8>(driver,
-            filename, width, height, rasterband_count, no_data_value),
-        GDALDataType::GDT_Int16 => _init_dataset::<i16>(driver,
-            filename, width, height, rasterband_count, no_data_value),
-        GDALDataType::GDT_UInt16 => _init_dataset::<u16>(driver,
-            filename, width, height, rasterband_count, no_data_value),
-        GDALDataType::GDT_Float32 => _init_dataset::<f32>(driver,
-            filename, width, height, rasterband_count, no_data_value),
+        GDALDataType::GDT_Byte => _init_dataset::<u8>(driver, filename,
+            width, height, rasterband_count, no_data_value, fill),
+        GDALDataType::GDT_Int16 => _init_dataset::<i16>(driver, filename,
+            width, height, rasterband_count, no_data_value, fill),
+        GDALDataType::GDT_UInt16 => _init_dataset::<u16>(driver, filename,
+            width, height, rasterband_count, no_data_value, fill),
+        GDALDataType::GDT_Float32 => _init_dataset::<f32>(driver, filename,
+            width, height, rasterband_count, no_data_value, fill),
         _ => unimplemented!(),
     }
 }
 
 pub fn _init_dataset<T: Copy + FromPrimitive + GdalType>(
         driver: &Driver, filename: &str, width: isize, height: isize,
-        rasterband_count: isize, no_data_value: Option<f64>)
+        rasterband_count: isize, no_data_value: Option<f64>, fill: bool)
         -> Result<Dataset, Box<dyn Error>> {
     // create dataset
     let dataset = driver.create_with_band_type::<T>
         (filename, width, height, rasterband_count)?;
 
-    // if no_data value exists -> write to rasterband
+    // if no_data value exists -> set on rasterband
     if let Some(no_data_value) = no_data_value {
-        let (buf_width, buf_height) = (width as usize, height as usize);
-        let buffer = Buffer::new((buf_width, buf_height), 
-            vec!(T::from_f64(no_data_value); buf_width * buf_height));
-
-        // iterate over rasterbands
         for i in 0..rasterband_count {
-            // write no_data buffer to rasterband
             let rasterband = dataset.rasterband(i as isize + 1)?;
             rasterband.set_no_data_value(no_data_value)?;
 
-            rasterband.write::<T>((0, 0),
-                (buf_width, buf_height), &buffer)?;
+            // if requested -> fill rasterband with no_data value
+            if fill {
+                fill_rasterband(&rasterband, no_data_value)?;
+            }
         }
     }
 
     Ok(dataset)
 }
 
+fn fill_rasterband(rasterband: &RasterBand, value: f64)
+        -> Result<(), Box<dyn Error>> {
+    let rv = unsafe {
+        gdal_sys::GDALFillRaster(rasterband.c_rasterband(), value, 0.0)
+    };
+
+    if rv != CPLErr::CE_None {
+        return Err("failed to fill rasterband".into());
+    }
+
+    Ok(())
+}
+
+pub fn copy_valid_raster(src_dataset: &Dataset, src_index: isize,
+        src_window: (isize, isize), src_window_size: (usize, usize),
+        dst_dataset: &Dataset, dst_index: isize, 
+        dst_window: (isize, isize), dst_window_size: (usize, usize),
+        resampling: Resampling) -> Result<(), Box<dyn Error>> {
+    match src_dataset.rasterband(src_index)?.band_type() {
+        GDALDataType::GDT_Byte => _copy_valid_raster::<u8>(src_dataset, 
+            src_index, src_window, src_window_size, dst_dataset, 
+            dst_index, dst_window, dst_window_size, resampling),
+        GDALDataType::GDT_Int16 => _copy_valid_raster::<i16>(src_dataset, 
+            src_index, src_window, src_window_size, dst_dataset, 
+            dst_index, dst_window, dst_window_size, resampling),
+        GDALDataType::GDT_UInt16 => _copy_valid_raster::<u16>(src_dataset, 
+            src_index, src_window, src_window_size, dst_dataset, 
+            dst_index, dst_window, dst_window_size, resampling),
+        GDALDataType::GDT_Float32 => _copy_valid_raster::<f32>(src_dataset, 
+            src_index, src_window, src_window_size, dst_dataset, 
+            dst_index, dst_window, dst_window_size, resampling),
+        _ => unimplemented!(),
+    }
+}
+
+fn _copy_valid_raster<T: Copy + FromPrimitive + GdalType + PartialEq>(
+        src_dataset: &Dataset, src_index: isize,
+        src_window: (isize, isize), src_window_size: (usize, usize),
+        dst_dataset: &Dataset, dst_index: isize,
+        dst_window: (isize, isize), dst_window_size: (usize, usize),
+        resampling: Resampling) -> Result<(), Box<dyn Error>> {
+    // if no_data value is undefined -> all pixels are valid
+    let src_rasterband = src_dataset.rasterband(src_index)?;
+    let no_data_value = match src_rasterband.no_data_value() {
+        Some(value) => T::from_f64(value),
+        None => return _copy_raster::<T>(src_dataset, src_index,
+            src_window, src_window_size, dst_dataset, dst_index,
+            dst_window, dst_window_size, resampling),
+    };
+
+    // read source and destination rasterband windows
+    let buffer = read_resampled::<T>(&src_rasterband, src_window,
+        src_window_size, dst_window_size, resampling)?;
+
+    let dst_rasterband = dst_dataset.rasterband(dst_index)?;
+    let mut dst_buffer = dst_rasterband.read_as::<T>(dst_window,
+        dst_window_size, dst_window_size)?;
+
+    // overwrite destination pixels with valid source pixels
+    for (dst_pixel, pixel) in dst_buffer.data.iter_mut()
+            .zip(buffer.data.iter()) {
+        if *pixel != no_data_value {
+            *dst_pixel = *pixel;
+        }
+    }
+
+    dst_rasterband.write::<T>(dst_window, dst_window_size, &dst_buffer)?;
+    Ok(())
+}
+
+pub fn create_dataset(driver: &Driver, filename: &str,
+        gdal_type: GDALDataType::Type, width: isize, height: isize,
+        rasterband_count: isize, no_data_value: Option<f64>,
+        creation_options: &[(&str, &str)])
+        -> Result<Dataset, Box<dyn Error>> {
+    let c_filename = CString::new(filename)?;
+
+    // create dataset
+    let c_options = to_string_list(creation_options)?;
+    let c_dataset = unsafe {
+        let c_dataset = gdal_sys::GDALCreate(driver.c_driver(),
+            c_filename.as_ptr(), width as c_int, height as c_int,
+            rasterband_count as c_int, gdal_type, c_options);
+        gdal_sys::CSLDestroy(c_options);
+        c_dataset
+    };
+
+    if c_dataset.is_null() {
+        return Err(format!("failed to create dataset '{}'",
+            filename).into());
+    }
+
+    let dataset = unsafe { Dataset::from_c_dataset(c_dataset) };
+
+    // if no_data value exists -> fill rasterbands
+    if let Some(no_data_value) = no_data_value {
+        for i in 0..rasterband_count {
+            let rasterband = dataset.rasterband(i + 1)?;
+            rasterband.set_no_data_value(no_data_value)?;
+            fill_rasterband(&rasterband, no_data_value)?;
+        }
+    }
+
+    Ok(dataset)
+}
+
+#[derive(Clone, Debug)]
+pub struct CogOptions {
+    /// gdal compression method, for example "DEFLATE" or "LZW"
+    pub compression: String,
+    /// internal tile width and height in pixels
+    pub block_size: usize,
+    /// resampling method used to build overviews
+    pub overview_resampling: Resampling,
+    /// additional COG driver creation options
+    pub creation_options: Vec<(String, String)>,
+}
+
+impl Default for CogOptions {
+    fn default() -> Self {
+        CogOptions {
+            compression: "DEFLATE".to_string(),
+            block_size: 512,
+            overview_resampling: Resampling::Nearest,
+            creation_options: Vec::new(),
+        }
+    }
+}
+
+pub fn write_cog(dataset: &Dataset, path: &str, options: &CogOptions)
+        -> Result<(), Box<dyn Error>> {
+    // the COG driver handles tiling, overviews and ifd ordering
+    let driver = Driver::get("COG")?;
+    let block_size = options.block_size.to_string();
+    let mut creation_options = vec![
+        ("COMPRESS", options.compression.as_str()),
+        ("BLOCKSIZE", block_size.as_str()),
+        ("OVERVIEW_RESAMPLING", options.overview_resampling.to_gdal_name()),
+    ];
+
+    for (key, value) in options.creation_options.iter() {
+        creation_options.push((key.as_str(), value.as_str()));
+    }
+
+    let c_path = CString::new(path)?;
+    let c_options = to_string_list(&creation_options)?;
+    let c_dataset = unsafe {
+        let c_dataset = gdal_sys::GDALCreateCopy(driver.c_driver(),
+            c_path.as_ptr(), dataset.c_dataset(), 0, c_options,
+            None, std::ptr::null_mut());
+        gdal_sys::CSLDestroy(c_options);
+        c_dataset
+    };
+
+    if c_dataset.is_null() {
+        return Err(format!("failed to write cog '{}'", path).into());
+    }
+
+    // close dataset to flush the file
+    unsafe { gdal_sys::GDALClose(c_dataset) };
+    Ok(())
+}
+
+pub fn build_overviews(dataset: &Dataset, levels: &[usize],
+        resampling: Resampling) -> Result<(), Box<dyn Error>> {
+    if levels.iter().any(|x| *x < 2) {
+        return Err("overview levels must be at least 2".into());
+    }
+
+    let c_resampling = CString::new(resampling.to_gdal_name())?;
+    let mut factors: Vec<c_int> = levels.iter()
+        .map(|x| *x as c_int).collect();
+
+    let rv = unsafe {
+        gdal_sys::GDALBuildOverviews(dataset.c_dataset(),
+            c_resampling.as_ptr(), factors.len() as c_int,
+            factors.as_mut_ptr(), 0, std::ptr::null_mut(),
+            None, std::ptr::null_mut())
+    };
+
+    if rv != CPLErr::CE_None {
+        return Err("failed to build overviews".into());
+    }
+
+    Ok(())
+}
+
+pub fn overview_levels(dataset: &Dataset, min_size: usize) -> Vec<usize> {
+    // halve until the smaller dimension falls below min_size
+    let (width, height) = dataset.raster_size();
+    let mut levels = Vec::new();
+    let mut factor = 2;
+    while width.min(height) / factor >= min_size.max(1) {
+        levels.push(factor);
+        factor *= 2;
+    }
+
+    levels
+}
+
+fn to_string_list(options: &[(&str, &str)])
+        -> Result<*mut *mut c_char, Box<dyn Error>> {
+    let mut c_options = Vec::new();
+    for (key, value) in options.iter() {
+        c_options.push((CString::new(*key)?, CString::new(*value)?));
+    }
+
+    // build gdal string list of key=value pairs
+    let mut string_list = std::ptr::null_mut();
+    for (key, value) in c_options.iter() {
+        string_list = unsafe {
+            gdal_sys::CSLSetNameValue(string_list,
+                key.as_ptr(), value.as_ptr())
+        };
+    }
+
+    Ok(string_list)
+}
+
+fn to_argv(args: &[String]) -> Result<*mut *mut c_char, Box<dyn Error>> {
+    let mut c_args = Vec::new();
+    for arg in args.iter() {
+        c_args.push(CString::new(arg.as_str())?);
+    }
+
+    // build gdal string list of arguments
+    let mut string_list = std::ptr::null_mut();
+    for arg in c_args.iter() {
+        string_list = unsafe {
+            gdal_sys::CSLAddString(string_list, arg.as_ptr())
+        };
+    }
+
+    Ok(string_list)
+}
+
+pub fn band_descriptions(dataset: &Dataset)
+        -> Result<Vec<String>, Box<dyn Error>> {
+    let mut descriptions = Vec::new();
+    for i in 0..dataset.raster_count() {
+        let rasterband = dataset.rasterband(i+1)?;
+        let description = unsafe {
+            let rv = gdal_sys::GDALGetDescription(
+                rasterband.c_rasterband() as GDALMajorObjectH);
+            std::ffi::CStr::from_ptr(rv).to_string_lossy().into_owned()
+        };
+
+        descriptions.push(description);
+    }
+
+    Ok(descriptions)
+}
+
+pub fn select_bands(dataset: &Dataset, indices: &[isize])
+        -> Result<Dataset, Box<dyn Error>> {
+    // build virtual dataset referencing selected rasterbands
+    let mut args = Vec::new();
+    for index in indices.iter() {
+        args.push("-b".to_string());
+        args.push(index.to_string());
+    }
+
+    let c_args = to_argv(&args)?;
+    let c_filename = CString::new("")?;
+    let mut usage_error: c_int = 0;
+    let c_dataset = unsafe {
+        let options = gdal_sys::GDALBuildVRTOptionsNew(c_args,
+            std::ptr::null_mut());
+        gdal_sys::CSLDestroy(c_args);
+
+        let mut c_datasets = [dataset.c_dataset()];
+        let c_dataset = gdal_sys::GDALBuildVRT(c_filename.as_ptr(), 1,
+            c_datasets.as_mut_ptr(), std::ptr::null(), options,
+            &mut usage_error);
+        gdal_sys::GDALBuildVRTOptionsFree(options);
+        c_dataset
+    };
+
+    if c_dataset.is_null() || usage_error != 0 {
+        return Err("failed to select rasterbands".into());
+    }
+
+    Ok(unsafe { Dataset::from_c_dataset(c_dataset) })
+}
+
 pub fn copy_raster(src_dataset: &Dataset, src_index: isize,
         src_window: (isize, isize), src_window_size: (usize, usize),
         dst_dataset: &Dataset, dst_index: isize, 
         dst_window: (isize, isize), dst_window_size: (usize, usize))
         -> Result<(), Box<dyn Error>> {
+    copy_raster_resampled(src_dataset, src_index, src_window,
+        src_window_size, dst_dataset, dst_index, dst_window,
+        dst_window_size, Resampling::Nearest)
+}
+
+pub fn copy_raster_resampled(src_dataset: &Dataset, src_index: isize,
+        src_window: (isize, isize), src_window_size: (usize, usize),
+        dst_dataset: &Dataset, dst_index: isize, 
+        dst_window: (isize, isize), dst_window_size: (usize, usize),
+        resampling: Resampling) -> Result<(), Box<dyn Error>> {
     match src_dataset.rasterband(src_index)?.band_type() {
         GDALDataType::GDT_Byte => _copy_raster::<u8>(src_dataset, 
             src_index, src_window, src_window_size, dst_dataset, 
-            dst_index, dst_window, dst_window_size),
+            dst_index, dst_window, dst_window_size, resampling),
         GDALDataType::GDT_Int16 => _copy_raster::<i16>(src_dataset, 
             src_index, src_window, src_window_size, dst_dataset, 
-            dst_index, dst_window, dst_window_size),
+            dst_index, dst_window, dst_window_size, resampling),
         GDALDataType::GDT_UInt16 => _copy_raster::<u16>(src_dataset, 
             src_index, src_window, src_window_size, dst_dataset, 
-            dst_index, dst_window, dst_window_size),
+            dst_index, dst_window, dst_window_size, resampling),
         GDALDataType::GDT_Float32 => _copy_raster::<f32>(src_dataset, 
             src_index, src_window, src_window_size, dst_dataset, 
-            dst_index, dst_window, dst_window_size),
+            dst_index, dst_window, dst_window_size, resampling),
         _ => unimplemented!(),
     }
 }
@@ -235,11 +866,12 @@ fn _copy_raster<T: Copy + GdalType>(src_dataset: &Dataset,
         src_index: isize, src_window: (isize, isize), 
         src_window_size: (usize, usize), dst_dataset: &Dataset,
         dst_index: isize, dst_window: (isize, isize), 
-        dst_window_size: (usize, usize)) -> Result<(), Box<dyn Error>> {
+        dst_window_size: (usize, usize), resampling: Resampling)
+        -> Result<(), Box<dyn Error>> {
     // read rasterband data into buffer
     let src_rasterband = src_dataset.rasterband(src_index)?;
-    let buffer = src_rasterband.read_as::<T>(src_window,
-        src_window_size, dst_window_size)?;
+    let buffer = read_resampled::<T>(&src_rasterband, src_window,
+        src_window_size, dst_window_size, resampling)?;
 
     // write to new rasterband
     let dst_rasterband = dst_dataset.rasterband(dst_index)?;
@@ -252,3 +884,318 @@ fn _copy_raster<T: Copy + GdalType>(src_dataset: &Dataset,
 
     Ok(())
 }
+
+pub fn copy_metadata(src_dataset: &Dataset, dst_dataset: &Dataset)
+        -> Result<(), Box<dyn Error>> {
+    unsafe {
+        _copy_metadata(src_dataset.c_dataset() as GDALMajorObjectH,
+            dst_dataset.c_dataset() as GDALMajorObjectH)
+    }
+}
+
+pub fn copy_band_metadata(src_rasterband: &RasterBand,
+        dst_rasterband: &RasterBand) -> Result<(), Box<dyn Error>> {
+    unsafe {
+        let src_band = src_rasterband.c_rasterband();
+        let dst_band = dst_rasterband.c_rasterband();
+        let src = src_band as GDALMajorObjectH;
+        let dst = dst_band as GDALMajorObjectH;
+
+        // copy band description
+        gdal_sys::GDALSetDescription(dst, gdal_sys::GDALGetDescription(src));
+
+        // copy color interpretation
+        gdal_sys::GDALSetRasterColorInterpretation(dst_band,
+            gdal_sys::GDALGetRasterColorInterpretation(src_band));
+
+        // copy scale and offset if defined
+        let mut success: c_int = 0;
+        let scale = gdal_sys::GDALGetRasterScale(src_band, &mut success);
+        if success != 0 {
+            gdal_sys::GDALSetRasterScale(dst_band, scale);
+        }
+
+        let offset = gdal_sys::GDALGetRasterOffset(src_band, &mut success);
+        if success != 0 {
+            gdal_sys::GDALSetRasterOffset(dst_band, offset);
+        }
+
+        // copy category names
+        let categories = gdal_sys::GDALGetRasterCategoryNames(src_band);
+        if !categories.is_null() {
+            gdal_sys::GDALSetRasterCategoryNames(dst_band, categories);
+        }
+
+        _copy_metadata(src, dst)
+    }
+}
+
+pub fn copy_all_metadata(src_dataset: &Dataset, dst_dataset: &Dataset)
+        -> Result<(), Box<dyn Error>> {
+    copy_metadata(src_dataset, dst_dataset)?;
+
+    // copy metadata of corresponding rasterbands
+    let count = src_dataset.raster_count().min(dst_dataset.raster_count());
+    for i in 0..count {
+        copy_band_metadata(&src_dataset.rasterband(i+1)?,
+            &dst_dataset.rasterband(i+1)?)?;
+    }
+
+    Ok(())
+}
+
+unsafe fn _copy_metadata(src: GDALMajorObjectH, dst: GDALMajorObjectH)
+        -> Result<(), Box<dyn Error>> {
+    // copy default domain metadata key/value pairs
+    let metadata = gdal_sys::GDALGetMetadata(src, std::ptr::null());
+    if !metadata.is_null() && gdal_sys::GDALSetMetadata(dst,
+            metadata, std::ptr::null()) != CPLErr::CE_None {
+        return Err("failed to copy metadata".into());
+    }
+
+    Ok(())
+}
+
+pub(crate) unsafe fn get_object_metadata(object: GDALMajorObjectH)
+        -> Vec<(String, String)> {
+    // parse default domain metadata 'key=value' string list
+    let mut metadata = Vec::new();
+    let string_list = gdal_sys::GDALGetMetadata(object, std::ptr::null());
+    if string_list.is_null() {
+        return metadata;
+    }
+
+    let mut i = 0;
+    while !(*string_list.offset(i)).is_null() {
+        let item = std::ffi::CStr::from_ptr(*string_list.offset(i))
+            .to_string_lossy().into_owned();
+        if let Some(index) = item.find('=') {
+            metadata.push((item[..index].to_string(),
+                item[index+1..].to_string()));
+        }
+
+        i += 1;
+    }
+
+    metadata
+}
+
+pub(crate) unsafe fn set_object_metadata(object: GDALMajorObjectH,
+        metadata: &[(String, String)]) -> Result<(), Box<dyn Error>> {
+    for (key, value) in metadata.iter() {
+        let c_key = CString::new(key.as_str())?;
+        let c_value = CString::new(value.as_str())?;
+        if gdal_sys::GDALSetMetadataItem(object, c_key.as_ptr(),
+                c_value.as_ptr(), std::ptr::null()) != CPLErr::CE_None {
+            return Err(format!("failed to set metadata '{}'", key).into());
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) unsafe fn get_object_description(object: GDALMajorObjectH)
+        -> String {
+    let rv = gdal_sys::GDALGetDescription(object);
+    std::ffi::CStr::from_ptr(rv).to_string_lossy().into_owned()
+}
+
+pub(crate) unsafe fn set_object_description(object: GDALMajorObjectH,
+        description: &str) -> Result<(), Box<dyn Error>> {
+    let c_description = CString::new(description)?;
+    gdal_sys::GDALSetDescription(object, c_description.as_ptr());
+    Ok(())
+}
+
+pub fn read_resampled<T: Copy + GdalType>(rasterband: &RasterBand,
+        window: (isize, isize), window_size: (usize, usize),
+        size: (usize, usize), resampling: Resampling)
+        -> Result<Buffer<T>, Box<dyn Error>> {
+    _read_resampled(rasterband, window, window_size,
+        size, None, resampling)
+}
+
+pub(crate) fn read_resampled_rows<T: Copy + GdalType>(
+        rasterband: &RasterBand, window: (isize, isize),
+        window_size: (usize, usize), size: (usize, usize),
+        rows: (usize, usize), resampling: Resampling)
+        -> Result<Buffer<T>, Box<dyn Error>> {
+    // map destination rows onto a fractional source window so
+    // pixels match those of a read over the full window
+    let scale = window_size.1 as f64 / size.1 as f64;
+    let y_start = window.1 as f64 + rows.0 as f64 * scale;
+    let y_end = window.1 as f64 + (rows.0 + rows.1) as f64 * scale;
+
+    // compute integer source rows containing the fractional window
+    let src_y = (y_start.floor() as isize).max(window.1);
+    let src_end = (y_end.ceil() as isize)
+        .min(window.1 + window_size.1 as isize).max(src_y + 1);
+    let floating_window = (window.0 as f64, y_start,
+        window_size.0 as f64, y_end.min(src_end as f64) - y_start);
+
+    _read_resampled(rasterband, (window.0, src_y),
+        (window_size.0, (src_end - src_y) as usize), (size.0, rows.1),
+        Some(floating_window), resampling)
+}
+
+fn _read_resampled<T: Copy + GdalType>(rasterband: &RasterBand,
+        window: (isize, isize), window_size: (usize, usize),
+        size: (usize, usize), floating_window: Option<(f64, f64, f64, f64)>,
+        resampling: Resampling) -> Result<Buffer<T>, Box<dyn Error>> {
+    let pixel_count = size.0 * size.1;
+    let mut data: Vec<T> = Vec::with_capacity(pixel_count);
+
+    // initialize extra arguments to specify resampling algorithm
+    // and if defined -> the fractional source window
+    let (x_off, y_off, x_size, y_size) =
+        floating_window.unwrap_or((0.0, 0.0, 0.0, 0.0));
+    let mut extra_arg = gdal_sys::GDALRasterIOExtraArg {
+        nVersion: 1,
+        eResampleAlg: resampling.to_gdal(),
+        pfnProgress: None,
+        pProgressData: std::ptr::null_mut(),
+        bFloatingPointWindowValidity: floating_window.is_some() as c_int,
+        dfXOff: x_off,
+        dfYOff: y_off,
+        dfXSize: x_size,
+        dfYSize: y_size,
+    };
+
+    // read rasterband window into buffer
+    let rv = unsafe {
+        gdal_sys::GDALRasterIOEx(rasterband.c_rasterband(),
+            GDALRWFlag::GF_Read, window.0 as c_int, window.1 as c_int,
+            window_size.0 as c_int, window_size.1 as c_int,
+            data.as_mut_ptr() as *mut c_void, size.0 as c_int,
+            size.1 as c_int, T::gdal_type(), 0, 0, &mut extra_arg)
+    };
+
+    if rv != CPLErr::CE_None {
+        return Err("failed to read rasterband window".into());
+    }
+
+    unsafe { data.set_len(pixel_count) };
+    Ok(Buffer::new(size, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+    use gdal::raster::Buffer;
+    use gdal_sys::GDALDataType;
+
+    use std::path::Path;
+
+    #[test]
+    fn fill_cancelled() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let datasets = vec![Dataset::open(path).expect("open dataset"),
+            Dataset::open(path).expect("open dataset")];
+
+        let token = super::CancellationToken::new();
+        let options = super::FillOptions {
+            cancellation: Some(token.clone()),
+        };
+        assert!(super::fill_with_options(&datasets, &options).is_ok());
+
+        token.cancel();
+        let error = match super::fill_with_options(&datasets, &options) {
+            Ok(_) => panic!("fill completed after cancellation"),
+            Err(e) => e,
+        };
+
+        assert!(error.downcast_ref::<super::Cancelled>().is_some());
+    }
+
+    #[test]
+    fn fill_band_count() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let band_dataset = super::select_bands(&dataset, &[1])
+            .expect("select bands");
+
+        assert!(super::fill(&[&dataset, &band_dataset]).is_err());
+    }
+
+    #[test]
+    fn coverage() {
+        // nodata is compared after conversion to the rasterband type
+        let dataset = super::DatasetBuilder::new(4, 4)
+            .gdal_type(GDALDataType::GDT_Float32).no_data(0.1)
+            .create_mem().expect("create dataset");
+        dataset.rasterband(1).expect("rasterband")
+            .write::<f32>((0, 0), (2, 2), &Buffer::new((2, 2),
+                vec![1.0; 4])).expect("write pixels");
+
+        let coverage = super::get_coverage(&dataset).expect("coverage");
+        assert_eq!(coverage, 0.25);
+    }
+
+    #[test]
+    fn write_cog() {
+        use gdal::Metadata;
+
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // overviews are built until they fit within a single block
+        let options = super::CogOptions {
+            block_size: 64,
+            ..super::CogOptions::default()
+        };
+        super::write_cog(&dataset, "/vsimem/write_cog.tif", &options)
+            .expect("write cog");
+
+        let cog_dataset = Dataset::open(Path::new("/vsimem/write_cog.tif"))
+            .expect("open cog");
+        assert_eq!(cog_dataset.metadata_item("LAYOUT", "IMAGE_STRUCTURE"),
+            Some("COG".to_string()));
+        assert_eq!(cog_dataset.metadata_item("COMPRESSION",
+            "IMAGE_STRUCTURE"), Some("DEFLATE".to_string()));
+        assert_eq!(crate::serialize::overview_factors(&cog_dataset)
+            .expect("overview factors"), vec![2, 4]);
+    }
+
+    #[test]
+    fn build_overviews() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let driver = gdal::Driver::get("GTiff").expect("get driver");
+        let overview_dataset = dataset
+            .create_copy(&driver, "/vsimem/build_overviews.tif")
+            .expect("copy dataset");
+
+        // halve until the smaller dimension falls below 64 pixels
+        let levels = super::overview_levels(&overview_dataset, 64);
+        assert_eq!(levels, vec![2, 4]);
+        assert!(super::overview_levels(&overview_dataset, 512).is_empty());
+
+        super::build_overviews(&overview_dataset,
+            &levels, super::Resampling::Average).expect("build overviews");
+        assert_eq!(crate::serialize::overview_factors(&overview_dataset)
+            .expect("overview factors"), vec![2, 4]);
+
+        assert!(super::build_overviews(&overview_dataset,
+            &[1], super::Resampling::Average).is_err());
+    }
+
+    #[test]
+    fn coverage_cancelled() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let token = super::CancellationToken::new();
+        token.cancel();
+
+        let options = super::CoverageOptions {
+            cancellation: Some(token),
+        };
+        let error = match super::get_coverage_with_options(&dataset,
+                &options) {
+            Ok(_) => panic!("coverage completed after cancellation"),
+            Err(e) => e,
+        };
+
+        assert!(error.downcast_ref::<super::Cancelled>().is_some());
+    }
+}
@@ -0,0 +1,244 @@
+use gdal::{Dataset, Driver};
+use gdal::raster::{Buffer, GdalType};
+use gdal_sys::{CPLErr, GDALDataType};
+
+use crate::FromPrimitive;
+
+use std::error::Error;
+use std::os::raw::c_int;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QaScheme {
+    /// landsat collection 2 QA_PIXEL bit flags
+    LandsatC2,
+    /// sentinel-2 scene classification layer values
+    Sentinel2Scl,
+}
+
+#[derive(Clone, Debug)]
+pub struct QaOptions {
+    /// mask clouds, including dilated clouds and cirrus
+    pub cloud: bool,
+    /// mask cloud shadows
+    pub shadow: bool,
+    /// mask snow and ice
+    pub snow: bool,
+}
+
+impl Default for QaOptions {
+    fn default() -> Self {
+        QaOptions {
+            cloud: true,
+            shadow: true,
+            snow: true,
+        }
+    }
+}
+
+pub fn from_qa(dataset: &Dataset, qa_band: isize, scheme: QaScheme)
+        -> Result<Dataset, Box<dyn Error>> {
+    from_qa_with_options(dataset, qa_band, scheme, &QaOptions::default())
+}
+
+pub fn from_qa_with_options(dataset: &Dataset, qa_band: isize,
+        scheme: QaScheme, options: &QaOptions)
+        -> Result<Dataset, Box<dyn Error>> {
+    let values = dataset.rasterband(qa_band)?.read_band_as::<u16>()?.data;
+    let mask: Vec<u8> = values.iter()
+        .map(|x| is_masked(*x, scheme, options) as u8).collect();
+
+    // write mask into a byte dataset, flagged pixels are 1
+    let (width, height) = dataset.raster_size();
+    let mask_dataset = crate::DatasetBuilder::new(width, height)
+        .fill(false).like(dataset)?.create_mem()?;
+
+    let buffer = Buffer::new((width, height), mask);
+    mask_dataset.rasterband(1)?
+        .write::<u8>((0, 0), (width, height), &buffer)?;
+
+    Ok(mask_dataset)
+}
+
+fn is_masked(value: u16, scheme: QaScheme, options: &QaOptions) -> bool {
+    match scheme {
+        QaScheme::LandsatC2 => {
+            // bits: 0 fill, 1 dilated cloud, 2 cirrus,
+            // 3 cloud, 4 cloud shadow, 5 snow
+            let bit = |i: u16| value & (1 << i) != 0;
+            bit(0)
+                || (options.cloud && (bit(1) || bit(2) || bit(3)))
+                || (options.shadow && bit(4))
+                || (options.snow && bit(5))
+        },
+        QaScheme::Sentinel2Scl => match value {
+            0 | 1 => true,
+            3 => options.shadow,
+            8 | 9 | 10 => options.cloud,
+            11 => options.snow,
+            _ => false,
+        },
+    }
+}
+
+pub fn apply(dataset: &Dataset, mask_dataset: &Dataset)
+        -> Result<Dataset, Box<dyn Error>> {
+    if dataset.raster_size() != mask_dataset.raster_size() {
+        return Err("dataset and mask dimensions differ".into());
+    }
+
+    let masked: Vec<bool> = mask_dataset.rasterband(1)?
+        .read_band_as::<u8>()?.data.iter().map(|x| *x != 0).collect();
+
+    // copy dataset into memory and set masked pixels to nodata
+    let driver = Driver::get("Mem")?;
+    let masked_dataset = dataset.create_copy(&driver, "unreachable")?;
+    mask_pixels(&masked_dataset, &masked)?;
+
+    Ok(masked_dataset)
+}
+
+pub fn clip_to_geometry(dataset: &Dataset, geometry: &str, invert: bool)
+        -> Result<Dataset, Box<dyn Error>> {
+    // geometry is wkt or geojson in the dataset projection
+    let geometry = crate::transform::parse_geometry(geometry)?;
+    let mask_dataset = rasterize(dataset, &[geometry.0], &[1.0],
+        GDALDataType::GDT_Byte)?;
+
+    let masked: Vec<bool> = mask_dataset.rasterband(1)?
+        .read_band_as::<u8>()?.data.iter()
+        .map(|x| (*x == 0) != invert).collect();
+
+    // copy dataset into memory and set clipped pixels to nodata
+    let driver = Driver::get("Mem")?;
+    let clip_dataset = dataset.create_copy(&driver, "unreachable")?;
+    mask_pixels(&clip_dataset, &masked)?;
+
+    Ok(clip_dataset)
+}
+
+pub(crate) fn mask_pixels(dataset: &Dataset, masked: &[bool])
+        -> Result<(), Box<dyn Error>> {
+    for i in 0..dataset.raster_count() {
+        let rasterband = dataset.rasterband(i+1)?;
+        let no_data_value = match rasterband.no_data_value() {
+            Some(value) => value,
+            None => {
+                rasterband.set_no_data_value(0.0)?;
+                0.0
+            },
+        };
+
+        match rasterband.band_type() {
+            GDALDataType::GDT_Byte => _mask_pixels::<u8>(dataset,
+                i+1, masked, no_data_value)?,
+            GDALDataType::GDT_Int16 => _mask_pixels::<i16>(dataset,
+                i+1, masked, no_data_value)?,
+            GDALDataType::GDT_UInt16 => _mask_pixels::<u16>(dataset,
+                i+1, masked, no_data_value)?,
+            GDALDataType::GDT_Float32 => _mask_pixels::<f32>(dataset,
+                i+1, masked, no_data_value)?,
+            _ => unimplemented!(),
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn rasterize(dataset: &Dataset,
+        geometries: &[gdal_sys::OGRGeometryH], burn_values: &[f64],
+        gdal_type: GDALDataType::Type) -> Result<Dataset, Box<dyn Error>> {
+    // geometries are in the dataset projection, unburned pixels are 0
+    let (width, height) = dataset.raster_size();
+    let rasterize_dataset = crate::DatasetBuilder::new(width, height)
+        .gdal_type(gdal_type).fill(false).like(dataset)?.create_mem()?;
+
+    let mut band_list = [1 as c_int];
+    let mut geometries = geometries.to_vec();
+    let mut burn_values = burn_values.to_vec();
+    let error = unsafe {
+        gdal_sys::GDALRasterizeGeometries(rasterize_dataset.c_dataset(), 1,
+            band_list.as_mut_ptr(), geometries.len() as c_int,
+            geometries.as_mut_ptr(), None, std::ptr::null_mut(),
+            burn_values.as_mut_ptr(), std::ptr::null_mut(), None,
+            std::ptr::null_mut())
+    };
+
+    if error != CPLErr::CE_None {
+        return Err("failed to rasterize geometry".into());
+    }
+
+    Ok(rasterize_dataset)
+}
+
+fn _mask_pixels<T: Copy + FromPrimitive + GdalType>(dataset: &Dataset,
+        index: isize, masked: &[bool], no_data_value: f64)
+        -> Result<(), Box<dyn Error>> {
+    let no_data_value = T::from_f64(no_data_value);
+
+    let rasterband = dataset.rasterband(index)?;
+    let mut buffer = rasterband.read_band_as::<T>()?;
+    for (pixel, masked) in buffer.data.iter_mut().zip(masked.iter()) {
+        if *masked {
+            *pixel = no_data_value;
+        }
+    }
+
+    let (width, height) = dataset.raster_size();
+    let buffer = Buffer::new((width, height), buffer.data);
+    rasterband.write::<T>((0, 0), (width, height), &buffer)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QaOptions, QaScheme};
+
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn clip_to_geometry() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let geometry = crate::testing::WEST_POLYGON;
+        let read_pixel = |dataset: &Dataset, x: isize| {
+            dataset.rasterband(1).expect("get rasterband")
+                .read_as::<f64>((x, 100), (1, 1), (1, 1))
+                .expect("read pixel").data[0]
+        };
+
+        let no_data_value = dataset.rasterband(1).expect("get rasterband")
+            .no_data_value().unwrap_or(0.0);
+        let clip_dataset = super::clip_to_geometry(&dataset,
+            geometry, false).expect("clip dataset");
+        assert_eq!(read_pixel(&clip_dataset, 10), read_pixel(&dataset, 10));
+        assert_eq!(read_pixel(&clip_dataset, 200), no_data_value);
+
+        let clip_dataset = super::clip_to_geometry(&dataset,
+            geometry, true).expect("clip dataset");
+        assert_eq!(read_pixel(&clip_dataset, 10), no_data_value);
+        assert_eq!(read_pixel(&clip_dataset, 200),
+            read_pixel(&dataset, 200));
+    }
+
+    #[test]
+    fn is_masked() {
+        let options = QaOptions::default();
+        let landsat = QaScheme::LandsatC2;
+        assert!(!super::is_masked(0b0100_0000, landsat, &options));
+        assert!(super::is_masked(0b0000_1000, landsat, &options));
+        assert!(super::is_masked(0b0001_0000, landsat, &options));
+        assert!(super::is_masked(0b0000_0001, landsat, &options));
+
+        let options = QaOptions { snow: false, ..QaOptions::default() };
+        assert!(!super::is_masked(0b0010_0000, landsat, &options));
+
+        let sentinel2 = QaScheme::Sentinel2Scl;
+        assert!(super::is_masked(9, sentinel2, &options));
+        assert!(!super::is_masked(4, sentinel2, &options));
+        assert!(!super::is_masked(11, sentinel2, &options));
+    }
+}
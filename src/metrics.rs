@@ -0,0 +1,107 @@
+use gdal::Dataset;
+
+use std::error::Error;
+
+pub fn rmse(result: &Dataset, reference: &Dataset, index: isize)
+        -> Result<f64, Box<dyn Error>> {
+    let errors = errors(result, reference, index)?;
+    let sum: f64 = errors.iter().map(|x| x * x).sum();
+
+    Ok((sum / errors.len() as f64).sqrt())
+}
+
+pub fn mae(result: &Dataset, reference: &Dataset, index: isize)
+        -> Result<f64, Box<dyn Error>> {
+    let errors = errors(result, reference, index)?;
+    let sum: f64 = errors.iter().map(|x| x.abs()).sum();
+
+    Ok(sum / errors.len() as f64)
+}
+
+pub fn psnr(result: &Dataset, reference: &Dataset, index: isize)
+        -> Result<f64, Box<dyn Error>> {
+    // identical rasterbands have unbounded psnr
+    let rmse = rmse(result, reference, index)?;
+    if rmse == 0.0 {
+        return Ok(f64::INFINITY);
+    }
+
+    // compute peak value of reference rasterband
+    let rasterband = reference.rasterband(index)?;
+    let no_data_value = rasterband.no_data_value();
+    let peak = rasterband.read_band_as::<f64>()?.data.iter()
+        .filter(|x| Some(**x) != no_data_value)
+        .cloned().fold(f64::MIN, f64::max);
+
+    Ok(20.0 * (peak / rmse).log10())
+}
+
+fn errors(result: &Dataset, reference: &Dataset, index: isize)
+        -> Result<Vec<f64>, Box<dyn Error>> {
+    if result.raster_size() != reference.raster_size() {
+        return Err("result and reference dimensions differ".into());
+    }
+
+    // read rasterbands
+    let result_rasterband = result.rasterband(index)?;
+    let result_no_data_value = result_rasterband.no_data_value();
+    let result_buffer = result_rasterband.read_band_as::<f64>()?;
+
+    let reference_rasterband = reference.rasterband(index)?;
+    let reference_no_data_value = reference_rasterband.no_data_value();
+    let reference_buffer = reference_rasterband.read_band_as::<f64>()?;
+
+    // compute errors over pixels valid in both rasterbands
+    let errors: Vec<f64> = result_buffer.data.iter()
+        .zip(reference_buffer.data.iter())
+        .filter(|(x, y)| Some(**x) != result_no_data_value
+            && Some(**y) != reference_no_data_value)
+        .map(|(x, y)| x - y)
+        .collect();
+
+    if errors.is_empty() {
+        return Err("no valid pixels to compare".into());
+    }
+
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+    use gdal::raster::Buffer;
+    use gdal_sys::GDALDataType;
+
+    use std::path::Path;
+
+    #[test]
+    fn metrics() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        assert_eq!(super::rmse(&dataset, &dataset, 1).expect("rmse"), 0.0);
+        assert_eq!(super::mae(&dataset, &dataset, 1).expect("mae"), 0.0);
+        assert_eq!(super::psnr(&dataset, &dataset, 1).expect("psnr"),
+            f64::INFINITY);
+
+        // offset pixels by one in a Float32 copy to avoid saturation
+        let size = dataset.raster_size();
+        let offset_dataset = crate::DatasetBuilder::new(size.0, size.1)
+            .gdal_type(GDALDataType::GDT_Float32)
+            .create_mem().expect("create dataset");
+        let buffer = dataset.rasterband(1).expect("rasterband")
+            .read_band_as::<f64>().expect("read band");
+        let data: Vec<f64> = buffer.data.iter().map(|x| x + 1.0).collect();
+        let peak = buffer.data.iter().cloned().fold(f64::MIN, f64::max);
+        offset_dataset.rasterband(1).expect("rasterband")
+            .write::<f64>((0, 0), size, &Buffer::new(size, data))
+            .expect("write band");
+
+        let rmse = super::rmse(&offset_dataset, &dataset, 1).expect("rmse");
+        let mae = super::mae(&offset_dataset, &dataset, 1).expect("mae");
+        let psnr = super::psnr(&offset_dataset, &dataset, 1).expect("psnr");
+        assert!((rmse - 1.0).abs() < 1e-9);
+        assert!((mae - 1.0).abs() < 1e-9);
+        assert!((psnr - 20.0 * peak.log10()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,116 @@
+use gdal::Dataset;
+use gdal::raster::Buffer;
+
+use crate::calc::Expr;
+use crate::transform::VirtualMosaic;
+
+use std::error::Error;
+
+pub struct MosaicView<'a> {
+    mosaic: VirtualMosaic<'a>,
+    layers: Vec<(String, Expr)>,
+}
+
+impl<'a> MosaicView<'a> {
+    pub fn new(datasets: &'a [Dataset]) -> Result<Self, Box<dyn Error>> {
+        Ok(MosaicView {
+            mosaic: crate::transform::merge_virtual(datasets)?,
+            layers: Vec::new(),
+        })
+    }
+
+    pub fn dataset(&self) -> &Dataset {
+        &self.mosaic
+    }
+
+    pub fn layers(&self) -> Vec<&str> {
+        self.layers.iter().map(|x| x.0.as_str()).collect()
+    }
+
+    pub fn register(&mut self, name: &str, expression: &str)
+            -> Result<(), Box<dyn Error>> {
+        // band names resolve against the mosaic band descriptions
+        let descriptions = crate::band_descriptions(&self.mosaic)?;
+        let expr = crate::calc::parse(expression, &descriptions)?;
+        self.register_expr(name, expr)
+    }
+
+    pub fn register_expr(&mut self, name: &str, expr: Expr)
+            -> Result<(), Box<dyn Error>> {
+        let mut indices = Vec::new();
+        expr.bands(&mut indices);
+        for index in indices.iter() {
+            if *index < 1 || *index > self.mosaic.raster_count() {
+                return Err(format!("band {} out of range", index).into());
+            }
+        }
+
+        // registering an existing name replaces its expression
+        match self.layers.iter_mut().find(|x| x.0 == name) {
+            Some(layer) => layer.1 = expr,
+            None => self.layers.push((name.to_string(), expr)),
+        }
+
+        Ok(())
+    }
+
+    pub fn read_window(&self, name: &str, offset: (isize, isize),
+            size: (usize, usize)) -> Result<Buffer<f32>, Box<dyn Error>> {
+        let expr = match self.layers.iter().find(|x| x.0 == name) {
+            Some((_, expr)) => expr,
+            None => return Err(format!("unknown layer '{}'", name).into()),
+        };
+
+        let (width, height) = self.mosaic.raster_size();
+        if offset.0 < 0 || offset.1 < 0
+                || offset.0 as usize + size.0 > width
+                || offset.1 as usize + size.1 > height {
+            return Err("window exceeds mosaic dimensions".into());
+        }
+
+        // only source pixels within the window are fetched
+        let mut indices = Vec::new();
+        expr.bands(&mut indices);
+
+        let window = (offset, size);
+        let bands = crate::calc::read_bands(&self.mosaic, &indices, &window)?;
+        Ok(Buffer::new(size,
+            crate::calc::evaluate_bands(expr, &bands, &window)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn read_window() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let datasets = vec![Dataset::open(path).expect("open dataset")];
+
+        let mut view = super::MosaicView::new(&datasets)
+            .expect("create mosaic view");
+        view.register("ndvi", "(B2 - B1) / (B2 + B1)")
+            .expect("register layer");
+        assert_eq!(view.layers(), vec!["ndvi"]);
+        assert!(view.register("invalid", "B9").is_err());
+
+        // window values match band math over the full dataset
+        let ndvi_dataset = crate::indices::ndvi(&datasets[0], 1, 2)
+            .expect("ndvi");
+        let expected = ndvi_dataset.rasterband(1).expect("rasterband")
+            .read_as::<f32>((64, 32), (16, 8), (16, 8))
+            .expect("read window");
+        let buffer = view.read_window("ndvi", (64, 32), (16, 8))
+            .expect("read layer window");
+        assert_eq!(buffer.data.len(), 16 * 8);
+        for (x, y) in buffer.data.iter().zip(expected.data.iter()) {
+            assert!(x == y || (x.is_nan() && y.is_nan()));
+        }
+
+        assert!(view.read_window("ndvi", (250, 0), (16, 8)).is_err());
+        assert!(view.read_window("evi", (0, 0), (16, 8)).is_err());
+    }
+}
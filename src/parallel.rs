@@ -0,0 +1,204 @@
+use gdal::Dataset;
+
+use std::cell::Cell;
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "rayon")]
+use std::sync::{Arc, Mutex};
+
+/// preferred number of rows in each processed block
+const BLOCK_ROWS: usize = 256;
+/// default bytes of block data held in memory at once
+const DEFAULT_MEMORY_BUDGET: usize = 512 * 1024 * 1024;
+
+static MEMORY_BUDGET: AtomicUsize = AtomicUsize::new(DEFAULT_MEMORY_BUDGET);
+
+thread_local! {
+    static SCOPED_MEMORY_BUDGET: Cell<Option<usize>> = Cell::new(None);
+}
+
+pub(crate) type Window = ((isize, isize), (usize, usize));
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryBudget {
+    bytes: usize,
+}
+
+impl MemoryBudget {
+    pub fn from_bytes(bytes: usize) -> Self {
+        MemoryBudget { bytes }
+    }
+
+    pub fn from_megabytes(megabytes: usize) -> Self {
+        MemoryBudget { bytes: megabytes * 1024 * 1024 }
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn current() -> Self {
+        let bytes = SCOPED_MEMORY_BUDGET.with(|x| x.get())
+            .unwrap_or_else(|| MEMORY_BUDGET.load(Ordering::SeqCst));
+        MemoryBudget { bytes }
+    }
+
+    pub fn set_global(&self) {
+        MEMORY_BUDGET.store(self.bytes, Ordering::SeqCst);
+    }
+
+    pub fn scope<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        // restore the previous budget even if f panics
+        struct Guard(Option<usize>);
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                SCOPED_MEMORY_BUDGET.with(|x| x.set(self.0));
+            }
+        }
+
+        let _guard = Guard(SCOPED_MEMORY_BUDGET
+            .with(|x| x.replace(Some(self.bytes))));
+        f()
+    }
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        MemoryBudget { bytes: DEFAULT_MEMORY_BUDGET }
+    }
+}
+
+#[cfg(feature = "rayon")]
+static THREAD_POOL: Mutex<Option<Arc<rayon::ThreadPool>>> = Mutex::new(None);
+
+#[cfg(feature = "rayon")]
+pub fn set_threads(threads: usize) -> Result<(), Box<dyn Error>> {
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads).build()?;
+
+    *THREAD_POOL.lock().map_err(|_| "thread pool lock poisoned")? =
+        Some(Arc::new(thread_pool));
+    Ok(())
+}
+
+#[cfg(feature = "rayon")]
+fn thread_pool() -> Option<Arc<rayon::ThreadPool>> {
+    THREAD_POOL.lock().ok().and_then(|x| x.clone())
+}
+
+#[cfg(feature = "rayon")]
+pub fn threads() -> usize {
+    match thread_pool() {
+        Some(thread_pool) => thread_pool.current_num_threads(),
+        None => rayon::current_num_threads(),
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+pub fn threads() -> usize {
+    1
+}
+
+pub(crate) fn block_windows(dataset: &Dataset, bytes_per_row: usize)
+        -> Result<Vec<Window>, Box<dyn Error>> {
+    let (width, height) = dataset.raster_size();
+    let (_, block_height) = dataset.rasterband(1)?.block_size();
+    Ok(row_windows(width, height, block_rows(block_height, bytes_per_row)))
+}
+
+pub(crate) fn block_rows(block_height: usize, bytes_per_row: usize)
+        -> usize {
+    // align row strips with the natural block height
+    let block_height = block_height.max(1);
+    let rows = ((BLOCK_ROWS + block_height - 1) / block_height)
+        * block_height;
+
+    // fit each batch of in-flight blocks within the memory budget
+    let budget = MemoryBudget::current().bytes() / (threads() * 2);
+    let budget_rows = budget / bytes_per_row.max(1);
+    match budget_rows >= block_height {
+        true => rows.min(budget_rows / block_height * block_height),
+        false => budget_rows.max(1),
+    }
+}
+
+pub(crate) fn row_windows(width: usize, height: usize, rows: usize)
+        -> Vec<Window> {
+    (0..height).step_by(rows.max(1))
+        .map(|y| ((0, y as isize), (width, rows.min(height - y))))
+        .collect()
+}
+
+pub(crate) fn par_process_blocks<B, R, RF, PF>(windows: &[Window],
+        mut read: RF, process: PF) -> Result<Vec<R>, Box<dyn Error>>
+        where B: Send, R: Send,
+            RF: FnMut(usize, &Window) -> Result<B, Box<dyn Error>>,
+            PF: Fn(&Window, B) -> R + Sync {
+    let mut results = Vec::with_capacity(windows.len());
+    let batch_size = threads() * 2;
+    for (i, batch) in windows.chunks(batch_size).enumerate() {
+        // gdal datasets are not thread safe, read on the calling thread
+        let mut blocks = Vec::with_capacity(batch.len());
+        for (j, window) in batch.iter().enumerate() {
+            blocks.push((*window, read(i * batch_size + j, window)?));
+        }
+
+        results.extend(process_batch(blocks, &process));
+    }
+
+    Ok(results)
+}
+
+#[cfg(feature = "rayon")]
+fn process_batch<B, R, PF>(blocks: Vec<(Window, B)>, process: &PF) -> Vec<R>
+        where B: Send, R: Send, PF: Fn(&Window, B) -> R + Sync {
+    use rayon::prelude::*;
+
+    let f = || blocks.into_par_iter()
+        .map(|(window, block)| process(&window, block)).collect();
+
+    match thread_pool() {
+        Some(thread_pool) => thread_pool.install(f),
+        None => f(),
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+fn process_batch<B, R, PF>(blocks: Vec<(Window, B)>, process: &PF) -> Vec<R>
+        where B: Send, R: Send, PF: Fn(&Window, B) -> R + Sync {
+    blocks.into_iter()
+        .map(|(window, block)| process(&window, block)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn par_process_blocks() {
+        let rows = super::block_rows(100, 10);
+        assert_eq!(rows, 300);
+
+        let windows = super::row_windows(10, 600, rows);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[1], ((0, 300), (10, 300)));
+
+        let sums = super::par_process_blocks(&windows,
+                |i, window| Ok(vec![i; (window.1).0 * (window.1).1]),
+                |_, block| block.iter().sum::<usize>())
+            .expect("process blocks");
+        assert_eq!(sums, vec![0, 3000]);
+    }
+
+    #[test]
+    fn memory_budget() {
+        let budget = super::MemoryBudget::from_bytes(
+            super::threads() * 2 * 10 * 150);
+        budget.scope(|| {
+            assert_eq!(super::MemoryBudget::current(), budget);
+            assert_eq!(super::block_rows(100, 10), 100);
+            assert_eq!(super::block_rows(200, 10), 150);
+        });
+
+        assert_eq!(super::MemoryBudget::current(),
+            super::MemoryBudget::default());
+    }
+}
@@ -0,0 +1,246 @@
+use gdal::{Dataset, Driver};
+
+use crate::{CancellationToken, CogOptions, Resampling};
+use crate::coordinate::{Geocode, Target};
+use crate::transform::ReprojectOptions;
+
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// file within the output directory recording completed inputs
+static COMPLETION_LOG: &str = ".satmod_pipeline";
+
+#[derive(Clone, Debug)]
+pub enum Operation {
+    /// reproject into the target spatial reference system
+    Reproject(Target, ReprojectOptions),
+    /// resample to the pixel size
+    Resample(f64, Resampling),
+    /// set pixels outside of a wkt or geojson geometry, in the dataset
+    /// projection, to nodata
+    Clip(String),
+    /// evaluate a band expression into a Float32 rasterband
+    Calc(String),
+    /// split into tiles at the geocode precision, output names are
+    /// suffixed with each tile geocode
+    Split(Geocode, usize),
+    /// write each dataset as a cloud optimized geotiff
+    WriteCog(CogOptions),
+    /// write each dataset as a geotiff
+    WriteGTiff,
+}
+
+#[derive(Clone, Debug)]
+pub struct PipelineOptions {
+    /// directory receiving written datasets and the completion log
+    pub output_directory: PathBuf,
+    /// number of inputs processed concurrently
+    pub threads: usize,
+    /// skip inputs recorded as complete by a previous run
+    pub resume: bool,
+    /// token checked between inputs to abort the pipeline
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        PipelineOptions {
+            output_directory: PathBuf::from("."),
+            threads: 1,
+            resume: true,
+            cancellation: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PipelineReport {
+    pub completed: Vec<String>,
+    /// inputs completed by a previous run
+    pub skipped: Vec<String>,
+    /// inputs which failed and the error message
+    pub failed: Vec<(String, String)>,
+}
+
+pub fn run(paths: &[&str], operations: &[Operation],
+        options: &PipelineOptions)
+        -> Result<PipelineReport, Box<dyn Error>> {
+    if options.threads == 0 {
+        return Err("pipeline requires at least one thread".into());
+    }
+
+    std::fs::create_dir_all(&options.output_directory)?;
+
+    // read inputs completed by previous runs
+    let log_path = options.output_directory.join(COMPLETION_LOG);
+    let mut completed = Vec::new();
+    if options.resume && log_path.exists() {
+        for line in BufReader::new(File::open(&log_path)?).lines() {
+            completed.push(line?);
+        }
+    }
+
+    let log = Mutex::new(OpenOptions::new()
+        .create(true).append(true).open(&log_path)?);
+
+    // process inputs on a bounded number of threads
+    let report = Mutex::new(PipelineReport::default());
+    let index = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..options.threads.min(paths.len()) {
+            scope.spawn(|| loop {
+                let i = index.fetch_add(1, Ordering::SeqCst);
+                if i >= paths.len()
+                        || crate::is_cancelled(&options.cancellation) {
+                    break;
+                }
+
+                let path = paths[i];
+                if completed.iter().any(|x| x == path) {
+                    if let Ok(mut report) = report.lock() {
+                        report.skipped.push(path.to_string());
+                    }
+
+                    continue;
+                }
+
+                // errors are collected per input
+                let result = process(path, operations,
+                        &options.output_directory)
+                    .and_then(|_| record(&log, path))
+                    .map_err(|e| e.to_string());
+
+                log::debug!("pipeline input '{}' finished: {:?}",
+                    path, result);
+                if let Ok(mut report) = report.lock() {
+                    match result {
+                        Ok(()) => report.completed.push(path.to_string()),
+                        Err(e) => report.failed.push((path.to_string(), e)),
+                    }
+                }
+            });
+        }
+    });
+
+    if let Some(token) = &options.cancellation {
+        token.check("pipeline")?;
+    }
+
+    // order results by input
+    let mut report = report.into_inner()
+        .map_err(|_| "pipeline report lock poisoned")?;
+    let position = |path: &str| paths.iter().position(|x| *x == path);
+    report.completed.sort_by_key(|x| position(x.as_str()));
+    report.skipped.sort_by_key(|x| position(x.as_str()));
+    report.failed.sort_by_key(|x| position(x.0.as_str()));
+
+    Ok(report)
+}
+
+fn process(path: &str, operations: &[Operation], directory: &Path)
+        -> Result<(), Box<dyn Error>> {
+    let dataset = Dataset::open(Path::new(path))?;
+    let name = Path::new(path).file_stem().and_then(|x| x.to_str())
+        .ok_or("invalid input path")?.to_string();
+
+    // datasets flow between operations, splits fan out
+    let mut datasets = vec![(name, dataset)];
+    for operation in operations.iter() {
+        let mut outputs = Vec::new();
+        for (name, dataset) in datasets.into_iter() {
+            match operation {
+                Operation::Reproject(target, options) => outputs.push((name,
+                    crate::transform::reproject(&dataset, target, options)?)),
+                Operation::Resample(pixel_size, resampling) => outputs.push(
+                    (name, crate::transform::resample(&dataset,
+                        *pixel_size, *resampling)?)),
+                Operation::Clip(geometry) => outputs.push((name,
+                    crate::mask::clip_to_geometry(&dataset,
+                        geometry, false)?)),
+                Operation::Calc(expression) => outputs.push((name,
+                    crate::calc::evaluate(&dataset, expression)?)),
+                Operation::Split(geocode, precision) => {
+                    for result in crate::transform::split_iter(&dataset,
+                            *geocode, *precision)? {
+                        let result = result?;
+                        outputs.push((format!("{}_{}", name, result.geocode),
+                            result.dataset));
+                    }
+                },
+                Operation::WriteCog(options) => {
+                    let path = output_path(directory, &name)?;
+                    crate::write_cog(&dataset, &path, options)?;
+                    outputs.push((name, dataset));
+                },
+                Operation::WriteGTiff => {
+                    let path = output_path(directory, &name)?;
+                    let driver = Driver::get("GTiff")?;
+                    drop(dataset.create_copy(&driver, &path)?);
+                    outputs.push((name, dataset));
+                },
+            }
+        }
+
+        datasets = outputs;
+    }
+
+    Ok(())
+}
+
+fn output_path(directory: &Path, name: &str)
+        -> Result<String, Box<dyn Error>> {
+    let path = directory.join(format!("{}.tif", name));
+    Ok(path.to_str().ok_or("invalid output path")?.to_string())
+}
+
+fn record(log: &Mutex<File>, path: &str) -> Result<(), Box<dyn Error>> {
+    // inputs are recorded only after every operation succeeds
+    let mut log = log.lock().map_err(|_| "pipeline log lock poisoned")?;
+    writeln!(log, "{}", path)?;
+    log.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Operation, PipelineOptions};
+
+    use crate::Resampling;
+
+    #[test]
+    fn run() {
+        let directory = std::env::temp_dir().join("satmod_pipeline_test");
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let paths = ["fixtures/MCD43A4.h10v04.006.tif",
+            "fixtures/missing.tif"];
+        let operations = [
+            Operation::Resample(4343.56 * 2.0, Resampling::Average),
+            Operation::WriteGTiff,
+        ];
+        let options = PipelineOptions {
+            output_directory: directory.clone(),
+            threads: 2,
+            ..PipelineOptions::default()
+        };
+
+        let report = super::run(&paths, &operations, &options)
+            .expect("run pipeline");
+        assert_eq!(report.completed, vec![paths[0].to_string()]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, paths[1]);
+        assert!(directory.join("MCD43A4.h10v04.006.tif").exists());
+
+        // completed inputs are skipped when resuming
+        let report = super::run(&paths, &operations, &options)
+            .expect("resume pipeline");
+        assert_eq!(report.skipped, vec![paths[0].to_string()]);
+        assert!(report.completed.is_empty());
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,284 @@
+use byteorder::LittleEndian;
+use gdal::{Dataset, Driver};
+use gdal_sys::GDALMajorObjectH;
+use prost::Message;
+
+use std::error::Error;
+use std::io::{Read, Write};
+use std::os::raw::c_int;
+
+// messages mirror the documented schema in proto/dataset.proto
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MetadataEntry {
+    #[prost(string, tag = "1")]
+    pub key: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BandMessage {
+    #[prost(uint32, tag = "1")]
+    pub gdal_type: u32,
+    #[prost(string, tag = "2")]
+    pub description: String,
+    #[prost(message, repeated, tag = "3")]
+    pub metadata: Vec<MetadataEntry>,
+    #[prost(double, optional, tag = "4")]
+    pub no_data_value: Option<f64>,
+    #[prost(uint32, tag = "5")]
+    pub color_interpretation: u32,
+    #[prost(double, optional, tag = "6")]
+    pub scale: Option<f64>,
+    #[prost(double, optional, tag = "7")]
+    pub offset: Option<f64>,
+    #[prost(bytes = "vec", tag = "8")]
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DatasetMessage {
+    #[prost(uint32, tag = "1")]
+    pub width: u32,
+    #[prost(uint32, tag = "2")]
+    pub height: u32,
+    #[prost(double, repeated, tag = "3")]
+    pub transform: Vec<f64>,
+    #[prost(string, tag = "4")]
+    pub projection: String,
+    #[prost(message, repeated, tag = "5")]
+    pub metadata: Vec<MetadataEntry>,
+    #[prost(message, repeated, tag = "6")]
+    pub bands: Vec<BandMessage>,
+}
+
+fn to_entries(metadata: Vec<(String, String)>) -> Vec<MetadataEntry> {
+    metadata.into_iter()
+        .map(|(key, value)| MetadataEntry { key, value })
+        .collect()
+}
+
+fn from_entries(entries: &[MetadataEntry]) -> Vec<(String, String)> {
+    entries.iter()
+        .map(|x| (x.key.clone(), x.value.clone()))
+        .collect()
+}
+
+pub fn to_message(dataset: &Dataset)
+        -> Result<DatasetMessage, Box<dyn Error>> {
+    let (width, height) = dataset.raster_size();
+
+    let mut bands = Vec::new();
+    for i in 0..dataset.raster_count() {
+        let rasterband = dataset.rasterband(i+1)?;
+        let gdal_type = rasterband.band_type();
+        let data = crate::serialize::encode_raster::<LittleEndian>(
+            dataset, i+1, gdal_type, 0, height)?;
+
+        let band = unsafe {
+            let c_rasterband = rasterband.c_rasterband();
+            let object = c_rasterband as GDALMajorObjectH;
+
+            let mut success: c_int = 0;
+            let scale = gdal_sys::GDALGetRasterScale(c_rasterband,
+                &mut success);
+            let scale = if success != 0 { Some(scale) } else { None };
+
+            let offset = gdal_sys::GDALGetRasterOffset(c_rasterband,
+                &mut success);
+            let offset = if success != 0 { Some(offset) } else { None };
+
+            BandMessage {
+                gdal_type,
+                description: crate::get_object_description(object),
+                metadata: to_entries(crate::get_object_metadata(object)),
+                no_data_value: rasterband.no_data_value(),
+                color_interpretation:
+                    gdal_sys::GDALGetRasterColorInterpretation(c_rasterband),
+                scale,
+                offset,
+                data,
+            }
+        };
+
+        bands.push(band);
+    }
+
+    let metadata = unsafe {
+        crate::get_object_metadata(dataset.c_dataset() as GDALMajorObjectH)
+    };
+
+    Ok(DatasetMessage {
+        width: width as u32,
+        height: height as u32,
+        transform: dataset.geo_transform()?.to_vec(),
+        projection: dataset.projection(),
+        metadata: to_entries(metadata),
+        bands,
+    })
+}
+
+pub fn from_message(message: &DatasetMessage)
+        -> Result<Dataset, Box<dyn Error>> {
+    if message.bands.is_empty() {
+        return Err("message contains no rasterbands".into());
+    } else if message.transform.len() != 6 {
+        return Err("message geo transform is invalid".into());
+    }
+
+    // initialize dataset
+    let (width, height) = (message.width as usize, message.height as usize);
+    let driver = Driver::get("Mem")?;
+    let dataset = crate::init_dataset_with_fill(&driver, "unreachable",
+        message.bands[0].gdal_type, width as isize, height as isize,
+        message.bands.len() as isize, message.bands[0].no_data_value,
+        false)?;
+
+    let mut transform = [0.0f64; 6];
+    transform.copy_from_slice(&message.transform);
+    dataset.set_geo_transform(&transform)?;
+    dataset.set_projection(&message.projection)?;
+    unsafe {
+        crate::set_object_metadata(dataset.c_dataset() as GDALMajorObjectH,
+            &from_entries(&message.metadata))?;
+    }
+
+    // write rasterbands
+    for (i, band) in message.bands.iter().enumerate() {
+        if band.data.len() != width * height
+                * crate::serialize::type_size(band.gdal_type) {
+            return Err("rasterband payload size mismatch".into());
+        }
+
+        let index = (i + 1) as isize;
+        let rasterband = dataset.rasterband(index)?;
+        if let Some(no_data_value) = band.no_data_value {
+            rasterband.set_no_data_value(no_data_value)?;
+        }
+
+        unsafe {
+            let c_rasterband = rasterband.c_rasterband();
+            let object = c_rasterband as GDALMajorObjectH;
+            crate::set_object_description(object, &band.description)?;
+            crate::set_object_metadata(object,
+                &from_entries(&band.metadata))?;
+
+            gdal_sys::GDALSetRasterColorInterpretation(c_rasterband,
+                band.color_interpretation);
+            if let Some(scale) = band.scale {
+                gdal_sys::GDALSetRasterScale(c_rasterband, scale);
+            }
+
+            if let Some(offset) = band.offset {
+                gdal_sys::GDALSetRasterOffset(c_rasterband, offset);
+            }
+        }
+
+        crate::serialize::decode_raster::<LittleEndian>(&dataset, index,
+            band.gdal_type, 0, height, &band.data)?;
+    }
+
+    Ok(dataset)
+}
+
+pub fn write<T: Write>(dataset: &Dataset, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    let message = to_message(dataset)?;
+    writer.write_all(&message.encode_length_delimited_to_vec())?;
+    Ok(())
+}
+
+pub fn read<T: Read>(reader: &mut T) -> Result<Dataset, Box<dyn Error>> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    let message = DatasetMessage::decode_length_delimited(&buf[..])?;
+    from_message(&message)
+}
+
+pub fn from_binary<T: Read>(reader: &mut T)
+        -> Result<DatasetMessage, Box<dyn Error>> {
+    to_message(&crate::serialize::read(reader)?)
+}
+
+pub fn to_binary<T: Write>(message: &DatasetMessage, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    crate::serialize::write(&from_message(message)?, writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::{Dataset, Driver};
+    use gdal_sys::GDALMajorObjectH;
+
+    use std::io::Cursor;
+    use std::os::raw::c_int;
+    use std::path::Path;
+
+    #[test]
+    fn message_cycle() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // set nodata, scale, offset and description on a copy
+        let driver = Driver::get("Mem").expect("get driver");
+        let dataset = dataset.create_copy(&driver, "unreachable")
+            .expect("copy dataset");
+        let rasterband = dataset.rasterband(2).expect("read raster");
+        rasterband.set_no_data_value(7.0).expect("set nodata");
+        unsafe {
+            let c_rasterband = rasterband.c_rasterband();
+            gdal_sys::GDALSetRasterScale(c_rasterband, 0.5);
+            gdal_sys::GDALSetRasterOffset(c_rasterband, 1.0);
+            crate::set_object_description(c_rasterband as GDALMajorObjectH,
+                "nir").expect("set description");
+        }
+
+        let message = super::to_message(&dataset).expect("to message");
+        let mut buffer = Vec::new();
+        super::write(&dataset, &mut buffer).expect("write dataset");
+        let dataset2 = super::read(&mut Cursor::new(buffer))
+            .expect("read dataset");
+        assert_eq!(super::to_message(&dataset2).expect("to message"),
+            message);
+
+        let rasterband2 = dataset2.rasterband(2).expect("read raster2");
+        assert_eq!(rasterband2.no_data_value(), Some(7.0));
+        unsafe {
+            let c_rasterband = rasterband2.c_rasterband();
+            let mut success: c_int = 0;
+            assert_eq!(gdal_sys::GDALGetRasterScale(c_rasterband,
+                &mut success), 0.5);
+            assert_eq!(gdal_sys::GDALGetRasterOffset(c_rasterband,
+                &mut success), 1.0);
+            assert_eq!(crate::get_object_description(
+                c_rasterband as GDALMajorObjectH), "nir");
+        }
+
+        for i in 0..dataset.raster_count() {
+            let data = dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<u8>().expect("read band");
+            let data2 = dataset2.rasterband(i+1).expect("read raster2")
+                .read_band_as::<u8>().expect("read band2");
+            assert_eq!(data.data, data2.data);
+        }
+    }
+
+    #[test]
+    fn binary_cycle() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // converting through messages preserves the binary format
+        let mut buffer = Vec::new();
+        crate::serialize::write(&dataset, &mut buffer)
+            .expect("write dataset");
+        let message = super::from_binary(&mut Cursor::new(buffer.clone()))
+            .expect("from binary");
+
+        let mut buffer2 = Vec::new();
+        super::to_binary(&message, &mut buffer2).expect("to binary");
+        assert_eq!(buffer, buffer2);
+    }
+}
@@ -0,0 +1,225 @@
+use gdal::{Dataset, Driver};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::coordinate::Geocode;
+
+use std::error::Error;
+use std::path::Path;
+
+#[pyclass(name = "Dataset", unsendable)]
+pub struct PyDataset {
+    dataset: Dataset,
+}
+
+#[pymethods]
+impl PyDataset {
+    #[getter]
+    fn size(&self) -> (usize, usize) {
+        self.dataset.raster_size()
+    }
+
+    #[getter]
+    fn band_count(&self) -> isize {
+        self.dataset.raster_count()
+    }
+
+    #[getter]
+    fn geo_transform(&self) -> PyResult<Vec<f64>> {
+        Ok(self.dataset.geo_transform().map_err(to_py_err)?.to_vec())
+    }
+
+    #[getter]
+    fn projection(&self) -> String {
+        self.dataset.projection()
+    }
+
+    fn read_band(&self, index: isize) -> PyResult<Vec<f64>> {
+        let rasterband = self.dataset.rasterband(index)
+            .map_err(to_py_err)?;
+        Ok(rasterband.read_band_as::<f64>().map_err(to_py_err)?.data)
+    }
+}
+
+fn to_py_err<E: Into<Box<dyn Error>>>(error: E) -> PyErr {
+    PyRuntimeError::new_err(error.into().to_string())
+}
+
+struct Unsync<T>(T);
+
+// datasets are unsendable so python cannot reach them from other threads
+// while the borrow is held, allowing gdal calls to run without the gil
+unsafe impl<T> Send for Unsync<T> {}
+
+fn without_gil<T, F>(py: Python, f: F) -> PyResult<T>
+        where T: Send, F: FnOnce() -> Result<T, Box<dyn Error>> {
+    let f = Unsync(f);
+    py.allow_threads(move || (f.0)().map_err(|e| e.to_string()))
+        .map_err(to_py_err)
+}
+
+#[pyfunction]
+fn open(py: Python, path: &str) -> PyResult<PyDataset> {
+    let dataset = without_gil(py,
+        || Ok(Dataset::open(Path::new(path))?))?;
+    Ok(PyDataset { dataset })
+}
+
+#[pyfunction]
+fn coverage(py: Python, dataset: &PyDataset) -> PyResult<f64> {
+    without_gil(py, || crate::get_coverage(&dataset.dataset))
+}
+
+#[pyfunction]
+fn split(py: Python, dataset: &PyDataset, geocode: &str,
+        precision: usize) -> PyResult<Vec<(String, PyDataset)>> {
+    let geocode = match geocode {
+        "geohash" => Geocode::Geohash,
+        "quadtile" => Geocode::QuadTile,
+        _ => return Err(PyRuntimeError::new_err(
+            format!("unknown geocode '{}'", geocode))),
+    };
+
+    let tiles = without_gil(py, || {
+        let mut tiles = Vec::new();
+        for result in crate::transform::split_iter(&dataset.dataset,
+                geocode, precision)? {
+            let result = result?;
+            tiles.push((result.geocode, result.dataset));
+        }
+
+        Ok(tiles)
+    })?;
+
+    Ok(tiles.into_iter()
+        .map(|(geocode, dataset)| (geocode, PyDataset { dataset }))
+        .collect())
+}
+
+#[pyfunction]
+fn merge(py: Python, datasets: Vec<PyRef<PyDataset>>)
+        -> PyResult<PyDataset> {
+    // python owns each dataset, operate on borrowed references
+    let datasets: Vec<&Dataset> = datasets.iter()
+        .map(|x| &x.dataset).collect();
+    let dataset = without_gil(py,
+        || crate::transform::merge(&datasets))?;
+    Ok(PyDataset { dataset })
+}
+
+#[pyfunction]
+fn fill(py: Python, datasets: Vec<PyRef<PyDataset>>)
+        -> PyResult<PyDataset> {
+    let datasets: Vec<&Dataset> = datasets.iter()
+        .map(|x| &x.dataset).collect();
+    let dataset = without_gil(py, || crate::fill(&datasets))?;
+    Ok(PyDataset { dataset })
+}
+
+#[pyfunction]
+fn write(py: Python, dataset: &PyDataset, path: &str) -> PyResult<()> {
+    without_gil(py, || {
+        let driver = Driver::get("GTiff")?;
+        dataset.dataset.create_copy(&driver, path)?;
+        Ok(())
+    })
+}
+
+#[pyfunction]
+fn serialize<'py>(py: Python<'py>, dataset: &PyDataset)
+        -> PyResult<&'py PyBytes> {
+    let data = without_gil(py, || {
+        let mut data = Vec::new();
+        crate::serialize::write(&dataset.dataset, &mut data)?;
+        Ok(data)
+    })?;
+
+    Ok(PyBytes::new(py, &data))
+}
+
+#[pyfunction]
+fn deserialize(py: Python, data: &[u8]) -> PyResult<PyDataset> {
+    let dataset = without_gil(py,
+        || crate::serialize::read_from_slice(data))?;
+    Ok(PyDataset { dataset })
+}
+
+#[pymodule]
+fn st_image(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyDataset>()?;
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    m.add_function(wrap_pyfunction!(coverage, m)?)?;
+    m.add_function(wrap_pyfunction!(split, m)?)?;
+    m.add_function(wrap_pyfunction!(merge, m)?)?;
+    m.add_function(wrap_pyfunction!(fill, m)?)?;
+    m.add_function(wrap_pyfunction!(write, m)?)?;
+    m.add_function(wrap_pyfunction!(serialize, m)?)?;
+    m.add_function(wrap_pyfunction!(deserialize, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+
+    use std::path::Path;
+
+    const FIXTURE: &str = "fixtures/MCD43A4.h10v04.006.tif";
+
+    #[test]
+    fn open() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dataset = super::open(py, FIXTURE).expect("open dataset");
+            assert_eq!(dataset.size(), (256, 256));
+            assert_eq!(dataset.band_count(), 3);
+            assert!(super::open(py, "fixtures/missing.tif").is_err());
+        });
+    }
+
+    #[test]
+    fn merge_fill() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dataset = Py::new(py, super::open(py, FIXTURE)
+                .expect("open dataset")).expect("create dataset");
+
+            // results match operating on the source datasets directly
+            let source = gdal::Dataset::open(Path::new(FIXTURE))
+                .expect("open dataset");
+            let expected = crate::transform::merge(&[&source, &source])
+                .expect("merge");
+            let merged = super::merge(py,
+                    vec![dataset.borrow(py), dataset.borrow(py)])
+                .expect("merge");
+            assert_eq!(merged.size(), expected.raster_size());
+            assert_eq!(merged.read_band(1).expect("read band"),
+                expected.rasterband(1).expect("rasterband")
+                    .read_band_as::<f64>().expect("read band").data);
+
+            let filled = super::fill(py,
+                    vec![dataset.borrow(py), dataset.borrow(py)])
+                .expect("fill");
+            assert_eq!(filled.read_band(2).expect("read band"),
+                dataset.borrow(py).read_band(2).expect("read band"));
+        });
+    }
+
+    #[test]
+    fn serialize() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dataset = super::open(py, FIXTURE).expect("open dataset");
+            let data = super::serialize(py, &dataset).expect("serialize");
+            let copy = super::deserialize(py, data.as_bytes())
+                .expect("deserialize");
+            assert_eq!(copy.size(), dataset.size());
+            assert_eq!(copy.geo_transform().expect("geo transform"),
+                dataset.geo_transform().expect("geo transform"));
+            assert_eq!(copy.read_band(3).expect("read band"),
+                dataset.read_band(3).expect("read band"));
+            assert!(super::coverage(py, &dataset).expect("coverage") > 0.0);
+        });
+    }
+}
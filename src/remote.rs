@@ -0,0 +1,404 @@
+use gdal::Dataset;
+
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Clone, PartialEq)]
+pub enum Credentials {
+    /// gdal's default credential chain, e.g. environment variables
+    Default,
+    /// unsigned requests against public buckets
+    Anonymous,
+    /// aws access keys with an optional session token and region
+    Aws {
+        access_key_id: String,
+        secret_access_key: String,
+        session_token: Option<String>,
+        region: Option<String>,
+    },
+    /// google cloud service account credentials file
+    Gcs {
+        credentials_file: String,
+    },
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // secrets are redacted so credentials may be logged
+        match self {
+            Credentials::Default => f.write_str("Default"),
+            Credentials::Anonymous => f.write_str("Anonymous"),
+            Credentials::Aws { access_key_id, session_token, region, .. } =>
+                f.debug_struct("Aws")
+                    .field("access_key_id", access_key_id)
+                    .field("secret_access_key", &"<redacted>")
+                    .field("session_token",
+                        &session_token.as_ref().map(|_| "<redacted>"))
+                    .field("region", region)
+                    .finish(),
+            Credentials::Gcs { credentials_file } => f.debug_struct("Gcs")
+                .field("credentials_file", credentials_file)
+                .finish(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// retries after a failed open or range read
+    pub max_retries: u32,
+    /// delay before the first retry, doubled on each subsequent retry
+    pub retry_delay: Duration,
+    /// maximum duration of a single request
+    pub timeout: Duration,
+    /// maximum duration to establish a connection
+    pub connect_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            retry_delay: Duration::from_millis(500),
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+pub fn open_http(url: &str, policy: &RetryPolicy)
+        -> Result<Dataset, Box<dyn Error>> {
+    open_http_with_headers(url, policy, &[])
+}
+
+pub fn open_http_with_headers(url: &str, policy: &RetryPolicy,
+        headers: &[(&str, &str)]) -> Result<Dataset, Box<dyn Error>> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("'{}' is not an http url", url).into());
+    }
+
+    // gdal retries the open and failed range reads within the policy,
+    // options are scoped to the url so concurrent opens keep their own
+    // settings
+    let path = format!("/vsicurl/{}", url);
+    let options: ConfigOptions = vec![
+        ("GDAL_HTTP_TIMEOUT",
+            Some(policy.timeout.as_secs_f64().to_string())),
+        ("GDAL_HTTP_CONNECTTIMEOUT",
+            Some(policy.connect_timeout.as_secs_f64().to_string())),
+        ("GDAL_HTTP_MAX_RETRY", Some(policy.max_retries.to_string())),
+        ("GDAL_HTTP_RETRY_DELAY",
+            Some(policy.retry_delay.as_secs_f64().to_string())),
+        ("GDAL_HTTP_HEADERS", match headers.is_empty() {
+            true => None,
+            false => Some(encode_headers(headers)),
+        }),
+    ];
+
+    let mut guard = ConfigGuard::new();
+    set_path_options(&path, &options, &mut guard)?;
+    guard.set("GDAL_DISABLE_READDIR_ON_OPEN", Some("EMPTY_DIR"))?;
+
+    match Dataset::open(Path::new(&path)) {
+        Ok(dataset) => Ok(dataset),
+        Err(e) => {
+            log::debug!("failed to open '{}': {}", redact_url(url), e);
+            Err(e.into())
+        },
+    }
+}
+
+fn encode_headers(headers: &[(&str, &str)]) -> String {
+    // entries are quoted so values may contain commas
+    headers.iter().map(|(key, value)| {
+            let entry = format!("{}: {}", key, value);
+            format!("\"{}\"", entry.replace('\\', "\\\\")
+                .replace('"', "\\\""))
+        })
+        .collect::<Vec<String>>().join(",")
+}
+
+fn redact_url(url: &str) -> String {
+    // query parameters may hold presigned tokens
+    match url.split_once('?') {
+        Some((base, _)) => format!("{}?<redacted>", base),
+        None => url.to_string(),
+    }
+}
+
+pub fn open_remote(url: &str, credentials: &Credentials)
+        -> Result<Dataset, Box<dyn Error>> {
+    let path = vsi_path(url)?;
+
+    // credentials are scoped to the bucket rather than the process
+    let options = if path.starts_with("/vsis3/") {
+        s3_options(credentials)?
+    } else if path.starts_with("/vsigs/") {
+        gs_options(credentials)?
+    } else if *credentials != Credentials::Default {
+        return Err(format!("credentials unsupported for '{}'", url).into());
+    } else {
+        Vec::new()
+    };
+
+    // older gdal drops thread-local credentials when the open returns,
+    // leaving later range reads unsigned
+    if !PATH_OPTIONS && *credentials != Credentials::Default {
+        return Err("credentials require gdal >= 3.6".into());
+    }
+
+    let mut guard = ConfigGuard::new();
+    set_path_options(&bucket_prefix(&path), &options, &mut guard)?;
+
+    // avoid listing remote directories when opening single files
+    guard.set("GDAL_DISABLE_READDIR_ON_OPEN", Some("EMPTY_DIR"))?;
+    Ok(Dataset::open(Path::new(&path))?)
+}
+
+pub fn vsi_path(url: &str) -> Result<String, Box<dyn Error>> {
+    match url.split_once("://") {
+        Some(("s3", key)) => Ok(format!("/vsis3/{}", key)),
+        Some(("gs", key)) | Some(("gcs", key)) =>
+            Ok(format!("/vsigs/{}", key)),
+        Some(("http", _)) | Some(("https", _)) =>
+            Ok(format!("/vsicurl/{}", url)),
+        Some((scheme, _)) =>
+            Err(format!("unsupported url scheme '{}'", scheme).into()),
+        None if url.starts_with("/vsi") => Ok(url.to_string()),
+        None => Err(format!("'{}' is not a remote url", url).into()),
+    }
+}
+
+fn bucket_prefix(path: &str) -> String {
+    // '/vsis3/bucket/key' -> '/vsis3/bucket/'
+    let mut parts = path.splitn(4, '/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(""), Some(handler), Some(bucket)) if !bucket.is_empty() =>
+            format!("/{}/{}/", handler, bucket),
+        _ => path.to_string(),
+    }
+}
+
+type ConfigOptions = Vec<(&'static str, Option<String>)>;
+
+fn s3_options(credentials: &Credentials)
+        -> Result<ConfigOptions, Box<dyn Error>> {
+    // every key is set, clearing values left by earlier credentials
+    let (no_sign, keys) = match credentials {
+        Credentials::Default => (None, None),
+        Credentials::Anonymous => (Some("YES"), None),
+        Credentials::Aws { access_key_id, secret_access_key,
+                session_token, region } => (Some("NO"),
+            Some((access_key_id, secret_access_key, session_token, region))),
+        Credentials::Gcs { .. } =>
+            return Err("gcs credentials unsupported for s3".into()),
+    };
+
+    Ok(vec![
+        ("AWS_NO_SIGN_REQUEST", no_sign.map(|x| x.to_string())),
+        ("AWS_ACCESS_KEY_ID", keys.map(|x| x.0.clone())),
+        ("AWS_SECRET_ACCESS_KEY", keys.map(|x| x.1.clone())),
+        ("AWS_SESSION_TOKEN", keys.and_then(|x| x.2.clone())),
+        ("AWS_REGION", keys.and_then(|x| x.3.clone())),
+    ])
+}
+
+fn gs_options(credentials: &Credentials)
+        -> Result<ConfigOptions, Box<dyn Error>> {
+    let (no_sign, credentials_file) = match credentials {
+        Credentials::Default => (None, None),
+        Credentials::Anonymous => (Some("YES"), None),
+        Credentials::Gcs { credentials_file } =>
+            (Some("NO"), Some(credentials_file.clone())),
+        Credentials::Aws { .. } =>
+            return Err("aws credentials unsupported for gcs".into()),
+    };
+
+    Ok(vec![
+        ("GS_NO_SIGN_REQUEST", no_sign.map(|x| x.to_string())),
+        ("GOOGLE_APPLICATION_CREDENTIALS", credentials_file),
+    ])
+}
+
+// gdal >= 3.6 keeps options for requests under the path prefix, so
+// range reads after open observe them without touching global state
+const PATH_OPTIONS: bool = cfg!(any(major_ge_4, all(major_is_3, minor_ge_6)));
+
+#[cfg(any(major_ge_4, all(major_is_3, minor_ge_6)))]
+fn set_path_options(prefix: &str, options: &[(&str, Option<String>)],
+        _: &mut ConfigGuard) -> Result<(), Box<dyn Error>> {
+    let c_prefix = CString::new(prefix)?;
+    for (key, value) in options.iter() {
+        let c_key = CString::new(*key)?;
+        let c_value = value.as_deref().map(CString::new).transpose()?;
+
+        // a missing value clears the option for the prefix
+        unsafe {
+            gdal_sys::VSISetPathSpecificOption(c_prefix.as_ptr(),
+                c_key.as_ptr(), c_value.as_ref()
+                    .map_or(std::ptr::null(), |x| x.as_ptr()));
+        }
+    }
+
+    Ok(())
+}
+
+// older gdal applies options to the opening thread until the guard
+// drops, so later range reads fall back to the global options
+#[cfg(not(any(major_ge_4, all(major_is_3, minor_ge_6))))]
+fn set_path_options(_: &str, options: &[(&str, Option<String>)],
+        guard: &mut ConfigGuard) -> Result<(), Box<dyn Error>> {
+    for (key, value) in options.iter() {
+        guard.set(key, value.as_deref())?;
+    }
+
+    Ok(())
+}
+
+// thread-local gdal configuration options, restored when dropped
+pub(crate) struct ConfigGuard {
+    previous: Vec<(CString, Option<CString>)>,
+}
+
+impl ConfigGuard {
+    pub(crate) fn new() -> ConfigGuard {
+        ConfigGuard { previous: Vec::new() }
+    }
+
+    pub(crate) fn set(&mut self, key: &str, value: Option<&str>)
+            -> Result<(), Box<dyn Error>> {
+        // a missing value defers to the global option
+        let c_key = CString::new(key)?;
+        let c_value = value.map(CString::new).transpose()?;
+
+        unsafe {
+            let c_previous = gdal_sys::CPLGetThreadLocalConfigOption(
+                c_key.as_ptr(), std::ptr::null());
+            let previous = match c_previous.is_null() {
+                true => None,
+                false => Some(CStr::from_ptr(c_previous).to_owned()),
+            };
+
+            gdal_sys::CPLSetThreadLocalConfigOption(c_key.as_ptr(),
+                c_value.as_ref().map_or(std::ptr::null(), |x| x.as_ptr()));
+            self.previous.push((c_key, previous));
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for ConfigGuard {
+    fn drop(&mut self) {
+        // restore in reverse so repeated keys regain their first value
+        for (c_key, c_value) in self.previous.drain(..).rev() {
+            unsafe {
+                gdal_sys::CPLSetThreadLocalConfigOption(c_key.as_ptr(),
+                    c_value.as_ref().map_or(std::ptr::null(),
+                        |x| x.as_ptr()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn vsi_path() {
+        assert_eq!(super::vsi_path("s3://bucket/key.tif")
+            .expect("s3 path"), "/vsis3/bucket/key.tif");
+        assert_eq!(super::vsi_path("gs://bucket/dir/key.tif")
+            .expect("gs path"), "/vsigs/bucket/dir/key.tif");
+        assert_eq!(super::vsi_path("https://host/key.tif")
+            .expect("http path"), "/vsicurl/https://host/key.tif");
+        assert!(super::vsi_path("ftp://host/key.tif").is_err());
+        assert!(super::vsi_path("key.tif").is_err());
+    }
+
+    #[test]
+    fn open_remote_credentials() {
+        let credentials = super::Credentials::Anonymous;
+        assert!(super::open_remote("https://host/key.tif",
+            &credentials).is_err());
+
+        // older gdal rejects credentials before sending any request
+        if !super::PATH_OPTIONS {
+            let error = match super::open_remote("s3://bucket/key.tif",
+                    &credentials) {
+                Ok(_) => panic!("credentials accepted on gdal < 3.6"),
+                Err(e) => e,
+            };
+
+            assert!(error.to_string().contains("gdal >= 3.6"));
+        }
+    }
+
+    #[test]
+    fn bucket_prefix() {
+        assert_eq!(super::bucket_prefix("/vsis3/bucket/dir/key.tif"),
+            "/vsis3/bucket/");
+        assert_eq!(super::bucket_prefix("/vsigs/bucket/key.tif"),
+            "/vsigs/bucket/");
+    }
+
+    #[test]
+    fn credentials_debug() {
+        let credentials = super::Credentials::Aws {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI".to_string(),
+            session_token: Some("FQoGZXIvYXdzE".to_string()),
+            region: None,
+        };
+
+        let debug = format!("{:?}", credentials);
+        assert!(debug.contains("AKIDEXAMPLE"));
+        assert!(!debug.contains("wJalrXUtnFEMI"));
+        assert!(!debug.contains("FQoGZXIvYXdzE"));
+    }
+
+    #[test]
+    fn config_guard() {
+        let key = "ST_IMAGE_CONFIG_GUARD_TEST";
+        let get = || unsafe {
+            let c_key = std::ffi::CString::new(key).expect("key");
+            let c_value = gdal_sys::CPLGetConfigOption(c_key.as_ptr(),
+                std::ptr::null());
+            match c_value.is_null() {
+                true => None,
+                false => Some(std::ffi::CStr::from_ptr(c_value)
+                    .to_string_lossy().into_owned()),
+            }
+        };
+
+        {
+            let mut guard = super::ConfigGuard::new();
+            guard.set(key, Some("a")).expect("set option");
+            guard.set(key, Some("b")).expect("set option");
+            assert_eq!(get().as_deref(), Some("b"));
+
+            // other threads observe the global value
+            assert_eq!(std::thread::spawn(move || get()).join()
+                .expect("join thread"), None);
+        }
+
+        assert_eq!(get(), None);
+    }
+
+    #[test]
+    fn encode_headers() {
+        assert_eq!(super::encode_headers(&[("Authorization", "Bearer a,b"),
+                ("X-Quote", "\"")]),
+            "\"Authorization: Bearer a,b\",\"X-Quote: \\\"\"");
+    }
+
+    #[test]
+    fn redact_url() {
+        assert_eq!(super::redact_url(
+                "https://host/key.tif?X-Amz-Signature=abc"),
+            "https://host/key.tif?<redacted>");
+        assert_eq!(super::redact_url("https://host/key.tif"),
+            "https://host/key.tif");
+    }
+}
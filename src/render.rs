@@ -0,0 +1,126 @@
+use gdal::{Dataset, Driver};
+use gdal::raster::Buffer;
+use gdal_sys::GDALDataType;
+
+use std::error::Error;
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static THUMBNAIL_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    fn driver(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "PNG",
+            ImageFormat::Jpeg => "JPEG",
+        }
+    }
+}
+
+pub fn thumbnail(dataset: &Dataset, bands: &[isize], max_size: usize,
+        format: ImageFormat) -> Result<Vec<u8>, Box<dyn Error>> {
+    if bands.len() != 1 && bands.len() != 3 {
+        return Err("thumbnails require one or three bands".into());
+    } else if max_size == 0 {
+        return Err("thumbnail size must be positive".into());
+    }
+
+    // compute downsampled dimensions preserving aspect ratio
+    let (width, height) = dataset.raster_size();
+    let scale = (max_size as f64 / width.max(height) as f64).min(1.0);
+    let size = (((width as f64 * scale).round() as usize).max(1),
+        ((height as f64 * scale).round() as usize).max(1));
+
+    // stretch each band into a byte dataset
+    let driver = Driver::get("Mem")?;
+    let thumbnail_dataset = crate::init_dataset_with_fill(&driver,
+        "unreachable", GDALDataType::GDT_Byte, size.0 as isize,
+        size.1 as isize, bands.len() as isize, None, false)?;
+
+    for (i, index) in bands.iter().enumerate() {
+        let rasterband = dataset.rasterband(*index)?;
+        let no_data_value = rasterband.no_data_value();
+        let buffer = rasterband.read_as::<f64>((0, 0),
+            (width, height), size)?;
+
+        let data = stretch(&buffer.data, no_data_value, 0.02, 0.98);
+        let buffer = Buffer::new(size, data);
+        thumbnail_dataset.rasterband((i + 1) as isize)?
+            .write::<u8>((0, 0), size, &buffer)?;
+    }
+
+    encode(&thumbnail_dataset, format)
+}
+
+fn stretch(values: &[f64], no_data_value: Option<f64>,
+        low: f64, high: f64) -> Vec<u8> {
+    // compute percentile bounds of valid values
+    let mut valid: Vec<f64> = values.iter().cloned()
+        .filter(|x| Some(*x) != no_data_value && !x.is_nan()).collect();
+    if valid.is_empty() {
+        return vec![0; values.len()];
+    }
+
+    valid.sort_by(|a, b| a.partial_cmp(b)
+        .unwrap_or(std::cmp::Ordering::Equal));
+    let min = valid[(low * (valid.len() - 1) as f64).round() as usize];
+    let max = valid[(high * (valid.len() - 1) as f64).round() as usize];
+    let range = (max - min).max(std::f64::EPSILON);
+
+    // linearly scale values, nodata pixels are black
+    values.iter().map(|x| {
+        if Some(*x) == no_data_value || x.is_nan() {
+            0
+        } else {
+            ((x - min) / range * 255.0).max(0.0).min(255.0).round() as u8
+        }
+    }).collect()
+}
+
+fn encode(dataset: &Dataset, format: ImageFormat)
+        -> Result<Vec<u8>, Box<dyn Error>> {
+    // write image to a unique in-memory file
+    let path = format!("/vsimem/satmod_thumbnail_{}",
+        THUMBNAIL_COUNT.fetch_add(1, Ordering::SeqCst));
+    let driver = Driver::get(format.driver())?;
+    drop(dataset.create_copy(&driver, &path)?);
+
+    // take ownership of the in-memory file buffer
+    let c_path = CString::new(path)?;
+    let mut length = 0;
+    let data = unsafe {
+        let buffer = gdal_sys::VSIGetMemFileBuffer(c_path.as_ptr(),
+            &mut length, 1);
+        if buffer.is_null() {
+            return Err("failed to encode thumbnail".into());
+        }
+
+        let data = std::slice::from_raw_parts(buffer,
+            length as usize).to_vec();
+        gdal_sys::VSIFree(buffer as *mut c_void);
+        data
+    };
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn stretch() {
+        let values: Vec<f64> = (0..101).map(|x| x as f64).collect();
+        let data = super::stretch(&values, Some(50.0), 0.0, 1.0);
+
+        assert_eq!(data[0], 0);
+        assert_eq!(data[50], 0);
+        assert_eq!(data[100], 255);
+        assert!(data[25] > 50 && data[25] < 80);
+    }
+}
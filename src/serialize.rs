@@ -1,16 +1,207 @@
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, ByteOrder, LittleEndian,
+    ReadBytesExt, WriteBytesExt};
 use gdal::{Dataset, Driver};
-use gdal::raster::Buffer;
-use gdal_sys::GDALDataType;
+use gdal::raster::{Buffer, RasterBand};
+use gdal_sys::{CPLErr, GDALDataType, GDALMajorObjectH, GDALRWFlag};
 
 use std::error::Error;
-use std::io::{Read, Write};
+use std::ffi::CString;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::os::raw::{c_int, c_void};
+
+/// leading bytes identifying a serialized dataset
+const MAGIC: [u8; 4] = *b"STIM";
+/// header format version, incremented whenever header fields change
+const FORMAT_VERSION: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde",
+    derive(serde::Serialize, serde::Deserialize))]
+pub enum Compression {
+    None,
+    Zstd,
+    Lz4,
+    Deflate,
+}
+
+impl Compression {
+    fn to_u8(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zstd => 1,
+            Compression::Lz4 => 2,
+            Compression::Deflate => 3,
+        }
+    }
+
+    fn from_u8(value: u8) -> Result<Compression, Box<dyn Error>> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Zstd),
+            2 => Ok(Compression::Lz4),
+            3 => Ok(Compression::Deflate),
+            x => Err(format!("unknown compression codec '{}'", x).into()),
+        }
+    }
+
+    fn compress(self, data: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Zstd => zstd_compress(&data),
+            Compression::Lz4 => lz4_compress(&data),
+            Compression::Deflate => deflate_compress(&data),
+        }
+    }
+
+    fn decompress(self, data: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Zstd => zstd_decompress(&data),
+            Compression::Lz4 => lz4_decompress(&data),
+            Compression::Deflate => deflate_decompress(&data),
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_compress(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(zstd::stream::encode_all(data, 0)?)
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decompress(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(zstd::stream::decode_all(data)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_compress(_: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("zstd compression requires the 'zstd' feature".into())
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_decompress(_: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("zstd compression requires the 'zstd' feature".into())
+}
+
+#[cfg(feature = "lz4")]
+fn lz4_compress(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(lz4_flex::compress_prepend_size(data))
+}
+
+#[cfg(feature = "lz4")]
+fn lz4_decompress(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(lz4_flex::decompress_size_prepended(data)?)
+}
+
+#[cfg(not(feature = "lz4"))]
+fn lz4_compress(_: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("lz4 compression requires the 'lz4' feature".into())
+}
+
+#[cfg(not(feature = "lz4"))]
+fn lz4_decompress(_: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("lz4 compression requires the 'lz4' feature".into())
+}
+
+#[cfg(feature = "deflate")]
+fn deflate_compress(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(),
+        flate2::Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(feature = "deflate")]
+fn deflate_decompress(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut decoded = Vec::new();
+    flate2::read::ZlibDecoder::new(data).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+#[cfg(not(feature = "deflate"))]
+fn deflate_compress(_: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("deflate compression requires the 'deflate' feature".into())
+}
+
+#[cfg(not(feature = "deflate"))]
+fn deflate_decompress(_: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("deflate compression requires the 'deflate' feature".into())
+}
+
+#[derive(Clone, Debug)]
+pub struct SerializeOptions {
+    /// codec applied to rasterband payloads
+    pub compression: Compression,
+    /// append a crc32 of each rasterband payload, verified on read
+    pub checksum: bool,
+    /// stream rasterbands in chunks of this many rows to bound memory,
+    /// rather than whole rasterbands
+    pub chunk_rows: Option<usize>,
+    /// encode rasterband payloads little-endian, avoiding byte swaps
+    /// between little-endian hosts
+    pub little_endian: bool,
+    /// overview decimation factors serialized after each full resolution
+    /// rasterband, generated on the fly where the dataset lacks them
+    pub overviews: Vec<usize>,
+    /// run-length encode nodata pixels, shrinking sparse edge tiles
+    pub nodata_rle: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            compression: Compression::None,
+            checksum: false,
+            chunk_rows: None,
+            little_endian: false,
+            overviews: Vec::new(),
+            nodata_rle: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde",
+    derive(serde::Serialize, serde::Deserialize))]
+pub struct DatasetHeader {
+    pub width: usize,
+    pub height: usize,
+    pub transform: [f64; 6],
+    pub projection: String,
+    pub gdal_type: GDALDataType::Type,
+    pub no_data_value: Option<f64>,
+    pub rasterband_count: usize,
+    pub compression: Compression,
+    pub checksum: bool,
+    /// rows per payload chunk
+    pub chunk_rows: usize,
+    /// rasterband payload byte order, the header is always big-endian
+    pub little_endian: bool,
+    /// overview decimation factors in ascending order
+    pub overviews: Vec<usize>,
+    /// payloads of rasterbands with nodata values are run-length encoded
+    pub nodata_rle: bool,
+    pub metadata: Vec<(String, String)>,
+}
+
+pub fn read_header<T: Read>(reader: &mut T)
+        -> Result<DatasetHeader, Box<dyn Error>> {
+    // validate format identifier and version
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err("not a serialized dataset".into());
+    }
+
+    let version = reader.read_u8()?;
+    if version != FORMAT_VERSION {
+        return Err(format!("unsupported serialization format \
+            version '{}'", version).into());
+    }
 
-pub fn read<T: Read>(reader: &mut T)
-        -> Result<Dataset, Box<dyn Error>> {
     // read image dimensions
-    let width = reader.read_u32::<BigEndian>()? as isize;
-    let height = reader.read_u32::<BigEndian>()? as isize;
+    let width = reader.read_u32::<BigEndian>()? as usize;
+    let height = reader.read_u32::<BigEndian>()? as usize;
 
     // read geo transform
     let mut transform = [0.0f64; 6];
@@ -19,123 +210,1347 @@ pub fn read<T: Read>(reader: &mut T)
     }
  
     // read projection
-    let projection_len = reader.read_u32::<BigEndian>()?;
-    let mut projection_buf = vec![0u8; projection_len as usize];
-    reader.read_exact(&mut projection_buf)?;
-    let projection = String::from_utf8(projection_buf)?;
+    let projection = read_string(reader)?;
 
     // read gdal type and no_data value
     let gdal_type = reader.read_u32::<BigEndian>()?;
-    let no_data_value = match reader.read_u8()? {
-        0 => None,
-        _ => Some(reader.read_f64::<BigEndian>()?),
+    let no_data_value = read_option(reader)?;
+ 
+    // read rasterband count and compression codec
+    let rasterband_count = reader.read_u8()? as usize;
+    let compression = Compression::from_u8(reader.read_u8()?)?;
+    let checksum = reader.read_u8()? != 0;
+    let chunk_rows = match reader.read_u32::<BigEndian>()? {
+        0 => height,
+        x => x as usize,
     };
+    let little_endian = reader.read_u8()? != 0;
+
+    // read overview factors
+    let mut overviews = Vec::new();
+    for _ in 0..reader.read_u8()? {
+        overviews.push(reader.read_u32::<BigEndian>()? as usize);
+    }
+    let nodata_rle = reader.read_u8()? != 0;
+
+    // read dataset metadata
+    let metadata = read_metadata(reader)?;
+
+    Ok(DatasetHeader {
+        width,
+        height,
+        transform,
+        projection,
+        gdal_type,
+        no_data_value,
+        rasterband_count,
+        compression,
+        checksum,
+        chunk_rows,
+        little_endian,
+        overviews,
+        nodata_rle,
+        metadata,
+    })
+}
+
+pub fn read<T: Read>(reader: &mut T)
+        -> Result<Dataset, Box<dyn Error>> {
+    let header = read_header(reader)?;
+    let indices: Vec<isize> =
+        (1..=header.rasterband_count as isize).collect();
+
+    _read_bands(&header, &indices, reader)
+}
+
+pub fn read_bands<T: Read>(reader: &mut T, indices: &[isize])
+        -> Result<Dataset, Box<dyn Error>> {
+    let header = read_header(reader)?;
+    _read_bands(&header, indices, reader)
+}
+
+fn _read_bands<T: Read>(header: &DatasetHeader, indices: &[isize],
+        reader: &mut T) -> Result<Dataset, Box<dyn Error>> {
+    // validate rasterband indices
+    for (i, index) in indices.iter().enumerate() {
+        if *index < 1 || *index > header.rasterband_count as isize {
+            return Err(format!("rasterband '{}' out of range",
+                index).into());
+        } else if indices[..i].contains(index) {
+            return Err(format!("rasterband '{}' duplicated",
+                index).into());
+        }
+    }
+
+    let dataset = header_dataset(header, indices.len())?;
  
-    // read rasterband count
-    let rasterband_count = reader.read_u8()? as isize;
+    // read requested rasterbands and skip the remainder
+    let max_index = indices.iter().cloned().max().unwrap_or(0);
+    for i in 0..max_index {
+        match indices.iter().position(|x| *x == i + 1) {
+            Some(position) => read_raster(&dataset,
+                (position + 1) as isize, header, reader)?,
+            None => skip_raster(header, reader)?,
+        }
+    }
 
-    // initialize dataset
+    Ok(dataset)
+}
+
+fn header_dataset(header: &DatasetHeader, rasterband_count: usize)
+        -> Result<Dataset, Box<dyn Error>> {
     let driver = Driver::get("Mem")?;
-    let dataset = crate::init_dataset(&driver, "unreachable", gdal_type,
-        width, height, rasterband_count, no_data_value)?;
+    let dataset = crate::init_dataset_with_fill(&driver, "unreachable",
+        header.gdal_type, header.width as isize, header.height as isize,
+        rasterband_count as isize, header.no_data_value, false)?;
 
-    dataset.set_geo_transform(&transform)?;
-    dataset.set_projection(&projection)?;
- 
-    // read rasterbands
-    for i in 0..rasterband_count {
-        read_raster(&dataset, i+1, reader)?;
+    dataset.set_geo_transform(&header.transform)?;
+    dataset.set_projection(&header.projection)?;
+    unsafe {
+        crate::set_object_metadata(
+            dataset.c_dataset() as GDALMajorObjectH, &header.metadata)?;
+    }
+
+    // allocate overviews to be filled from the payload
+    if !header.overviews.is_empty() {
+        let c_resampling = CString::new("NONE")?;
+        let mut factors: Vec<c_int> = header.overviews.iter()
+            .map(|x| *x as c_int).collect();
+
+        let rv = unsafe {
+            gdal_sys::GDALBuildOverviews(dataset.c_dataset(),
+                c_resampling.as_ptr(), factors.len() as c_int,
+                factors.as_mut_ptr(), 0, std::ptr::null_mut(),
+                None, std::ptr::null_mut())
+        };
+
+        if rv != CPLErr::CE_None {
+            return Err("failed to allocate overviews".into());
+        }
     }
 
     Ok(dataset)
 }
 
-fn read_raster<T: Read>(dataset: &Dataset, index: isize,
-        reader: &mut T) -> Result<(), Box<dyn Error>> {
-    // compute raster size
+pub fn overview_factors(dataset: &Dataset)
+        -> Result<Vec<usize>, Box<dyn Error>> {
+    let (width, _) = dataset.raster_size();
+    let c_rasterband = dataset.rasterband(1)?.c_rasterband();
+
+    // derive decimation factors from existing overview widths
+    let mut factors = Vec::new();
+    unsafe {
+        for i in 0..gdal_sys::GDALGetOverviewCount(c_rasterband) {
+            let c_overview = gdal_sys::GDALGetOverview(c_rasterband, i);
+            let overview_width =
+                gdal_sys::GDALGetRasterBandXSize(c_overview) as usize;
+            if overview_width > 0 {
+                let factor = (width as f64
+                    / overview_width as f64).round() as usize;
+                factors.push(factor);
+            }
+        }
+    }
+
+    factors.sort_unstable();
+    factors.dedup();
+    Ok(factors)
+}
+
+fn overview_size(header: &DatasetHeader, factor: usize) -> (usize, usize) {
+    ((header.width + factor - 1) / factor,
+        (header.height + factor - 1) / factor)
+}
+
+pub fn read_from_slice(data: &[u8]) -> Result<Dataset, Box<dyn Error>> {
+    read(&mut Cursor::new(data))
+}
+
+fn write_payload(dataset: &Dataset, index: isize, header: &DatasetHeader,
+        gdal_type: GDALDataType::Type, y_offset: usize, rows: usize,
+        data: &[u8]) -> Result<(), Box<dyn Error>> {
+    // payloads in native byte order are written without decoding
+    let native = header.little_endian == cfg!(target_endian = "little");
+    if native || type_size(gdal_type) == 1 {
+        let rv = unsafe {
+            gdal_sys::GDALRasterIO(dataset.rasterband(index)?.c_rasterband(),
+                GDALRWFlag::GF_Write, 0, y_offset as c_int,
+                header.width as c_int, rows as c_int,
+                data.as_ptr() as *mut c_void, header.width as c_int,
+                rows as c_int, gdal_type, 0, 0)
+        };
+
+        if rv != CPLErr::CE_None {
+            return Err("failed to write rasterband payload".into());
+        }
+
+        return Ok(());
+    }
+
+    match header.little_endian {
+        true => decode_raster::<LittleEndian>(dataset, index,
+            gdal_type, y_offset, rows, data),
+        false => decode_raster::<BigEndian>(dataset, index,
+            gdal_type, y_offset, rows, data),
+    }
+}
+
+// containers hold concatenated datasets followed by an index of
+// (geocode, offset, length) entries and a trailing u64 index offset
+pub struct ContainerWriter<T: Write> {
+    writer: T,
+    options: SerializeOptions,
+    offset: u64,
+    entries: Vec<ContainerEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContainerEntry {
+    pub geocode: String,
+    pub offset: u64,
+    pub length: u64,
+}
+
+impl<T: Write> ContainerWriter<T> {
+    pub fn new(writer: T, options: SerializeOptions) -> ContainerWriter<T> {
+        ContainerWriter {
+            writer,
+            options,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn write_entry(&mut self, geocode: &str, dataset: &Dataset)
+            -> Result<(), Box<dyn Error>> {
+        if self.entries.iter().any(|x| x.geocode == geocode) {
+            return Err(format!("geocode '{}' duplicated", geocode).into());
+        }
+
+        let mut buf = Vec::new();
+        write_with_options(dataset, &mut buf, &self.options)?;
+        self.writer.write_all(&buf)?;
+
+        self.entries.push(ContainerEntry {
+            geocode: geocode.to_string(),
+            offset: self.offset,
+            length: buf.len() as u64,
+        });
+        self.offset += buf.len() as u64;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<T, Box<dyn Error>> {
+        // write index and trailing index offset
+        self.writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for entry in self.entries.iter() {
+            write_string(&entry.geocode, &mut self.writer)?;
+            self.writer.write_u64::<BigEndian>(entry.offset)?;
+            self.writer.write_u64::<BigEndian>(entry.length)?;
+        }
+
+        self.writer.write_u64::<BigEndian>(self.offset)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+pub fn write_container<T: Write>(tiles: &[crate::transform::SplitResult],
+        writer: &mut T, options: &SerializeOptions)
+        -> Result<(), Box<dyn Error>> {
+    let mut container = ContainerWriter::new(writer, options.clone());
+    for tile in tiles.iter() {
+        container.write_entry(&tile.geocode, &tile.dataset)?;
+    }
+
+    container.finish()?;
+    Ok(())
+}
+
+pub fn read_index<T: Read + Seek>(reader: &mut T)
+        -> Result<Vec<ContainerEntry>, Box<dyn Error>> {
+    // read index offset from the stream trailer
+    let start = reader.seek(SeekFrom::Start(0))?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    if end - start < 12 {
+        return Err("container too short for an index".into());
+    }
+
+    reader.seek(SeekFrom::End(-8))?;
+    let index_offset = reader.read_u64::<BigEndian>()?;
+    if index_offset > end - 12 {
+        return Err("container index offset out of range".into());
+    }
+
+    // read index entries
+    reader.seek(SeekFrom::Start(index_offset))?;
+    let count = reader.read_u32::<BigEndian>()?;
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let geocode = read_string(reader)?;
+        let offset = reader.read_u64::<BigEndian>()?;
+        let length = reader.read_u64::<BigEndian>()?;
+        entries.push(ContainerEntry { geocode, offset, length });
+    }
+
+    Ok(entries)
+}
+
+pub fn read_entry<T: Read + Seek>(reader: &mut T, geocode: &str)
+        -> Result<Dataset, Box<dyn Error>> {
+    let entries = read_index(reader)?;
+    let entry = match entries.iter().find(|x| x.geocode == geocode) {
+        Some(entry) => entry,
+        None => return Err(format!("geocode '{}' not found in container",
+            geocode).into()),
+    };
+
+    reader.seek(SeekFrom::Start(entry.offset))?;
+    read(&mut (&mut *reader).take(entry.length))
+}
+
+// async transfers prefix the synchronous format with its u64 length, so
+// streams are readable with read_async or by skipping the first 8 bytes
+#[cfg(feature = "async")]
+pub async fn read_async<T: tokio::io::AsyncRead + Unpin>(reader: &mut T)
+        -> Result<Dataset, Box<dyn Error>> {
+    use tokio::io::AsyncReadExt;
+
+    let length = reader.read_u64().await?;
+    let mut buf = vec![0u8; length as usize];
+    reader.read_exact(&mut buf).await?;
+
+    // decode on the blocking pool to keep gdal off runtime workers
+    let dataset = tokio::task::spawn_blocking(move || {
+        read_from_slice(&buf).map_err(|e| e.to_string())
+    }).await??;
+
+    Ok(dataset)
+}
+
+// the dataset is moved onto the blocking pool and returned once encoded
+#[cfg(feature = "async")]
+pub async fn write_async<T: tokio::io::AsyncWrite + Unpin>(
+        dataset: Dataset, writer: &mut T, options: &SerializeOptions)
+        -> Result<Dataset, Box<dyn Error>> {
+    use tokio::io::AsyncWriteExt;
+
+    let options = options.clone();
+    let (dataset, buf) = tokio::task::spawn_blocking(move || {
+        let mut buf = Vec::new();
+        match write_with_options(&dataset, &mut buf, &options) {
+            Ok(()) => Ok((dataset, buf)),
+            Err(e) => Err(e.to_string()),
+        }
+    }).await??;
+
+    writer.write_u64(buf.len() as u64).await?;
+    writer.write_all(&buf).await?;
+    Ok(dataset)
+}
+
+#[cfg(feature = "arrow")]
+pub fn to_arrow(dataset: &Dataset)
+        -> Result<arrow::record_batch::RecordBatch, Box<dyn Error>> {
+    use arrow::array::{ArrayRef, Float32Array, Int16Array,
+        UInt16Array, UInt32Array, UInt8Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    use std::sync::Arc;
+
+    // compute pixel coordinate columns
     let (width, height) = dataset.raster_size();
-    let size = (width * height) as usize;
+    let xs: Vec<u32> = (0..width * height)
+        .map(|i| (i % width) as u32).collect();
+    let ys: Vec<u32> = (0..width * height)
+        .map(|i| (i / width) as u32).collect();
 
-    // read raster type
-    let gdal_type = reader.read_u32::<BigEndian>()?;
-    match gdal_type  {
-        GDALDataType::GDT_Byte => {
+    let mut fields = vec![Field::new("x", DataType::UInt32, false),
+        Field::new("y", DataType::UInt32, false)];
+    let mut columns: Vec<ArrayRef> = vec![Arc::new(UInt32Array::from(xs)),
+        Arc::new(UInt32Array::from(ys))];
+
+    // add a column per rasterband where nodata pixels are null
+    for i in 0..dataset.raster_count() {
+        let rasterband = dataset.rasterband(i+1)?;
+        let description = unsafe {
+            crate::get_object_description(
+                rasterband.c_rasterband() as GDALMajorObjectH)
+        };
+        let name = if description.is_empty() {
+            format!("band_{}", i + 1)
+        } else {
+            description
+        };
+
+        let (data_type, column): (DataType, ArrayRef) =
+                match rasterband.band_type() {
+            GDALDataType::GDT_Byte => (DataType::UInt8, Arc::new(
+                UInt8Array::from(arrow_values::<u8>(&rasterband)?))),
+            GDALDataType::GDT_Int16 => (DataType::Int16, Arc::new(
+                Int16Array::from(arrow_values::<i16>(&rasterband)?))),
+            GDALDataType::GDT_UInt16 => (DataType::UInt16, Arc::new(
+                UInt16Array::from(arrow_values::<u16>(&rasterband)?))),
+            GDALDataType::GDT_Float32 => (DataType::Float32, Arc::new(
+                Float32Array::from(arrow_values::<f32>(&rasterband)?))),
+            x => return Err(format!("rasterband type {} unsupported \
+                by arrow conversion", x).into()),
+        };
+
+        fields.push(Field::new(&name, data_type, true));
+        columns.push(column);
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    Ok(arrow::record_batch::RecordBatch::try_new(schema, columns)?)
+}
+
+#[cfg(feature = "arrow")]
+fn arrow_values<T>(rasterband: &gdal::raster::RasterBand)
+        -> Result<Vec<Option<T>>, Box<dyn Error>>
+        where T: Copy + crate::FromPrimitive
+            + gdal::raster::GdalType + PartialEq {
+    let no_data_value = rasterband.no_data_value().map(T::from_f64);
+    let buffer = rasterband.read_band_as::<T>()?;
+
+    Ok(buffer.data.into_iter()
+        .map(|x| if Some(x) == no_data_value { None } else { Some(x) })
+        .collect())
+}
+
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: Write + Send>(tiles: &[crate::transform::SplitResult],
+        epsg_code: u32, writer: W, options: &SerializeOptions)
+        -> Result<(), Box<dyn Error>> {
+    use arrow::array::{ArrayRef, BinaryArray, Float64Array,
+        StringArray, UInt32Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    if tiles.is_empty() {
+        return Err("no tiles to write".into());
+    }
+
+    let rasterband_count = tiles[0].dataset.raster_count();
+    if tiles.iter().any(|x| x.dataset.raster_count() != rasterband_count) {
+        return Err("tile rasterband counts differ".into());
+    }
+
+    // compute tile attribute columns
+    let mut geocodes = Vec::new();
+    let (mut min_xs, mut max_xs) = (Vec::new(), Vec::new());
+    let (mut min_ys, mut max_ys) = (Vec::new(), Vec::new());
+    let (mut widths, mut heights) = (Vec::new(), Vec::new());
+    let mut metadata = Vec::new();
+    let mut geometries = Vec::new();
+    for tile in tiles.iter() {
+        geocodes.push(tile.geocode.clone());
+        min_xs.push(tile.bounds.0);
+        max_xs.push(tile.bounds.1);
+        min_ys.push(tile.bounds.2);
+        max_ys.push(tile.bounds.3);
+
+        let (width, height) = tile.dataset.raster_size();
+        widths.push(width as u32);
+        heights.push(height as u32);
+
+        // acquisition metadata as 'key=value' lines
+        let tile_metadata = unsafe { crate::get_object_metadata(
+            tile.dataset.c_dataset() as GDALMajorObjectH) };
+        metadata.push(tile_metadata.iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<String>>().join("\n"));
+
+        geometries.push(bounds_wkb(tile.bounds));
+    }
+
+    let mut fields = vec![
+        Field::new("geocode", DataType::Utf8, false),
+        Field::new("min_x", DataType::Float64, false),
+        Field::new("max_x", DataType::Float64, false),
+        Field::new("min_y", DataType::Float64, false),
+        Field::new("max_y", DataType::Float64, false),
+        Field::new("width", DataType::UInt32, false),
+        Field::new("height", DataType::UInt32, false),
+        Field::new("metadata", DataType::Utf8, false),
+        Field::new("geometry", DataType::Binary, false),
+    ];
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(geocodes)),
+        Arc::new(Float64Array::from(min_xs)),
+        Arc::new(Float64Array::from(max_xs)),
+        Arc::new(Float64Array::from(min_ys)),
+        Arc::new(Float64Array::from(max_ys)),
+        Arc::new(UInt32Array::from(widths)),
+        Arc::new(UInt32Array::from(heights)),
+        Arc::new(StringArray::from(metadata)),
+        Arc::new(BinaryArray::from(geometries.iter()
+            .map(|x| x.as_slice()).collect::<Vec<&[u8]>>())),
+    ];
+
+    // add a compressed little-endian payload column per rasterband
+    for i in 0..rasterband_count {
+        let gdal_type = tiles[0].dataset.rasterband(i+1)?.band_type();
+        let mut payloads = Vec::new();
+        for tile in tiles.iter() {
+            if tile.dataset.rasterband(i+1)?.band_type() != gdal_type {
+                return Err(format!("tile rasterband {} types differ",
+                    i + 1).into());
+            }
+
+            let (_, height) = tile.dataset.raster_size();
+            let data = encode_raster::<LittleEndian>(&tile.dataset,
+                i+1, gdal_type, 0, height)?;
+            payloads.push(options.compression.compress(data)?);
+        }
+
+        // record the pixel type needed to decode payloads
+        let type_name = unsafe { std::ffi::CStr::from_ptr(
+            gdal_sys::GDALGetDataTypeName(gdal_type)) };
+        let mut field_metadata = HashMap::new();
+        field_metadata.insert("gdal_type".to_string(),
+            type_name.to_string_lossy().into_owned());
+
+        fields.push(Field::new(&format!("band_{}", i + 1),
+            DataType::Binary, false).with_metadata(field_metadata));
+        columns.push(Arc::new(BinaryArray::from(payloads.iter()
+            .map(|x| x.as_slice()).collect::<Vec<&[u8]>>())));
+    }
+
+    // record geoparquet metadata for the bounds geometry column
+    let geo = format!("{{\"version\":\"1.0.0\",\
+        \"primary_column\":\"geometry\",\"columns\":{{\"geometry\":\
+        {{\"encoding\":\"WKB\",\"geometry_types\":[\"Polygon\"],\
+        \"crs\":{}}}}}}}", projjson(epsg_code)?);
+
+    let compression = match options.compression {
+        Compression::None => "none",
+        Compression::Zstd => "zstd",
+        Compression::Lz4 => "lz4",
+        Compression::Deflate => "deflate",
+    };
+
+    let mut schema_metadata = HashMap::new();
+    schema_metadata.insert("geo".to_string(), geo);
+    schema_metadata.insert("compression".to_string(),
+        compression.to_string());
+    let schema = Arc::new(Schema::new_with_metadata(fields,
+        schema_metadata));
+
+    let batch = arrow::record_batch::RecordBatch::try_new(
+        schema.clone(), columns)?;
+    let mut arrow_writer =
+        parquet::arrow::ArrowWriter::try_new(writer, schema, None)?;
+    arrow_writer.write(&batch)?;
+    arrow_writer.close()?;
+
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+fn bounds_wkb(bounds: (f64, f64, f64, f64)) -> Vec<u8> {
+    let (min_x, max_x, min_y, max_y) = bounds;
+    let points = [(min_x, min_y), (max_x, min_y),
+        (max_x, max_y), (min_x, max_y), (min_x, min_y)];
+
+    // little-endian polygon with a single ring
+    let mut wkb = vec![1u8];
+    for value in [3u32, 1, points.len() as u32].iter() {
+        wkb.extend_from_slice(&value.to_le_bytes());
+    }
+
+    for (x, y) in points.iter() {
+        wkb.extend_from_slice(&x.to_le_bytes());
+        wkb.extend_from_slice(&y.to_le_bytes());
+    }
+
+    wkb
+}
+
+#[cfg(feature = "parquet")]
+fn projjson(epsg_code: u32) -> Result<String, Box<dyn Error>> {
+    let spatial_ref = gdal::spatial_ref::SpatialRef::from_epsg(epsg_code)?;
+
+    let mut c_json = std::ptr::null_mut();
+    unsafe {
+        gdal_sys::OSRExportToPROJJSON(spatial_ref.to_c_hsrs(),
+            &mut c_json, std::ptr::null());
+        if c_json.is_null() {
+            return Err("failed to export projjson".into());
+        }
+
+        let json = std::ffi::CStr::from_ptr(c_json)
+            .to_string_lossy().into_owned();
+        gdal_sys::VSIFree(c_json as *mut c_void);
+        Ok(json)
+    }
+}
+
+fn npy_descr(gdal_type: GDALDataType::Type) -> &'static str {
+    match gdal_type {
+        GDALDataType::GDT_Byte => "|u1",
+        GDALDataType::GDT_Int16 => "<i2",
+        GDALDataType::GDT_UInt16 => "<u2",
+        GDALDataType::GDT_Float32 => "<f4",
+        _ => unimplemented!(),
+    }
+}
+
+fn npy_header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let shape: Vec<String> = shape.iter().map(|x| x.to_string()).collect();
+    let shape = match shape.len() {
+        1 => format!("({},)", shape[0]),
+        _ => format!("({})", shape.join(", ")),
+    };
+
+    let mut dict = format!("{{'descr': '{}', 'fortran_order': False, \
+        'shape': {}, }}", descr, shape);
+
+    // pad so the array data is 64 byte aligned
+    while (10 + dict.len() + 1) % 64 != 0 {
+        dict.push(' ');
+    }
+    dict.push('\n');
+
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header
+}
+
+fn npy_bytes(dataset: &Dataset, indices: &[isize])
+        -> Result<Vec<u8>, Box<dyn Error>> {
+    let (width, height) = dataset.raster_size();
+    let gdal_type = dataset.rasterband(indices[0])?.band_type();
+
+    let mut shape = vec![height, width];
+    if indices.len() > 1 {
+        shape.insert(0, indices.len());
+    }
+
+    let mut data = npy_header(npy_descr(gdal_type), &shape);
+    for index in indices.iter() {
+        if dataset.rasterband(*index)?.band_type() != gdal_type {
+            return Err("npy rasterband types differ".into());
+        }
+
+        data.extend(encode_raster::<LittleEndian>(dataset,
+            *index, gdal_type, 0, height)?);
+    }
+
+    Ok(data)
+}
+
+// writes a (bands, height, width) array, or (height, width) for one band
+pub fn to_npy<T: Write>(dataset: &Dataset, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    let indices: Vec<isize> = (1..=dataset.raster_count()).collect();
+    writer.write_all(&npy_bytes(dataset, &indices)?)?;
+    Ok(())
+}
+
+// writes an uncompressed zip archive with a 'band_N.npy' per rasterband
+pub fn to_npz<T: Write>(dataset: &Dataset, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    let mut offset = 0u32;
+    let mut entries = Vec::new();
+    for i in 0..dataset.raster_count() {
+        let name = format!("band_{}.npy", i + 1);
+        let data = npy_bytes(dataset, &[i+1])?;
+        let crc = crc32fast::hash(&data);
+
+        // write local file header and data
+        writer.write_u32::<LittleEndian>(0x04034b50)?;
+        write_zip_entry(&name, crc, data.len() as u32, writer)?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(&data)?;
+
+        let entry_offset = offset;
+        offset += 30 + name.len() as u32 + data.len() as u32;
+        entries.push((name, crc, data.len() as u32, entry_offset));
+    }
+
+    // write central directory
+    let mut directory_size = 0;
+    for (name, crc, size, entry_offset) in entries.iter() {
+        writer.write_u32::<LittleEndian>(0x02014b50)?;
+        writer.write_u16::<LittleEndian>(20)?;
+        write_zip_entry(name, *crc, *size, writer)?;
+        writer.write_u16::<LittleEndian>(0)?;
+        writer.write_u16::<LittleEndian>(0)?;
+        writer.write_u16::<LittleEndian>(0)?;
+        writer.write_u32::<LittleEndian>(0)?;
+        writer.write_u32::<LittleEndian>(*entry_offset)?;
+        writer.write_all(name.as_bytes())?;
+
+        directory_size += 46 + name.len() as u32;
+    }
+
+    // write end of central directory record
+    writer.write_u32::<LittleEndian>(0x06054b50)?;
+    writer.write_u16::<LittleEndian>(0)?;
+    writer.write_u16::<LittleEndian>(0)?;
+    writer.write_u16::<LittleEndian>(entries.len() as u16)?;
+    writer.write_u16::<LittleEndian>(entries.len() as u16)?;
+    writer.write_u32::<LittleEndian>(directory_size)?;
+    writer.write_u32::<LittleEndian>(offset)?;
+    writer.write_u16::<LittleEndian>(0)?;
+
+    Ok(())
+}
+
+fn write_zip_entry<T: Write>(name: &str, crc: u32, size: u32,
+        writer: &mut T) -> Result<(), Box<dyn Error>> {
+    // version, flags, stored method, 1980-01-01 timestamp
+    writer.write_u16::<LittleEndian>(20)?;
+    writer.write_u16::<LittleEndian>(0)?;
+    writer.write_u16::<LittleEndian>(0)?;
+    writer.write_u16::<LittleEndian>(0)?;
+    writer.write_u16::<LittleEndian>(33)?;
+
+    writer.write_u32::<LittleEndian>(crc)?;
+    writer.write_u32::<LittleEndian>(size)?;
+    writer.write_u32::<LittleEndian>(size)?;
+    writer.write_u16::<LittleEndian>(name.len() as u16)?;
+    writer.write_u16::<LittleEndian>(0)?;
+
+    Ok(())
+}
+
+fn read_raster<T: Read>(dataset: &Dataset, index: isize,
+        header: &DatasetHeader, reader: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    let (width, height) = (header.width, header.height);
+    let gdal_type = read_band_properties(dataset, index, reader)?;
+    let rle_value = rle_value(&dataset.rasterband(index)?,
+        header, gdal_type);
+
+    // read payload chunks
+    let mut y_offset = 0;
+    while y_offset < height {
+        let rows = header.chunk_rows.min(height - y_offset);
+        let size = width * rows * type_size(gdal_type);
+        let data = read_chunk(index, header,
+            rle_value.as_deref(), size, reader)?;
+
+        write_payload(dataset, index, header,
+            gdal_type, y_offset, rows, &data)?;
+        y_offset += rows;
+    }
+
+    read_overviews(dataset, index, header,
+        gdal_type, rle_value.as_deref(), reader)
+}
+
+fn read_chunk<T: Read>(index: isize, header: &DatasetHeader,
+        rle_value: Option<&[u8]>, size: usize, reader: &mut T)
+        -> Result<Vec<u8>, Box<dyn Error>> {
+    let data = match length_prefixed(header) {
+        false => {
             let mut data = vec![0u8; size];
             reader.read_exact(&mut data)?;
+            data
+        },
+        true => {
+            let length = reader.read_u64::<BigEndian>()?;
+            let mut data = vec![0u8; length as usize];
+            reader.read_exact(&mut data)?;
+            decode_chunk(data, header.compression, rle_value, size)?
+        },
+    };
+
+    if data.len() != size {
+        return Err("rasterband payload size mismatch".into());
+    }
+
+    // validate payload against the transmitted checksum
+    if header.checksum && reader.read_u32::<BigEndian>()?
+            != crc32fast::hash(&data) {
+        return Err(format!("rasterband {} checksum mismatch",
+            index).into());
+    }
+
+    Ok(data)
+}
+
+fn read_overviews<T: Read>(dataset: &Dataset, index: isize,
+        header: &DatasetHeader, gdal_type: GDALDataType::Type,
+        rle_value: Option<&[u8]>, reader: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    let c_rasterband = dataset.rasterband(index)?.c_rasterband();
+    let type_size = type_size(gdal_type);
+
+    for (i, factor) in header.overviews.iter().enumerate() {
+        let (width, height) = overview_size(header, *factor);
+        let mut data = read_chunk(index, header, rle_value,
+            width * height * type_size, reader)?;
+
+        // swap payload into native byte order
+        let native = header.little_endian
+            == cfg!(target_endian = "little");
+        if !native {
+            for value in data.chunks_mut(type_size) {
+                value.reverse();
+            }
+        }
+
+        let rv = unsafe {
+            let c_overview = gdal_sys::GDALGetOverview(c_rasterband,
+                i as c_int);
+            if c_overview.is_null() {
+                return Err(format!("overview {} not allocated",
+                    factor).into());
+            }
+
+            gdal_sys::GDALRasterIO(c_overview, GDALRWFlag::GF_Write,
+                0, 0, width as c_int, height as c_int,
+                data.as_mut_ptr() as *mut c_void, width as c_int,
+                height as c_int, gdal_type, 0, 0)
+        };
+
+        if rv != CPLErr::CE_None {
+            return Err("failed to write overview payload".into());
+        }
+    }
+
+    Ok(())
+}
+
+fn read_band_properties<T: Read>(dataset: &Dataset, index: isize,
+        reader: &mut T) -> Result<GDALDataType::Type, Box<dyn Error>> {
+    // read raster type, description and metadata
+    let gdal_type = reader.read_u32::<BigEndian>()?;
+    let description = read_string(reader)?;
+    let metadata = read_metadata(reader)?;
+
+    // read nodata, color interpretation, scale and offset
+    let no_data_value = read_option(reader)?;
+    let color_interpretation = reader.read_u32::<BigEndian>()?;
+    let scale = read_option(reader)?;
+    let offset = read_option(reader)?;
+
+    let rasterband = dataset.rasterband(index)?;
+    match no_data_value {
+        Some(value) => rasterband.set_no_data_value(value)?,
+        None => unsafe {
+            gdal_sys::GDALDeleteRasterNoDataValue(
+                rasterband.c_rasterband());
+        },
+    }
+
+    unsafe {
+        let c_rasterband = rasterband.c_rasterband();
+        crate::set_object_description(
+            c_rasterband as GDALMajorObjectH, &description)?;
+        crate::set_object_metadata(
+            c_rasterband as GDALMajorObjectH, &metadata)?;
 
-            let buffer = Buffer::new((width as usize,
-                height as usize), data);
+        gdal_sys::GDALSetRasterColorInterpretation(c_rasterband,
+            color_interpretation);
+        if let Some(scale) = scale {
+            gdal_sys::GDALSetRasterScale(c_rasterband, scale);
+        }
+
+        if let Some(offset) = offset {
+            gdal_sys::GDALSetRasterOffset(c_rasterband, offset);
+        }
+    }
+
+    Ok(gdal_type)
+}
+
+fn skip_raster<T: Read>(header: &DatasetHeader, reader: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    let gdal_type = reader.read_u32::<BigEndian>()?;
+    read_string(reader)?;
+    read_metadata(reader)?;
+
+    // skip nodata, color interpretation, scale and offset
+    read_option(reader)?;
+    reader.read_u32::<BigEndian>()?;
+    read_option(reader)?;
+    read_option(reader)?;
+
+    // skip payload chunks and overviews
+    let mut sizes = Vec::new();
+    let mut y_offset = 0;
+    while y_offset < header.height {
+        let rows = header.chunk_rows.min(header.height - y_offset);
+        sizes.push(header.width * rows * type_size(gdal_type));
+        y_offset += rows;
+    }
+
+    for factor in header.overviews.iter() {
+        let (width, height) = overview_size(header, *factor);
+        sizes.push(width * height * type_size(gdal_type));
+    }
 
-            dataset.rasterband(index)?.write::<u8>((0, 0),
-                (width as usize, height as usize), &buffer)?;
+    for size in sizes {
+        let mut length = match length_prefixed(header) {
+            false => size as u64,
+            true => reader.read_u64::<BigEndian>()?,
+        };
+
+        if header.checksum {
+            length += 4;
+        }
+
+        let skipped = std::io::copy(&mut (&mut *reader).take(length),
+            &mut std::io::sink())?;
+        if skipped != length {
+            return Err("unexpected end of rasterband payload".into());
+        }
+    }
+
+    Ok(())
+}
+
+fn read_option<T: Read>(reader: &mut T)
+        -> Result<Option<f64>, Box<dyn Error>> {
+    match reader.read_u8()? {
+        0 => Ok(None),
+        _ => Ok(Some(reader.read_f64::<BigEndian>()?)),
+    }
+}
+
+fn read_string<T: Read>(reader: &mut T)
+        -> Result<String, Box<dyn Error>> {
+    let length = reader.read_u32::<BigEndian>()?;
+    let mut buf = vec![0u8; length as usize];
+    reader.read_exact(&mut buf)?;
+
+    Ok(String::from_utf8(buf)?)
+}
+
+fn read_metadata<T: Read>(reader: &mut T)
+        -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let count = reader.read_u32::<BigEndian>()?;
+
+    let mut metadata = Vec::new();
+    for _ in 0..count {
+        let key = read_string(reader)?;
+        let value = read_string(reader)?;
+        metadata.push((key, value));
+    }
+
+    Ok(metadata)
+}
+
+pub(crate) fn type_size(gdal_type: GDALDataType::Type) -> usize {
+    match gdal_type {
+        GDALDataType::GDT_Byte => 1,
+        GDALDataType::GDT_Int16 => 2,
+        GDALDataType::GDT_UInt16 => 2,
+        GDALDataType::GDT_Float32 => 4,
+        _ => unimplemented!(),
+    }
+}
+
+pub(crate) fn decode_raster<B: ByteOrder>(dataset: &Dataset, index: isize,
+        gdal_type: GDALDataType::Type, y_offset: usize, rows: usize,
+        data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let (width, _) = dataset.raster_size();
+    let (window, height) = ((0, y_offset as isize), rows);
+    let rasterband = dataset.rasterband(index)?;
+
+    match gdal_type {
+        GDALDataType::GDT_Byte => {
+            let buffer = Buffer::new((width, height), data.to_vec());
+            rasterband.write::<u8>(window, (width, height), &buffer)?;
         },
         GDALDataType::GDT_Int16 => {
-            // read rasterband
-            let mut data = Vec::new();
-            for _ in 0..size {
-                data.push(reader.read_i16::<BigEndian>()?);
-            }
+            let mut values = vec![0i16; width * height];
+            B::read_i16_into(data, &mut values);
+
+            let buffer = Buffer::new((width, height), values);
+            rasterband.write::<i16>(window, (width, height), &buffer)?;
+        },
+        GDALDataType::GDT_UInt16 => {
+            let mut values = vec![0u16; width * height];
+            B::read_u16_into(data, &mut values);
+
+            let buffer = Buffer::new((width, height), values);
+            rasterband.write::<u16>(window, (width, height), &buffer)?;
+        },
+        GDALDataType::GDT_Float32 => {
+            let mut values = vec![0f32; width * height];
+            B::read_f32_into(data, &mut values);
+
+            let buffer = Buffer::new((width, height), values);
+            rasterband.write::<f32>(window, (width, height), &buffer)?;
+        },
+        _ => unimplemented!(),
+    }
+
+    Ok(())
+}
+
+pub fn write<T: Write>(dataset: &Dataset, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    write_with_options(dataset, writer, &SerializeOptions::default())
+}
+
+pub fn write_with_options<T: Write>(dataset: &Dataset, writer: &mut T,
+        options: &SerializeOptions) -> Result<(), Box<dyn Error>> {
+    let header = dataset_header(dataset, options)?;
+    write_header(&header, writer)?;
+
+    // write rasterbands
+    for i in 0..dataset.raster_count() {
+        write_raster(dataset, i+1, &header, writer)?;
+    }
+
+    Ok(())
+}
+
+fn dataset_header(dataset: &Dataset, options: &SerializeOptions)
+        -> Result<DatasetHeader, Box<dyn Error>> {
+    let (width, height) = dataset.raster_size();
+    let rasterband = dataset.rasterband(1)?;
+    let metadata = unsafe {
+        crate::get_object_metadata(dataset.c_dataset() as GDALMajorObjectH)
+    };
+
+    let chunk_rows = match options.chunk_rows {
+        Some(rows) if rows > 0 => rows,
+        _ => height.max(1),
+    };
+
+    let mut overviews: Vec<usize> = options.overviews.iter()
+        .filter(|x| **x > 1).cloned().collect();
+    overviews.sort_unstable();
+    overviews.dedup();
+
+    // the header stores the overview count in a single byte
+    if overviews.len() > u8::MAX as usize {
+        return Err(format!("overview count {} exceeds {}",
+            overviews.len(), u8::MAX).into());
+    }
+
+    Ok(DatasetHeader {
+        width,
+        height,
+        transform: dataset.geo_transform()?,
+        projection: dataset.projection(),
+        gdal_type: rasterband.band_type(),
+        no_data_value: rasterband.no_data_value(),
+        rasterband_count: dataset.raster_count() as usize,
+        compression: options.compression,
+        checksum: options.checksum,
+        chunk_rows,
+        little_endian: options.little_endian,
+        overviews,
+        nodata_rle: options.nodata_rle,
+        metadata,
+    })
+}
+
+fn write_header<T: Write>(header: &DatasetHeader, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    // write format identifier and version
+    writer.write_all(&MAGIC)?;
+    writer.write_u8(FORMAT_VERSION)?;
+
+    // write image dimensions
+    writer.write_u32::<BigEndian>(header.width as u32)?;
+    writer.write_u32::<BigEndian>(header.height as u32)?;
+
+    // write geo transform
+    for val in header.transform.iter() {
+        writer.write_f64::<BigEndian>(*val)?;
+    }
+
+    // write projection
+    write_string(&header.projection, writer)?;
+
+    // write gdal type and no_data value
+    writer.write_u32::<BigEndian>(header.gdal_type)?;
+    write_option(header.no_data_value, writer)?;
+
+    // write rasterband count and compression codec
+    writer.write_u8(header.rasterband_count as u8)?;
+    writer.write_u8(header.compression.to_u8())?;
+    writer.write_u8(header.checksum as u8)?;
+    writer.write_u32::<BigEndian>(header.chunk_rows as u32)?;
+    writer.write_u8(header.little_endian as u8)?;
+
+    // write overview factors
+    writer.write_u8(header.overviews.len() as u8)?;
+    for factor in header.overviews.iter() {
+        writer.write_u32::<BigEndian>(*factor as u32)?;
+    }
+    writer.write_u8(header.nodata_rle as u8)?;
+
+    // write dataset metadata
+    write_metadata(&header.metadata, writer)
+}
+
+fn write_raster<T: Write>(dataset: &Dataset, index: isize,
+        header: &DatasetHeader, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    let rasterband = dataset.rasterband(index)?;
+    let gdal_type = rasterband.band_type();
+    writer.write_u32::<BigEndian>(gdal_type)?;
+
+    // write description and metadata
+    let (description, metadata) = unsafe {
+        let c_rasterband = rasterband.c_rasterband() as GDALMajorObjectH;
+        (crate::get_object_description(c_rasterband),
+            crate::get_object_metadata(c_rasterband))
+    };
+    write_string(&description, writer)?;
+    write_metadata(&metadata, writer)?;
+
+    // write nodata, color interpretation, scale and offset
+    let (color_interpretation, scale, offset) = unsafe {
+        let c_rasterband = rasterband.c_rasterband();
+        let mut success: c_int = 0;
+        let scale = gdal_sys::GDALGetRasterScale(c_rasterband, &mut success);
+        let scale = if success != 0 { Some(scale) } else { None };
+
+        let offset = gdal_sys::GDALGetRasterOffset(c_rasterband,
+            &mut success);
+        let offset = if success != 0 { Some(offset) } else { None };
+
+        (gdal_sys::GDALGetRasterColorInterpretation(c_rasterband),
+            scale, offset)
+    };
+
+    write_option(rasterband.no_data_value(), writer)?;
+    writer.write_u32::<BigEndian>(color_interpretation)?;
+    write_option(scale, writer)?;
+    write_option(offset, writer)?;
+    let rle_value = rle_value(&rasterband, header, gdal_type);
+
+    // write payload chunks
+    let height = header.height;
+    let mut y_offset = 0;
+    while y_offset < height {
+        let rows = header.chunk_rows.min(height - y_offset);
+
+        let data = match header.little_endian {
+            true => encode_raster::<LittleEndian>(dataset, index,
+                gdal_type, y_offset, rows)?,
+            false => encode_raster::<BigEndian>(dataset, index,
+                gdal_type, y_offset, rows)?,
+        };
+
+        write_chunk(data, header, rle_value.as_deref(), writer)?;
+        y_offset += rows;
+    }
+
+    // write overviews, gdal reads from existing overviews when present
+    let window_size = (header.width, header.height);
+    for factor in header.overviews.iter() {
+        let size = overview_size(header, *factor);
+        let data = match header.little_endian {
+            true => encode_buffer::<LittleEndian>(&rasterband,
+                gdal_type, (0, 0), window_size, size)?,
+            false => encode_buffer::<BigEndian>(&rasterband,
+                gdal_type, (0, 0), window_size, size)?,
+        };
+
+        write_chunk(data, header, rle_value.as_deref(), writer)?;
+    }
+
+    Ok(())
+}
+
+fn write_chunk<T: Write>(data: Vec<u8>, header: &DatasetHeader,
+        rle_value: Option<&[u8]>, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    // checksum covers the decoded payload
+    let crc = crc32fast::hash(&data);
+
+    // write payload, prefixed with its length when encoded
+    match length_prefixed(header) {
+        false => writer.write_all(&data)?,
+        true => {
+            let data = match rle_value {
+                Some(value) => rle_encode(&data, value),
+                None => data,
+            };
+
+            let data = header.compression.compress(data)?;
+            writer.write_u64::<BigEndian>(data.len() as u64)?;
+            writer.write_all(&data)?;
+        },
+    }
+
+    if header.checksum {
+        writer.write_u32::<BigEndian>(crc)?;
+    }
+
+    Ok(())
+}
+
+fn length_prefixed(header: &DatasetHeader) -> bool {
+    header.compression != Compression::None || header.nodata_rle
+}
+
+fn rle_value(rasterband: &RasterBand, header: &DatasetHeader,
+        gdal_type: GDALDataType::Type) -> Option<Vec<u8>> {
+    if !header.nodata_rle {
+        return None;
+    }
+
+    // encode nodata value in the payload byte order
+    rasterband.no_data_value().map(|value| {
+        let mut data = vec![0u8; type_size(gdal_type)];
+        match (gdal_type, header.little_endian) {
+            (GDALDataType::GDT_Byte, _) => data[0] = value as u8,
+            (GDALDataType::GDT_Int16, true) =>
+                LittleEndian::write_i16(&mut data, value as i16),
+            (GDALDataType::GDT_Int16, false) =>
+                BigEndian::write_i16(&mut data, value as i16),
+            (GDALDataType::GDT_UInt16, true) =>
+                LittleEndian::write_u16(&mut data, value as u16),
+            (GDALDataType::GDT_UInt16, false) =>
+                BigEndian::write_u16(&mut data, value as u16),
+            (GDALDataType::GDT_Float32, true) =>
+                LittleEndian::write_f32(&mut data, value as f32),
+            (GDALDataType::GDT_Float32, false) =>
+                BigEndian::write_f32(&mut data, value as f32),
+            _ => unimplemented!(),
+        }
+
+        data
+    })
+}
+
+// runs are encoded as a u32 nodata pixel count, a u32 valid pixel
+// count, then the valid pixel bytes
+fn rle_encode(data: &[u8], value: &[u8]) -> Vec<u8> {
+    let pixels: Vec<&[u8]> = data.chunks(value.len()).collect();
+    let mut encoded = Vec::new();
+
+    let mut i = 0;
+    while i < pixels.len() {
+        let start = i;
+        while i < pixels.len() && pixels[i] == value {
+            i += 1;
+        }
+
+        let nodata_count = i - start;
+        let start = i;
+        while i < pixels.len() && pixels[i] != value {
+            i += 1;
+        }
+
+        let mut counts = [0u8; 8];
+        BigEndian::write_u32(&mut counts[..4], nodata_count as u32);
+        BigEndian::write_u32(&mut counts[4..], (i - start) as u32);
+        encoded.extend_from_slice(&counts);
+        encoded.extend_from_slice(
+            &data[start * value.len()..i * value.len()]);
+    }
+
+    encoded
+}
+
+fn rle_decode(data: &[u8], value: &[u8], size: usize)
+        -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut decoded = Vec::with_capacity(size);
+    let mut cursor = Cursor::new(data);
+
+    while (cursor.position() as usize) < data.len() {
+        let nodata_count = cursor.read_u32::<BigEndian>()? as usize;
+        let length = cursor.read_u32::<BigEndian>()? as usize
+            * value.len();
+
+        if decoded.len() + nodata_count * value.len() + length > size {
+            return Err("run-length payload exceeds rasterband".into());
+        }
+
+        for _ in 0..nodata_count {
+            decoded.extend_from_slice(value);
+        }
+
+        let position = cursor.position() as usize;
+        if position + length > data.len() {
+            return Err("unexpected end of run-length payload".into());
+        }
+
+        decoded.extend_from_slice(&data[position..position + length]);
+        cursor.set_position((position + length) as u64);
+    }
 
-            let buffer = Buffer::new((width as usize,
-                height as usize), data);
+    Ok(decoded)
+}
 
-            dataset.rasterband(index)?.write::<i16>((0, 0),
-                (width as usize, height as usize), &buffer)?;
-        },
-        GDALDataType::GDT_UInt16 => {
-            // read rasterband
-            let mut data = Vec::new();
-            for _ in 0..size {
-                data.push(reader.read_u16::<BigEndian>()?);
-            }
+fn decode_chunk(data: Vec<u8>, compression: Compression,
+        rle_value: Option<&[u8]>, size: usize)
+        -> Result<Vec<u8>, Box<dyn Error>> {
+    let data = compression.decompress(data)?;
+    match rle_value {
+        Some(value) => rle_decode(&data, value, size),
+        None => Ok(data),
+    }
+}
 
-            let buffer = Buffer::new((width as usize,
-                height as usize), data);
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeaderEncoding {
+    Json,
+    Bincode,
+}
 
-            dataset.rasterband(index)?.write::<u16>((0, 0),
-                (width as usize, height as usize), &buffer)?;
-        },
-        GDALDataType::GDT_Float32 => {
-            // read rasterband
-            let mut data = Vec::new();
-            for _ in 0..size {
-                data.push(reader.read_f32::<BigEndian>()?);
-            }
+// serde container: encoding byte, length prefixed header, band payloads
+#[cfg(feature = "serde")]
+pub fn write_serde<T: Write>(dataset: &Dataset, writer: &mut T,
+        options: &SerializeOptions, encoding: HeaderEncoding)
+        -> Result<(), Box<dyn Error>> {
+    let header = dataset_header(dataset, options)?;
+    let (encoding_id, buf) = match encoding {
+        HeaderEncoding::Json => (0, serde_json::to_vec(&header)?),
+        HeaderEncoding::Bincode => (1, bincode::serialize(&header)?),
+    };
 
-            let buffer = Buffer::new((width as usize,
-                height as usize), data);
+    writer.write_u8(encoding_id)?;
+    writer.write_u32::<BigEndian>(buf.len() as u32)?;
+    writer.write_all(&buf)?;
 
-            dataset.rasterband(index)?.write::<f32>((0, 0),
-                (width as usize, height as usize), &buffer)?;
-        },
-        _ => unimplemented!(),
+    // write rasterbands
+    for i in 0..dataset.raster_count() {
+        write_raster(dataset, i+1, &header, writer)?;
     }
 
     Ok(())
 }
 
-pub fn write<T: Write>(dataset: &Dataset, writer: &mut T)
-        -> Result<(), Box<dyn Error>> {
-    // write image dimensions
-    let (width, height) = dataset.raster_size();
-    writer.write_u32::<BigEndian>(width as u32)?;
-    writer.write_u32::<BigEndian>(height as u32)?;
+#[cfg(feature = "serde")]
+pub fn read_serde_header<T: Read>(reader: &mut T)
+        -> Result<DatasetHeader, Box<dyn Error>> {
+    let encoding_id = reader.read_u8()?;
+    let length = reader.read_u32::<BigEndian>()?;
+    let mut buf = vec![0u8; length as usize];
+    reader.read_exact(&mut buf)?;
 
-    // write geo transform
-    let transform = dataset.geo_transform()?;
-    for val in transform.iter() {
-        writer.write_f64::<BigEndian>(*val)?;
+    match encoding_id {
+        0 => Ok(serde_json::from_slice(&buf)?),
+        1 => Ok(bincode::deserialize(&buf)?),
+        x => Err(format!("unknown header encoding '{}'", x).into()),
     }
+}
 
-    // write projection
-    let projection = dataset.projection();
-    writer.write_u32::<BigEndian>(projection.len() as u32)?;
-    writer.write_all(projection.as_bytes())?;
+#[cfg(feature = "serde")]
+pub fn read_serde<T: Read>(reader: &mut T)
+        -> Result<Dataset, Box<dyn Error>> {
+    let header = read_serde_header(reader)?;
+    let indices: Vec<isize> =
+        (1..=header.rasterband_count as isize).collect();
 
-    // write gdal type and no_data value
-    let rasterband = dataset.rasterband(1)?;
-    writer.write_u32::<BigEndian>(rasterband.band_type())?;
-    match rasterband.no_data_value() {
+    _read_bands(&header, &indices, reader)
+}
+
+fn write_option<T: Write>(value: Option<f64>, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    match value {
         Some(value) => {
             writer.write_u8(1)?;
             writer.write_f64::<BigEndian>(value)?
@@ -143,60 +1558,633 @@ pub fn write<T: Write>(dataset: &Dataset, writer: &mut T)
         None => writer.write_u8(0)?,
     }
 
-    // write rasterbands
-    writer.write_u8(dataset.raster_count() as u8)?;
-    for i in 0..dataset.raster_count() {
-        write_raster(dataset, i+1, writer)?;
+    Ok(())
+}
+
+fn write_string<T: Write>(value: &str, writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    writer.write_u32::<BigEndian>(value.len() as u32)?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn write_metadata<T: Write>(metadata: &[(String, String)], writer: &mut T)
+        -> Result<(), Box<dyn Error>> {
+    writer.write_u32::<BigEndian>(metadata.len() as u32)?;
+    for (key, value) in metadata.iter() {
+        write_string(key, writer)?;
+        write_string(value, writer)?;
     }
 
     Ok(())
 }
 
-fn write_raster<T: Write>(dataset: &Dataset, index: isize,
-        writer: &mut T) -> Result<(), Box<dyn Error>> {
-    let gdal_type = dataset.rasterband(index)?.band_type();
-    writer.write_u32::<BigEndian>(gdal_type)?;
+pub(crate) fn encode_raster<B: ByteOrder>(dataset: &Dataset, index: isize,
+        gdal_type: GDALDataType::Type, y_offset: usize, rows: usize)
+        -> Result<Vec<u8>, Box<dyn Error>> {
+    let (width, _) = dataset.raster_size();
+    let (window, size) = ((0, y_offset as isize), (width, rows));
+    let rasterband = dataset.rasterband(index)?;
 
-    match gdal_type {
-        GDALDataType::GDT_Byte => {
-            let buffer = dataset.rasterband(index)?
-                .read_band_as::<u8>()?;
-            writer.write_all(&buffer.data)?;
-        },
+    encode_buffer::<B>(&rasterband, gdal_type, window, size, size)
+}
+
+fn encode_buffer<B: ByteOrder>(rasterband: &RasterBand,
+        gdal_type: GDALDataType::Type, window: (isize, isize),
+        window_size: (usize, usize), size: (usize, usize))
+        -> Result<Vec<u8>, Box<dyn Error>> {
+    let data = match gdal_type {
+        GDALDataType::GDT_Byte =>
+            rasterband.read_as::<u8>(window, window_size, size)?.data,
         GDALDataType::GDT_Int16 => {
-            let buffer = dataset.rasterband(index)?
-                .read_band_as::<i16>()?;
-            for pixel in buffer.data {
-                writer.write_i16::<BigEndian>(pixel)?;
-            }
+            let buffer =
+                rasterband.read_as::<i16>(window, window_size, size)?;
+            let mut data = vec![0u8; buffer.data.len() * 2];
+            B::write_i16_into(&buffer.data, &mut data);
+            data
         },
         GDALDataType::GDT_UInt16 => {
-            let buffer = dataset.rasterband(index)?
-                .read_band_as::<u16>()?;
-            for pixel in buffer.data {
-                writer.write_u16::<BigEndian>(pixel)?;
-            }
+            let buffer =
+                rasterband.read_as::<u16>(window, window_size, size)?;
+            let mut data = vec![0u8; buffer.data.len() * 2];
+            B::write_u16_into(&buffer.data, &mut data);
+            data
         },
         GDALDataType::GDT_Float32 => {
-            let buffer = dataset.rasterband(index)?
-                .read_band_as::<f32>()?;
-            for pixel in buffer.data {
-                writer.write_f32::<BigEndian>(pixel)?;
-            }
-        }
+            let buffer =
+                rasterband.read_as::<f32>(window, window_size, size)?;
+            let mut data = vec![0u8; buffer.data.len() * 4];
+            B::write_f32_into(&buffer.data, &mut data);
+            data
+        },
         _ => unimplemented!(),
-    }
+    };
 
-    Ok(())
+    Ok(data)
 }
 
 #[cfg(test)]
 mod tests {
     use gdal::Dataset;
+    use gdal::raster::Buffer;
+    use gdal_sys::{CPLErr, GDALDataType, GDALRWFlag};
 
     use std::io::Cursor;
+    use std::os::raw::c_void;
     use std::path::Path;
 
+    #[test]
+    fn serialize_checksum() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // write dataset with checksums
+        let options = super::SerializeOptions {
+            checksum: true,
+            ..Default::default()
+        };
+
+        let mut buffer = Vec::new();
+        super::write_with_options(&dataset, &mut buffer, &options)
+            .expect("write dataset");
+
+        let mut cursor = Cursor::new(buffer.clone());
+        assert!(super::read(&mut cursor).is_ok());
+
+        // corrupt the final pixel of the last rasterband
+        let index = buffer.len() - 5;
+        buffer[index] ^= 0xFF;
+
+        let mut cursor = Cursor::new(buffer);
+        assert!(super::read(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn serialize_chunked() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // write dataset in chunks not dividing the raster height
+        let options = super::SerializeOptions {
+            chunk_rows: Some(100),
+            checksum: true,
+            ..Default::default()
+        };
+
+        let mut buffer = Vec::new();
+        super::write_with_options(&dataset, &mut buffer, &options)
+            .expect("write dataset");
+
+        let mut cursor = Cursor::new(buffer);
+        let dataset2 = super::read(&mut cursor).expect("read dataset");
+
+        for i in 0..dataset.raster_count() {
+            let data = dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<u8>().expect("read band");
+            let data2 = dataset2.rasterband(i+1).expect("read raster2")
+                .read_band_as::<u8>().expect("read band2");
+            assert_eq!(data.data, data2.data);
+        }
+    }
+
+    fn assert_cycle(dataset: &Dataset, options: &super::SerializeOptions) {
+        let mut buffer = Vec::new();
+        super::write_with_options(dataset, &mut buffer, options)
+            .expect("write dataset");
+        let dataset2 = super::read_from_slice(&buffer)
+            .expect("read dataset");
+
+        for i in 0..dataset.raster_count() {
+            let data = dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<u8>().expect("read band");
+            let data2 = dataset2.rasterband(i+1).expect("read raster2")
+                .read_band_as::<u8>().expect("read band2");
+            assert_eq!(data.data, data2.data);
+        }
+    }
+
+    fn assert_compression_cycle(compression: super::Compression) {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // compress whole rasterbands and chunks with checksums
+        assert_cycle(&dataset, &super::SerializeOptions {
+            compression,
+            ..Default::default()
+        });
+        assert_cycle(&dataset, &super::SerializeOptions {
+            compression,
+            checksum: true,
+            chunk_rows: Some(100),
+            ..Default::default()
+        });
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn serialize_zstd() {
+        assert_compression_cycle(super::Compression::Zstd);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn serialize_lz4() {
+        assert_compression_cycle(super::Compression::Lz4);
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn serialize_deflate() {
+        assert_compression_cycle(super::Compression::Deflate);
+    }
+
+    #[test]
+    fn serialize_compression_disabled() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let mut codecs = Vec::new();
+        if cfg!(not(feature = "zstd")) {
+            codecs.push(super::Compression::Zstd);
+        }
+        if cfg!(not(feature = "lz4")) {
+            codecs.push(super::Compression::Lz4);
+        }
+        if cfg!(not(feature = "deflate")) {
+            codecs.push(super::Compression::Deflate);
+        }
+
+        let mut buffer = Vec::new();
+        super::write(&dataset, &mut buffer).expect("write dataset");
+        let header = super::dataset_header(&dataset, &Default::default())
+            .expect("dataset header");
+
+        for compression in codecs {
+            // locate the codec byte by comparing encoded headers
+            let mut header_buffer = Vec::new();
+            super::write_header(&header, &mut header_buffer)
+                .expect("write header");
+            let mut codec_header = header.clone();
+            codec_header.compression = compression;
+            let mut codec_buffer = Vec::new();
+            super::write_header(&codec_header, &mut codec_buffer)
+                .expect("write header");
+            let position = header_buffer.iter().zip(codec_buffer.iter())
+                .position(|(x, y)| x != y).expect("codec byte");
+
+            // payloads of disabled codecs cannot be decoded
+            let mut codec_dataset = buffer.clone();
+            codec_dataset[position] = codec_buffer[position];
+            assert!(super::read_from_slice(&codec_dataset).is_err());
+
+            let options = super::SerializeOptions {
+                compression,
+                ..Default::default()
+            };
+            assert!(super::write_with_options(&dataset, &mut Vec::<u8>::new(),
+                &options).is_err());
+        }
+    }
+
+    #[test]
+    fn serialize_read_bands() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let mut buffer = Vec::new();
+        super::write(&dataset, &mut buffer).expect("write dataset");
+
+        // read header only
+        let mut cursor = Cursor::new(buffer.clone());
+        let header = super::read_header(&mut cursor).expect("read header");
+        assert_eq!((header.width, header.height), dataset.raster_size());
+        assert_eq!(header.rasterband_count, 3);
+
+        // read subset of rasterbands in a custom order
+        let mut cursor = Cursor::new(buffer);
+        let dataset2 = super::read_bands(&mut cursor, &[3, 1])
+            .expect("read bands");
+        assert_eq!(dataset2.raster_count(), 2);
+
+        let data = dataset.rasterband(3).expect("read raster")
+            .read_band_as::<u8>().expect("read band");
+        let data2 = dataset2.rasterband(1).expect("read raster2")
+            .read_band_as::<u8>().expect("read band2");
+        assert_eq!(data.data, data2.data);
+    }
+
+    #[test]
+    fn serialize_little_endian() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let options = super::SerializeOptions {
+            little_endian: true,
+            ..Default::default()
+        };
+
+        let mut buffer = Vec::new();
+        super::write_with_options(&dataset, &mut buffer, &options)
+            .expect("write dataset");
+
+        let mut cursor = Cursor::new(buffer);
+        let header = super::read_header(&mut cursor).expect("read header");
+        assert!(header.little_endian);
+
+        // multi-byte types round trip with their values intact
+        let size = dataset.raster_size();
+        let source = dataset.rasterband(1).expect("rasterband")
+            .read_band_as::<f64>().expect("read band");
+        let types: [(GDALDataType::Type, fn(f64) -> f64); 3] = [
+            (GDALDataType::GDT_Int16, |x| x * 100.0 - 12000.0),
+            (GDALDataType::GDT_UInt16, |x| x * 200.0 + 1.0),
+            (GDALDataType::GDT_Float32, |x| x * 0.5 + 0.25),
+        ];
+
+        for (gdal_type, map) in types.iter() {
+            let typed_dataset = crate::DatasetBuilder::new(size.0, size.1)
+                .gdal_type(*gdal_type)
+                .like(&dataset).expect("like dataset")
+                .create_mem().expect("create dataset");
+            let data: Vec<f64> = source.data.iter().map(|x| map(*x))
+                .collect();
+            typed_dataset.rasterband(1).expect("rasterband")
+                .write::<f64>((0, 0), size, &Buffer::new(size, data.clone()))
+                .expect("write band");
+
+            let mut buffer = Vec::new();
+            super::write_with_options(&typed_dataset, &mut buffer, &options)
+                .expect("write dataset");
+            let dataset2 = super::read_from_slice(&buffer)
+                .expect("read dataset");
+
+            let rasterband2 = dataset2.rasterband(1).expect("rasterband2");
+            assert_eq!(rasterband2.band_type(), *gdal_type);
+            assert_eq!(rasterband2.read_band_as::<f64>()
+                .expect("read band2").data, data);
+        }
+    }
+
+    #[test]
+    fn serialize_version() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let mut buffer = Vec::new();
+        super::write(&dataset, &mut buffer).expect("write dataset");
+        assert_eq!(&buffer[..4], b"STIM");
+
+        // unknown versions and unidentified payloads are rejected
+        let mut version_buffer = buffer.clone();
+        version_buffer[4] += 1;
+        assert!(super::read_from_slice(&version_buffer).is_err());
+        assert!(super::read_from_slice(&buffer[5..]).is_err());
+    }
+
+    #[test]
+    fn serialize_read_from_slice() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let mut buffer = Vec::new();
+        super::write(&dataset, &mut buffer).expect("write dataset");
+        let dataset2 = super::read_from_slice(&buffer)
+            .expect("read dataset");
+
+        for i in 0..dataset.raster_count() {
+            let data = dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<u8>().expect("read band");
+            let data2 = dataset2.rasterband(i+1).expect("read raster2")
+                .read_band_as::<u8>().expect("read band2");
+            assert_eq!(data.data, data2.data);
+        }
+    }
+
+    #[test]
+    fn serialize_overviews() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let options = super::SerializeOptions {
+            overviews: vec![4, 2],
+            ..Default::default()
+        };
+
+        let mut buffer = Vec::new();
+        super::write_with_options(&dataset, &mut buffer, &options)
+            .expect("write dataset");
+
+        // skip the first rasterband along with its overviews
+        let mut cursor = Cursor::new(buffer);
+        let dataset2 = super::read_bands(&mut cursor, &[2])
+            .expect("read bands");
+        assert_eq!(super::overview_factors(&dataset2)
+            .expect("overview factors"), vec![2, 4]);
+
+        // decoded overview pixels match a decimated source read
+        let expected = dataset.rasterband(2).expect("rasterband")
+            .read_as::<u8>((0, 0), (256, 256), (128, 128))
+            .expect("read overview");
+        let mut data = vec![0u8; 128 * 128];
+        let rv = unsafe {
+            let c_overview = gdal_sys::GDALGetOverview(
+                dataset2.rasterband(1).expect("rasterband2")
+                    .c_rasterband(), 0);
+            assert!(!c_overview.is_null());
+            gdal_sys::GDALRasterIO(c_overview, GDALRWFlag::GF_Read,
+                0, 0, 128, 128, data.as_mut_ptr() as *mut c_void,
+                128, 128, GDALDataType::GDT_Byte, 0, 0)
+        };
+        assert_eq!(rv, CPLErr::CE_None);
+        assert_eq!(data, expected.data);
+
+        // overview counts beyond a single byte are rejected
+        let options = super::SerializeOptions {
+            overviews: (2..=257).collect(),
+            ..Default::default()
+        };
+        assert!(super::write_with_options(&dataset, &mut Vec::new(),
+            &options).is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn serialize_async() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let mut buffer = Vec::new();
+        let dataset = super::write_async(dataset, &mut buffer,
+            &Default::default()).await.expect("write dataset");
+
+        // the length prefix wraps the synchronous format
+        let mut length = [0u8; 8];
+        length.copy_from_slice(&buffer[..8]);
+        assert_eq!(u64::from_be_bytes(length) as usize, buffer.len() - 8);
+        assert!(super::read_from_slice(&buffer[8..]).is_ok());
+
+        let dataset2 = super::read_async(&mut &buffer[..]).await
+            .expect("read dataset");
+        for i in 0..dataset.raster_count() {
+            let data = dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<u8>().expect("read band");
+            let data2 = dataset2.rasterband(i+1).expect("read raster2")
+                .read_band_as::<u8>().expect("read band2");
+            assert_eq!(data.data, data2.data);
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn serialize_parquet() {
+        use arrow::array::{Array, BinaryArray, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let tiles = crate::transform::split_geocode(&dataset,
+            crate::coordinate::Geocode::Geohash, 3).expect("split dataset");
+
+        let filename = std::env::temp_dir()
+            .join("satmod_serialize_parquet.parquet");
+        let file = std::fs::File::create(&filename).expect("create file");
+        super::write_parquet(&tiles, 4326, file, &Default::default())
+            .expect("write parquet");
+
+        let file = std::fs::File::open(&filename).expect("open file");
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+            .expect("read parquet");
+        let schema = builder.schema().clone();
+        assert_eq!(schema.metadata().get("compression")
+            .map(|x| x.as_str()), Some("none"));
+        assert_eq!(schema.field_with_name("band_1").expect("band field")
+            .metadata().get("gdal_type").map(|x| x.as_str()), Some("Byte"));
+
+        // decoded payloads match the tile rasterbands
+        let mut reader = builder.build().expect("build reader");
+        let batch = reader.next().expect("record batch")
+            .expect("read batch");
+        assert_eq!(batch.num_rows(), tiles.len());
+
+        let geocodes = batch.column(0).as_any()
+            .downcast_ref::<StringArray>().expect("geocode column");
+        let payloads = batch.column(schema.index_of("band_1")
+            .expect("band column")).as_any()
+            .downcast_ref::<BinaryArray>().expect("payload column");
+        for (i, tile) in tiles.iter().enumerate() {
+            assert_eq!(geocodes.value(i), tile.geocode);
+            let data = tile.dataset.rasterband(1).expect("rasterband")
+                .read_band_as::<u8>().expect("read band");
+            assert_eq!(payloads.value(i), data.data.as_slice());
+        }
+
+        std::fs::remove_file(&filename).expect("remove file");
+    }
+
+    #[test]
+    fn serialize_container() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let mut container = super::ContainerWriter::new(Vec::new(),
+            Default::default());
+        container.write_entry("9xj", &dataset).expect("write entry");
+        container.write_entry("9xk", &dataset).expect("write entry");
+        let buffer = container.finish().expect("finish container");
+
+        let mut cursor = Cursor::new(buffer);
+        let entries = super::read_index(&mut cursor).expect("read index");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].offset, entries[0].length);
+
+        let dataset2 = super::read_entry(&mut cursor, "9xk")
+            .expect("read entry");
+        assert_eq!(dataset2.raster_size(), dataset.raster_size());
+        assert!(super::read_entry(&mut cursor, "9xm").is_err());
+    }
+
+    #[test]
+    fn rle_cycle() {
+        let value = [0xFFu8, 0xFF];
+        let data = [0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x01,
+            0x00, 0x02, 0xFF, 0xFF];
+
+        let encoded = super::rle_encode(&data, &value);
+        assert_eq!(encoded.len(), 20);
+        assert_eq!(super::rle_decode(&encoded, &value, data.len())
+            .expect("decode"), data.to_vec());
+
+        // runs overflowing the expected size are rejected
+        assert!(super::rle_decode(&encoded, &value, 4).is_err());
+    }
+
+    #[test]
+    fn serialize_nodata_rle() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // blank the upper half of each rasterband with nodata
+        let driver = gdal::Driver::get("Mem").expect("get driver");
+        let dataset = dataset.create_copy(&driver, "unreachable")
+            .expect("copy dataset");
+        for i in 0..dataset.raster_count() {
+            let rasterband = dataset.rasterband(i+1).expect("read raster");
+            rasterband.set_no_data_value(0.0).expect("set nodata");
+            rasterband.write::<u8>((0, 0), (256, 128),
+                &Buffer::new((256, 128), vec![0u8; 256 * 128]))
+                .expect("write rows");
+        }
+
+        let options = super::SerializeOptions {
+            nodata_rle: true,
+            chunk_rows: Some(100),
+            checksum: true,
+            overviews: vec![2],
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        super::write_with_options(&dataset, &mut buffer, &options)
+            .expect("write dataset");
+
+        let mut plain_buffer = Vec::new();
+        super::write(&dataset, &mut plain_buffer).expect("write dataset");
+        assert!(buffer.len() < plain_buffer.len());
+
+        let dataset2 = super::read_from_slice(&buffer)
+            .expect("read dataset");
+
+        // skipping rasterbands consumes the length prefixed chunks
+        let mut cursor = Cursor::new(buffer);
+        let dataset3 = super::read_bands(&mut cursor, &[3])
+            .expect("read bands");
+
+        for i in 0..dataset.raster_count() {
+            let data = dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<u8>().expect("read band");
+            let data2 = dataset2.rasterband(i+1).expect("read raster2")
+                .read_band_as::<u8>().expect("read band2");
+            assert_eq!(data.data, data2.data);
+
+            if i == 2 {
+                let data3 = dataset3.rasterband(1).expect("read raster3")
+                    .read_band_as::<u8>().expect("read band3");
+                assert_eq!(data.data, data3.data);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_serde() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        for encoding in [super::HeaderEncoding::Json,
+                super::HeaderEncoding::Bincode].iter() {
+            let mut buffer = Vec::new();
+            super::write_serde(&dataset, &mut buffer,
+                &Default::default(), *encoding).expect("write dataset");
+
+            let mut cursor = Cursor::new(buffer.clone());
+            let header = super::read_serde_header(&mut cursor)
+                .expect("read header");
+            assert_eq!((header.width, header.height),
+                dataset.raster_size());
+            assert_eq!(header.transform,
+                dataset.geo_transform().expect("get transform"));
+            assert_eq!(header.projection, dataset.projection());
+
+            let mut cursor = Cursor::new(buffer);
+            let dataset2 = super::read_serde(&mut cursor)
+                .expect("read dataset");
+            for i in 0..dataset.raster_count() {
+                let data = dataset.rasterband(i+1).expect("read raster")
+                    .read_band_as::<u8>().expect("read band");
+                let data2 = dataset2.rasterband(i+1).expect("read raster2")
+                    .read_band_as::<u8>().expect("read band2");
+                assert_eq!(data.data, data2.data);
+            }
+        }
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn to_arrow() {
+        use arrow::array::Array;
+
+        // nodata pixels become nulls
+        let dataset = crate::DatasetBuilder::new(4, 3).no_data(0.0)
+            .fill(false).create_mem().expect("create dataset");
+        let data: Vec<u8> = vec![0, 1, 2, 0, 3, 0, 4, 5, 6, 7, 0, 8];
+        dataset.rasterband(1).expect("read raster")
+            .write::<u8>((0, 0), (4, 3), &Buffer::new((4, 3), data.clone()))
+            .expect("write band");
+
+        let batch = super::to_arrow(&dataset).expect("to arrow");
+        assert_eq!(batch.num_rows(), 12);
+        let column = batch.column(2);
+        for (j, value) in data.iter().enumerate() {
+            assert_eq!(column.is_null(j), *value == 0);
+        }
+
+        // unsupported rasterband types are rejected
+        let driver = gdal::Driver::get("Mem").expect("get driver");
+        let dataset = driver.create_with_band_type::<f64>("", 4, 3, 1)
+            .expect("create dataset");
+        assert!(super::to_arrow(&dataset).is_err());
+    }
+
+    #[test]
+    fn npy_header() {
+        let header = super::npy_header("<u2", &[3, 256, 256]);
+        assert_eq!(header.len() % 64, 0);
+        assert_eq!(&header[..6], b"\x93NUMPY");
+        assert_eq!(header[header.len() - 1], b'\n');
+
+        let dict = String::from_utf8_lossy(&header[10..]);
+        assert!(dict.contains("'shape': (3, 256, 256)"));
+    }
+
     #[test]
     fn serialize_cycle() {
         // read dataset
@@ -220,6 +2208,28 @@ mod tests {
         let transform = dataset.geo_transform();
         let transform2 = dataset2.geo_transform();
         assert_eq!(transform, transform2);
+
+        // compare rasterband properties
+        for i in 0..dataset.raster_count() {
+            let band = dataset.rasterband(i+1).expect("read raster");
+            let band2 = dataset2.rasterband(i+1).expect("read raster2");
+            assert_eq!(band.no_data_value(), band2.no_data_value());
+            unsafe {
+                assert_eq!(gdal_sys::GDALGetRasterColorInterpretation(
+                        band.c_rasterband()),
+                    gdal_sys::GDALGetRasterColorInterpretation(
+                        band2.c_rasterband()));
+            }
+        }
+
+        // compare metadata
+        let metadata = unsafe { crate::get_object_metadata(
+            dataset.rasterband(1).expect("read raster").c_rasterband()
+                as gdal_sys::GDALMajorObjectH) };
+        let metadata2 = unsafe { crate::get_object_metadata(
+            dataset2.rasterband(1).expect("read raster2").c_rasterband()
+                as gdal_sys::GDALMajorObjectH) };
+        assert_eq!(metadata, metadata2);
  
         // iterate over rasterbands
         for i in 1..dataset.raster_count() {
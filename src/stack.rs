@@ -0,0 +1,215 @@
+use gdal::Dataset;
+
+use crate::coordinate;
+
+use std::error::Error;
+
+pub struct Stack {
+    /// datasets ordered by acquisition
+    datasets: Vec<Dataset>,
+    /// acquisition timestamps in seconds since the unix epoch
+    timestamps: Vec<i64>,
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Stack {
+            datasets: Vec::new(),
+            timestamps: Vec::new(),
+        }
+    }
+
+    pub fn from_datasets(datasets: Vec<(i64, Dataset)>)
+            -> Result<Self, Box<dyn Error>> {
+        let mut stack = Stack::new();
+        for (timestamp, dataset) in datasets.into_iter() {
+            stack.push(timestamp, dataset)?;
+        }
+
+        Ok(stack)
+    }
+
+    pub fn push(&mut self, timestamp: i64, dataset: Dataset)
+            -> Result<(), Box<dyn Error>> {
+        if let Some(first) = self.datasets.first() {
+            validate_alignment(first, &dataset)?;
+        }
+
+        // insert after datasets acquired at or before the timestamp
+        let index = self.timestamps.iter()
+            .position(|x| *x > timestamp).unwrap_or(self.timestamps.len());
+        self.datasets.insert(index, dataset);
+        self.timestamps.insert(index, timestamp);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.datasets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.datasets.is_empty()
+    }
+
+    pub fn datasets(&self) -> &[Dataset] {
+        &self.datasets
+    }
+
+    pub fn timestamps(&self) -> &[i64] {
+        &self.timestamps
+    }
+
+    pub fn get(&self, index: usize) -> Option<(i64, &Dataset)> {
+        self.datasets.get(index).map(|x| (self.timestamps[index], x))
+    }
+
+    pub fn raster_size(&self) -> Option<(usize, usize)> {
+        self.datasets.first().map(|x| x.raster_size())
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(first) = self.datasets.first() {
+            for dataset in self.datasets.iter().skip(1) {
+                validate_alignment(first, dataset)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn time_series(&self, index: isize, x: usize, y: usize)
+            -> Result<Vec<(i64, Option<f64>)>, Box<dyn Error>> {
+        match self.raster_size() {
+            Some((width, height)) if x < width && y < height => {},
+            Some(_) => return Err(format!(
+                "pixel ({}, {}) outside of stack extent", x, y).into()),
+            None => return Err("stack contains no datasets".into()),
+        }
+
+        // read pixel from each dataset, nodata becomes none
+        let mut series = Vec::with_capacity(self.datasets.len());
+        for (timestamp, dataset) in
+                self.timestamps.iter().zip(self.datasets.iter()) {
+            let rasterband = dataset.rasterband(index)?;
+            let value = rasterband.read_as::<f64>(
+                (x as isize, y as isize), (1, 1), (1, 1))?.data[0];
+
+            let valid = Some(value) != rasterband.no_data_value()
+                && !value.is_nan();
+            series.push((*timestamp, if valid { Some(value) } else { None }));
+        }
+
+        Ok(series)
+    }
+
+    pub fn range(&self, start: i64, end: i64) -> std::ops::Range<usize> {
+        // index range of datasets acquired within [start, end)
+        let first = self.timestamps.iter()
+            .position(|x| *x >= start).unwrap_or(self.timestamps.len());
+        let last = self.timestamps.iter()
+            .position(|x| *x >= end).unwrap_or(self.timestamps.len());
+
+        first..last.max(first)
+    }
+
+    pub fn median(&self) -> Result<Dataset, Box<dyn Error>> {
+        crate::composite::median(&self.datasets)
+    }
+
+    pub fn quantile(&self, quantile: f64)
+            -> Result<Dataset, Box<dyn Error>> {
+        crate::composite::quantile(&self.datasets, quantile)
+    }
+
+    pub fn quantile_range(&self, start: i64, end: i64, quantile: f64)
+            -> Result<Dataset, Box<dyn Error>> {
+        crate::composite::quantile(&self.datasets[self.range(start, end)],
+            quantile)
+    }
+
+    pub fn fill(&self) -> Result<Dataset, Box<dyn Error>> {
+        // earlier acquisitions take precedence over later ones
+        if self.datasets.is_empty() {
+            return Err("stack contains no datasets".into());
+        }
+
+        crate::fill(&self.datasets)
+    }
+
+    pub fn fill_range(&self, start: i64, end: i64)
+            -> Result<Dataset, Box<dyn Error>> {
+        let range = self.range(start, end);
+        if range.is_empty() {
+            return Err("no datasets acquired within range".into());
+        }
+
+        crate::fill(&self.datasets[range])
+    }
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Stack::new()
+    }
+}
+
+fn validate_alignment(dataset: &Dataset, other: &Dataset)
+        -> Result<(), Box<dyn Error>> {
+    if other.raster_size() != dataset.raster_size()
+            || other.raster_count() != dataset.raster_count() {
+        return Err("stack dataset dimensions differ".into());
+    }
+
+    let transform = dataset.geo_transform()?;
+    let other_transform = other.geo_transform()?;
+    for (a, b) in transform.iter().zip(other_transform.iter()) {
+        if (a - b).abs() > 1e-6 * a.abs().max(1.0) {
+            return Err("stack dataset geo transforms differ".into());
+        }
+    }
+
+    if coordinate::identify_crs(&other.projection())?
+            != coordinate::identify_crs(&dataset.projection())? {
+        return Err("stack dataset spatial reference systems differ".into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+
+    use crate::Resampling;
+
+    use std::path::Path;
+
+    #[test]
+    fn stack() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let mut stack = super::Stack::new();
+        stack.push(200, Dataset::open(path).expect("open dataset"))
+            .expect("push dataset");
+        stack.push(100, Dataset::open(path).expect("open dataset"))
+            .expect("push dataset");
+        assert_eq!(stack.timestamps(), &[100, 200]);
+        assert_eq!(stack.range(150, 300), 1..2);
+
+        // series values match a direct pixel read
+        let dataset = Dataset::open(path).expect("open dataset");
+        let value = dataset.rasterband(1).expect("get rasterband")
+            .read_as::<f64>((128, 128), (1, 1), (1, 1))
+            .expect("read pixel").data[0];
+        let series = stack.time_series(1, 128, 128)
+            .expect("read time series");
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].0, 100);
+        assert!(series.iter().all(|x| x.1.map_or(true, |x| x == value)));
+
+        // misaligned datasets are rejected
+        let resampled = crate::transform::resample(&dataset,
+            4343.56 * 2.0, Resampling::Average).expect("resample dataset");
+        assert!(stack.push(300, resampled).is_err());
+        assert!(stack.time_series(1, 256, 0).is_err());
+    }
+}
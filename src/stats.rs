@@ -0,0 +1,246 @@
+use gdal::Dataset;
+use gdal_sys::GDALDataType;
+
+use crate::block::BlockIterator;
+
+use std::error::Error;
+
+#[derive(Clone, Debug)]
+pub struct StatisticsOptions {
+    /// number of histogram buckets
+    pub buckets: usize,
+    /// histogram value range, defaults to the band minimum and maximum
+    pub range: Option<(f64, f64)>,
+}
+
+impl Default for StatisticsOptions {
+    fn default() -> Self {
+        StatisticsOptions {
+            buckets: 256,
+            range: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BandStatistics {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub stddev: f64,
+    /// value range spanned by the histogram buckets
+    pub range: (f64, f64),
+    pub histogram: Vec<u64>,
+}
+
+pub fn band_statistics(dataset: &Dataset, index: isize,
+        options: &StatisticsOptions)
+        -> Result<BandStatistics, Box<dyn Error>> {
+    if options.buckets == 0 {
+        return Err("histogram requires at least one bucket".into());
+    }
+
+    let no_data_value = dataset.rasterband(index)?.no_data_value();
+
+    // compute moments with welford's online algorithm
+    let (mut count, mut mean, mut m2) = (0usize, 0.0, 0.0);
+    let (mut min, mut max) = (f64::MAX, f64::MIN);
+    for_each_block(dataset, index, |values| {
+        for value in valid_values(values, no_data_value) {
+            count += 1;
+            let delta = value - mean;
+            mean += delta / count as f64;
+            m2 += delta * (value - mean);
+
+            min = min.min(value);
+            max = max.max(value);
+        }
+    })?;
+
+    if count == 0 {
+        return Err("no valid pixels to compute statistics".into());
+    }
+
+    // bucket values over the histogram range
+    let range = options.range.unwrap_or((min, max));
+    let mut histogram = vec![0u64; options.buckets];
+    let width = (range.1 - range.0) / options.buckets as f64;
+    for_each_block(dataset, index, |values| {
+        for value in valid_values(values, no_data_value) {
+            if value < range.0 || value > range.1 {
+                continue;
+            }
+
+            let bucket = if width > 0.0 {
+                ((value - range.0) / width) as usize
+            } else {
+                0
+            };
+
+            histogram[bucket.min(options.buckets - 1)] += 1;
+        }
+    })?;
+
+    Ok(BandStatistics {
+        count,
+        min,
+        max,
+        mean,
+        stddev: (m2 / count as f64).sqrt(),
+        range,
+        histogram,
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct ZonalStatistics {
+    pub id: String,
+    /// number of valid pixels within the feature
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+pub fn zonal(dataset: &Dataset, index: isize, features: &Dataset,
+        layer_index: isize, id_field: &str)
+        -> Result<Vec<ZonalStatistics>, Box<dyn Error>> {
+    // feature geometries are rasterized in the dataset projection
+    let geometries = crate::transform::layer_geometries(features,
+        layer_index, Some(id_field), &dataset.projection())?;
+    let mut statistics: Vec<ZonalStatistics> = geometries.iter()
+        .map(|(id, _)| ZonalStatistics {
+            id: id.clone(),
+            count: 0,
+            min: f64::NAN,
+            max: f64::NAN,
+            mean: f64::NAN,
+        }).collect();
+
+    // burn feature indices, overlapping features take the last index
+    let handles: Vec<_> = geometries.iter().map(|x| (x.1).0).collect();
+    let burn_values: Vec<f64> = (1..=handles.len())
+        .map(|x| x as f64).collect();
+    let zone_dataset = crate::mask::rasterize(dataset, &handles,
+        &burn_values, GDALDataType::GDT_UInt32)?;
+    let zone_rasterband = zone_dataset.rasterband(1)?;
+
+    // accumulate valid pixels within each feature
+    let no_data_value = dataset.rasterband(index)?.no_data_value();
+    let mut sums = vec![0.0; statistics.len()];
+    for block in BlockIterator::<f64>::new(dataset, index)? {
+        let (offset, size, buffer) = block?;
+        let zones = zone_rasterband.read_as::<u32>(offset, size, size)?;
+        for (value, zone) in buffer.data.iter().zip(zones.data.iter()) {
+            if *zone == 0 || Some(*value) == no_data_value
+                    || value.is_nan() {
+                continue;
+            }
+
+            let i = *zone as usize - 1;
+            statistics[i].count += 1;
+            statistics[i].min = statistics[i].min.min(*value);
+            statistics[i].max = statistics[i].max.max(*value);
+            sums[i] += value;
+        }
+    }
+
+    for (zone, sum) in statistics.iter_mut().zip(sums.iter()) {
+        if zone.count != 0 {
+            zone.mean = sum / zone.count as f64;
+        }
+    }
+
+    Ok(statistics)
+}
+
+fn for_each_block<F: FnMut(&[f64])>(dataset: &Dataset, index: isize,
+        mut f: F) -> Result<(), Box<dyn Error>> {
+    for block in BlockIterator::<f64>::new(dataset, index)? {
+        let (_, _, buffer) = block?;
+        f(&buffer.data);
+    }
+
+    Ok(())
+}
+
+fn valid_values<'a>(values: &'a [f64], no_data_value: Option<f64>)
+        -> impl Iterator<Item = f64> + 'a {
+    values.iter().cloned()
+        .filter(move |x| Some(*x) != no_data_value && !x.is_nan())
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+
+    use std::path::Path;
+
+    #[test]
+    fn band_statistics() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let options = super::StatisticsOptions {
+            buckets: 16,
+            range: None,
+        };
+        let statistics = super::band_statistics(&dataset, 1, &options)
+            .expect("band statistics");
+
+        // compare with statistics over the entire band
+        let rasterband = dataset.rasterband(1).expect("get rasterband");
+        let no_data_value = rasterband.no_data_value();
+        let values: Vec<f64> = rasterband.read_band_as::<f64>()
+            .expect("read rasterband").data.into_iter()
+            .filter(|x| Some(*x) != no_data_value).collect();
+
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        assert_eq!(statistics.count, values.len());
+        assert!((statistics.mean - mean).abs() < 1e-6);
+        assert!(statistics.min <= statistics.mean
+            && statistics.mean <= statistics.max);
+        assert_eq!(statistics.histogram.len(), 16);
+        assert_eq!(statistics.histogram.iter().sum::<u64>() as usize,
+            values.len());
+    }
+
+    #[test]
+    fn zonal() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // two polygons along pixel edges, as (min_x, max_x, min_y, max_y)
+        let windows = [(10, 50, 20, 60), (100, 180, 120, 200)];
+        let polygons: Vec<String> = windows.iter()
+            .map(|x| crate::testing::pixel_polygon(&dataset, *x)
+                .expect("pixel polygon")).collect();
+        let features = crate::testing::feature_layer(&dataset.projection(),
+                &[("a", &polygons[0][..]), ("b", &polygons[1][..])])
+            .expect("feature layer");
+
+        let statistics = super::zonal(&dataset, 2, &features, 0, "id")
+            .expect("zonal statistics");
+        assert_eq!(statistics.len(), 2);
+
+        // compare with a direct read of each window
+        let rasterband = dataset.rasterband(2).expect("get rasterband");
+        for (zone, (id, window)) in statistics.iter()
+                .zip(["a", "b"].iter().zip(windows.iter())) {
+            let (min_x, max_x, min_y, max_y) = *window;
+            let size = (max_x - min_x, max_y - min_y);
+            let values = rasterband.read_as::<f64>(
+                    (min_x as isize, min_y as isize), size, size)
+                .expect("read window").data;
+
+            let min = values.iter().cloned().fold(f64::MAX, f64::min);
+            let max = values.iter().cloned().fold(f64::MIN, f64::max);
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            assert_eq!(zone.id, *id);
+            assert_eq!(zone.count, values.len());
+            assert_eq!((zone.min, zone.max), (min, max));
+            assert!((zone.mean - mean).abs() < 1e-9);
+        }
+    }
+}
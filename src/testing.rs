@@ -0,0 +1,177 @@
+use gdal::{Dataset, Driver};
+use gdal::spatial_ref::SpatialRef;
+use gdal_sys::{GDALDataType, OGRErr, OGRFieldType, OGRwkbGeometryType};
+
+use std::error::Error;
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+// sinusoidal polygon covering the western half of the MCD43A4 fixture
+pub const WEST_POLYGON: &str = "POLYGON((-8895604 5559752, \
+    -8339628 5559752, -8339628 4447801, -8895604 4447801, \
+    -8895604 5559752))";
+
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        // state must be non-zero
+        XorShift { state: seed.wrapping_mul(0x9E3779B97F4A7C15) | 1 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+pub fn gap_mask(size: usize, fraction: f64, seed: u64) -> Vec<bool> {
+    let mut rng = XorShift::new(seed);
+    (0..size).map(|_| rng.next_f64() < fraction).collect()
+}
+
+pub fn inject_gaps(dataset: &Dataset, fraction: f64, seed: u64)
+        -> Result<(Dataset, Vec<bool>), Box<dyn Error>> {
+    // copy dataset into memory
+    let driver = Driver::get("Mem")?;
+    let gap_dataset = dataset.create_copy(&driver, "unreachable")?;
+
+    // compute gap pixels
+    let (width, height) = dataset.raster_size();
+    let mask = gap_mask(width * height, fraction, seed);
+
+    // punch gaps into each rasterband
+    crate::mask::mask_pixels(&gap_dataset, &mask)?;
+
+    Ok((gap_dataset, mask))
+}
+
+pub fn recovered_fraction(dataset: &Dataset, mask: &[bool])
+        -> Result<f64, Box<dyn Error>> {
+    let (width, height) = dataset.raster_size();
+    let mut invalid_pixels = vec![true; width * height];
+
+    // identify pixels valid in any rasterband
+    for i in 0..dataset.raster_count() {
+        let rasterband = dataset.rasterband(i+1)?;
+        let no_data_value = rasterband.no_data_value().unwrap_or(0.0);
+        let buffer = rasterband.read_band_as::<f64>()?;
+
+        for (j, pixel) in buffer.data.iter().enumerate() {
+            if *pixel != no_data_value {
+                invalid_pixels[j] = false;
+            }
+        }
+    }
+
+    // compute fraction of gap pixels which are now valid
+    let gap_count = mask.iter().filter(|x| **x).count();
+    if gap_count == 0 {
+        return Ok(1.0);
+    }
+
+    let recovered_count = mask.iter().zip(invalid_pixels.iter())
+        .filter(|(gap, invalid)| **gap && !**invalid).count();
+
+    Ok(recovered_count as f64 / gap_count as f64)
+}
+
+pub fn feature_layer(projection: &str, features: &[(&str, &str)])
+        -> Result<Dataset, Box<dyn Error>> {
+    // create an in-memory vector dataset
+    let driver = Driver::get("Memory")?;
+    let dataset = crate::create_dataset(&driver, "unreachable",
+        GDALDataType::GDT_Unknown, 0, 0, 0, None, &[])?;
+    let spatial_ref = SpatialRef::from_wkt(projection)?;
+
+    // add a polygon layer with a string 'id' field
+    let c_name = CString::new("features")?;
+    let c_field = CString::new("id")?;
+    let layer = unsafe {
+        let layer = gdal_sys::GDALDatasetCreateLayer(dataset.c_dataset(),
+            c_name.as_ptr(), spatial_ref.to_c_hsrs(),
+            OGRwkbGeometryType::wkbPolygon, std::ptr::null_mut());
+        if layer.is_null() {
+            return Err("failed to create feature layer".into());
+        }
+
+        let field = gdal_sys::OGR_Fld_Create(c_field.as_ptr(),
+            OGRFieldType::OFTString);
+        let rv = gdal_sys::OGR_L_CreateField(layer, field, 1);
+        gdal_sys::OGR_Fld_Destroy(field);
+        if rv != OGRErr::OGRERR_NONE {
+            return Err("failed to create feature field".into());
+        }
+
+        layer
+    };
+
+    // add (id, wkt) features in the layer projection
+    for (id, wkt) in features.iter() {
+        let (c_id, c_wkt) = (CString::new(*id)?, CString::new(*wkt)?);
+        let rv = unsafe {
+            let mut geometry = std::ptr::null_mut();
+            let mut c_wkt_ptr = c_wkt.as_ptr() as *mut c_char;
+            if gdal_sys::OGR_G_CreateFromWkt(&mut c_wkt_ptr,
+                    spatial_ref.to_c_hsrs(), &mut geometry)
+                    != OGRErr::OGRERR_NONE {
+                return Err(format!("invalid feature wkt '{}'",
+                    wkt).into());
+            }
+
+            let feature = gdal_sys::OGR_F_Create(
+                gdal_sys::OGR_L_GetLayerDefn(layer));
+            gdal_sys::OGR_F_SetFieldString(feature, 0, c_id.as_ptr());
+            gdal_sys::OGR_F_SetGeometryDirectly(feature, geometry);
+            let rv = gdal_sys::OGR_L_CreateFeature(layer, feature);
+            gdal_sys::OGR_F_Destroy(feature);
+            rv
+        };
+
+        if rv != OGRErr::OGRERR_NONE {
+            return Err(format!("failed to create feature '{}'", id).into());
+        }
+    }
+
+    Ok(dataset)
+}
+
+pub fn pixel_polygon(dataset: &Dataset, window: (usize, usize, usize, usize))
+        -> Result<String, Box<dyn Error>> {
+    // (min_x, max_x, min_y, max_y) pixel window edges as a wkt polygon
+    let transform = dataset.geo_transform()?;
+    let coord = |x: usize, y: usize| {
+        let (x, y) = (x as f64, y as f64);
+        format!("{} {}", transform[0] + x * transform[1] + y * transform[2],
+            transform[3] + x * transform[4] + y * transform[5])
+    };
+
+    let (min_x, max_x, min_y, max_y) = window;
+    Ok(format!("POLYGON (({}, {}, {}, {}, {}))", coord(min_x, min_y),
+        coord(max_x, min_y), coord(max_x, max_y), coord(min_x, max_y),
+        coord(min_x, min_y)))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn gap_mask() {
+        let mask = super::gap_mask(10000, 0.25, 42);
+        let mask2 = super::gap_mask(10000, 0.25, 42);
+        assert_eq!(mask, mask2);
+
+        let mask3 = super::gap_mask(10000, 0.25, 43);
+        assert_ne!(mask, mask3);
+
+        let count = mask.iter().filter(|x| **x).count();
+        assert!(count > 2000 && count < 3000);
+    }
+}
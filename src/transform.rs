@@ -1,53 +1,166 @@
 use gdal::{Dataset, Driver};
-use gdal::spatial_ref::CoordTransform;
+use gdal::raster::{Buffer, GdalType, RasterBand};
+use gdal::spatial_ref::{CoordTransform, SpatialRef};
+use gdal_sys::{CPLErr, GDALDataType};
 
-use std::error::Error;
-
-pub fn merge(datasets: &[Dataset])
-        -> Result<Dataset, Box<dyn Error>> {
-    // TODO - ensure datasets are in same spatial reference system
-
-    // find minimum and maximum coordinates
-    let mut min_cx = f64::MAX;
-    let mut max_cx = f64::MIN;
-    let mut min_cy = f64::MAX;
-    let mut max_cy = f64::MIN;
+use crate::{CancellationToken, FromPrimitive, ProgressCallback,
+    Resampling, ToPrimitive};
+use crate::coordinate::{Geocode, Target, WEB_MERCATOR_EXTENT};
+use crate::grid::GridTile;
 
-    for dataset in datasets.iter() {
-        // TODO ensure transforms match
+use std::borrow::Borrow;
+use std::error::Error;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MergeStrategy {
+    /// first dataset covering a pixel wins
+    First,
+    /// last dataset covering a pixel wins
+    Last,
+    /// minimum of valid pixels
+    Min,
+    /// maximum of valid pixels
+    Max,
+    /// mean of valid pixels
+    Mean,
+    /// distance-weighted mean of valid pixels within the given number
+    /// of pixels from each dataset boundary
+    Feather(usize),
+    /// last valid pixel wins
+    NodataAware,
+}
 
-        let transform = dataset.geo_transform()?;
-        let (src_width, src_height) = dataset.raster_size();
-        let (width, height) = (src_width as f64, src_height as f64);
+#[derive(Clone, Debug)]
+pub struct GridSpec {
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_y: f64,
+    pub max_y: f64,
+    /// (x, y) pixel size
+    pub resolution: (f64, f64),
+    /// projection wkt
+    pub projection: String,
+}
 
-        let image_min_cx = transform[0];
-        let image_max_cx = transform[0] + (width * transform[1])
-            + (height * transform[2]);
-        let image_min_cy = transform[3] + (width * transform[4])
-            + (height * transform[5]);
-        let image_max_cy = transform[3];
+#[derive(Clone, Debug)]
+pub struct MergeOptions {
+    /// method to combine overlapping pixels
+    pub strategy: MergeStrategy,
+    /// resampling method for inputs not aligned with the target grid
+    pub resampling: Resampling,
+    /// target (x, y) pixel size, defaults to the finest input resolution
+    pub resolution: Option<(f64, f64)>,
+    /// explicit target grid, overrides resolution when defined
+    pub grid: Option<GridSpec>,
+    /// (x, y) origin the computed extent is snapped to at the target
+    /// resolution, aligning outputs with an external grid
+    pub grid_origin: Option<(f64, f64)>,
+    /// warp inputs into the target projection when they differ or
+    /// when their geo transforms are rotated
+    pub reproject: bool,
+    /// number of threads reading source datasets concurrently, only
+    /// supported by the Last strategy when merging in memory
+    pub threads: usize,
+    /// match rasterbands to those of the first dataset by description
+    /// rather than by index
+    pub match_bands: bool,
+    /// append a UInt16 rasterband with the index of the dataset
+    /// supplying each pixel
+    pub provenance: bool,
+    /// overview decimation factors built when merging to file
+    pub overviews: Vec<usize>,
+    /// match each dataset histogram to that of the first dataset
+    /// before merging to reduce brightness seams
+    pub histogram_match: bool,
+    /// token checked between blocks to abort the merge
+    pub cancellation: Option<CancellationToken>,
+}
 
-        min_cx = min_cx.min(image_min_cx);
-        max_cx = max_cx.max(image_max_cx);
-        min_cy = min_cy.min(image_min_cy);
-        max_cy = max_cy.max(image_max_cy);
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            strategy: MergeStrategy::Last,
+            resampling: Resampling::Nearest,
+            resolution: None,
+            grid: None,
+            grid_origin: None,
+            reproject: false,
+            threads: 1,
+            match_bands: false,
+            provenance: false,
+            overviews: Vec::new(),
+            histogram_match: false,
+            cancellation: None,
+        }
     }
+}
 
-    //println!("DST IMAGE BOUNDS {} {} {} {}",
-    //    min_cx, max_cx, min_cy, max_cy);
+pub fn merge<D: Borrow<Dataset>>(datasets: &[D])
+        -> Result<Dataset, Box<dyn Error>> {
+    merge_with_options(datasets, &MergeOptions::default())
+}
 
-    // compute merged image dimensions
-    let transform = datasets[0].geo_transform()?;
-    let min_px = (min_cx - transform[0]) / transform[1];
-    let max_px = (max_cx - transform[0]) / transform[1];
-    let min_py = (min_cy - transform[3]) / transform[5] * -1.0;
-    let max_py = (max_cy - transform[3]) / transform[5] * -1.0;
+pub fn merge_with_options<D: Borrow<Dataset>>(datasets: &[D],
+        options: &MergeOptions) -> Result<Dataset, Box<dyn Error>> {
+    merge_with_progress(datasets, options, &mut |_, _| true)
+}
 
-    //println!("  PIXELS {} {} {} {}", min_px, max_px, min_py, max_py);
+pub fn merge_with_progress<D: Borrow<Dataset>>(datasets: &[D],
+        options: &MergeOptions, progress: &mut ProgressCallback)
+        -> Result<Dataset, Box<dyn Error>> {
+    // cancellation is checked with each progress report
+    let mut progress = |fraction: f64, stage: &str| {
+        !crate::is_cancelled(&options.cancellation)
+            && progress(fraction, stage)
+    };
+    let progress: &mut ProgressCallback = &mut progress;
+    crate::report_progress(progress, 0.0, "validate")?;
+    let start = std::time::Instant::now();
+
+    if options.threads > 1 && (options.strategy != MergeStrategy::Last
+            || options.provenance) {
+        return Err(format!("threads unsupported for merge strategy {:?}",
+            options.strategy).into());
+    }
 
-    let dst_width = (max_px - min_px) as isize;
-    let dst_height = (max_py - min_py) as isize;
-    //println!("DST IMAGE DIMENSIONS {} {}", dst_width, dst_height);
+    // ensure datasets are in same spatial reference system
+    let projection = match &options.grid {
+        Some(grid) => grid.projection.clone(),
+        None => datasets[0].borrow().projection(),
+    };
+
+    // datasets may be owned or borrowed
+    let datasets: Vec<&Dataset> =
+        datasets.iter().map(|x| x.borrow()).collect();
+    let warped_datasets =
+        validate_projections(&datasets, &projection, options)?;
+    let datasets: Vec<&Dataset> = datasets.iter()
+        .zip(warped_datasets.iter())
+        .map(|(dataset, warped)| warped.as_ref().unwrap_or(*dataset))
+        .collect();
+
+    // if requested -> match rasterbands by description
+    let matched_datasets = match_bands(&datasets, options)?;
+    let datasets: Vec<&Dataset> = datasets.iter()
+        .zip(matched_datasets.iter())
+        .map(|(dataset, matched)| matched.as_ref().unwrap_or(*dataset))
+        .collect();
+
+    // if requested -> match histograms to the first dataset
+    let histogram_datasets = match_histograms(&datasets, options)?;
+    let datasets: Vec<&Dataset> = datasets.iter()
+        .zip(histogram_datasets.iter())
+        .map(|(dataset, matched)| matched.as_ref().unwrap_or(*dataset))
+        .collect();
+
+    // compute merged image transform and dimensions
+    let (merge_transform, dst_width, dst_height) =
+        merge_grid(&datasets, options)?;
 
     // open memory driver
     let driver = Driver::get("Mem")?;
@@ -61,204 +174,3204 @@ pub fn merge(datasets: &[Dataset])
         "unreachable", gdal_type, dst_width, dst_height,
         datasets[0].raster_count(), no_data_value)?;
 
-    // modify transform
-    let mut merge_transform = datasets[0].geo_transform()?;
-    merge_transform[0] = min_cx;
-    merge_transform[3] = max_cy;
-
     merge_dataset.set_geo_transform(&merge_transform)?;
-    merge_dataset.set_projection(&datasets[0].projection())?;
+    merge_dataset.set_projection(&projection)?;
+    crate::copy_all_metadata(datasets[0], &merge_dataset)?;
 
     // copy source rasters
-    for dataset in datasets.iter() {
-        // compute raster offsets
-        let transform = dataset.geo_transform()?;
-        let (src_width, src_height) = dataset.raster_size();
+    merge_rasters(&datasets, &merge_dataset, options, progress)?;
+
+    log::debug!("merged {} datasets in {:?}",
+        datasets.len(), start.elapsed());
+    crate::report_progress(progress, 1.0, "complete")?;
+    Ok(merge_dataset)
+}
+
+fn merge_rasters(datasets: &[&Dataset], merge_dataset: &Dataset,
+        options: &MergeOptions, progress: &mut ProgressCallback)
+        -> Result<(), Box<dyn Error>> {
+    match options.strategy {
+        MergeStrategy::Last | MergeStrategy::NodataAware
+            if !options.provenance => {},
+        _ => {
+            // if requested -> record source dataset of each pixel
+            // while combining the first rasterband
+            let count = merge_dataset.raster_count();
+            let provenance_index = match options.provenance {
+                true => Some(add_provenance_band(datasets.len(),
+                    merge_dataset, options)?),
+                false => None,
+            };
+
+            for i in 0..count {
+                merge_band(datasets, i+1, merge_dataset,
+                    provenance_index.filter(|_| i == 0), options)?;
+                crate::report_progress(progress,
+                    0.9 * (i + 1) as f64 / count as f64, "merge")?;
+            }
 
-        let dst_x_offset = ((transform[0] - merge_transform[0])
-            / merge_transform[1]) as isize;
-        let dst_y_offset = ((transform[3] - merge_transform[3])
-            / merge_transform[5]) as isize;
+            return Ok(());
+        },
+    }
+
+    if options.threads > 1 && options.strategy == MergeStrategy::Last {
+        return match merge_dataset.rasterband(1)?.band_type() {
+            GDALDataType::GDT_Byte => _merge_parallel::<u8>(datasets,
+                merge_dataset, options, progress),
+            GDALDataType::GDT_Int16 => _merge_parallel::<i16>(datasets,
+                merge_dataset, options, progress),
+            GDALDataType::GDT_UInt16 => _merge_parallel::<u16>(datasets,
+                merge_dataset, options, progress),
+            GDALDataType::GDT_Float32 => _merge_parallel::<f32>(datasets,
+                merge_dataset, options, progress),
+            _ => unimplemented!(),
+        };
+    }
+
+    let (dst_width, dst_height) = merge_dataset.raster_size();
+    let merge_transform = merge_dataset.geo_transform()?;
+
+    let copy_raster = copy_function(options);
+    for (k, dataset) in datasets.iter().enumerate() {
+        crate::report_progress(progress,
+            0.9 * k as f64 / datasets.len() as f64, "copy")?;
+
+        // compute raster windows on the target grid
+        let window = match merge_window(dataset, &merge_transform,
+                (dst_width as isize, dst_height as isize))? {
+            Some(window) => window,
+            None => continue,
+        };
 
         // copy all rasters
         for i in 0..dataset.raster_count() {
-            crate::copy_raster(dataset, i+1, 
-                (0, 0),
-                (src_width, src_height),
-                &merge_dataset, i+1,
-                (dst_x_offset, dst_y_offset), 
-                (src_width, src_height))?;
+            copy_raster(dataset, i+1, 
+                window.src_offset,
+                window.src_size,
+                merge_dataset, i+1,
+                window.dst_offset,
+                window.dst_size,
+                options.resampling)?;
         }
     }
-    
-    Ok(merge_dataset)
+
+    Ok(())
 }
 
-pub fn split(dataset: &Dataset, min_cx: f64, max_cx: f64,
-        min_cy : f64, max_cy: f64, epsg_code: u32)
-        -> Result<Option<Dataset>, Box<dyn Error>> {
-    let (src_width, src_height) = dataset.raster_size();
+fn add_provenance_band(dataset_count: usize, merge_dataset: &Dataset,
+        options: &MergeOptions) -> Result<isize, Box<dyn Error>> {
+    match options.strategy {
+        MergeStrategy::Mean | MergeStrategy::Feather(_) =>
+            return Err(format!("provenance undefined for merge \
+                strategy {:?}", options.strategy).into()),
+        _ if dataset_count >= u16::MAX as usize =>
+            return Err("too many datasets to record provenance".into()),
+        _ => {},
+    }
 
-    // initialize CoordTransforms from dataset
-    let (mut transform, projection, src_spatial_ref, dst_spatial_ref) =
-        crate::coordinate::get_transform_refs(dataset, epsg_code)?;
-    let coord_transform = CoordTransform::new(
-        &src_spatial_ref, &dst_spatial_ref)?;
-    let reverse_transform = CoordTransform::new(
-        &dst_spatial_ref, &src_spatial_ref)?;
+    // add provenance rasterband
+    let rv = unsafe {
+        gdal_sys::GDALAddBand(merge_dataset.c_dataset(),
+            GDALDataType::GDT_UInt16, std::ptr::null_mut())
+    };
 
-    // compute center point pixels
-    let mid_cx = (min_cx + max_cx) / 2.0;
-    let mid_cy = (min_cy + max_cy) / 2.0;
+    if rv != CPLErr::CE_None {
+        return Err("failed to add provenance rasterband".into());
+    }
 
-    let (center_tx, center_ty, _) = crate::coordinate::transform_coord(
-        mid_cx, mid_cy, 0.0, &reverse_transform)?;
+    let index = merge_dataset.raster_count();
+    merge_dataset.rasterband(index)?.set_no_data_value(u16::MAX as f64)?;
+    Ok(index)
+}
 
-    let center_px = (center_tx - transform[0]) / transform[1];
-    let center_py = (center_ty - transform[3]) / transform[5];
+pub struct VirtualMosaic<'a> {
+    dataset: Dataset,
+    // the virtual dataset references source dataset handles
+    sources: PhantomData<&'a [Dataset]>,
+}
 
-    // compute window pixel bounding box
-    let mut bound_min_px = center_px as isize;
-    let mut bound_max_px = center_px as isize;
-    let mut bound_min_py = center_py as isize;
-    let mut bound_max_py = center_py as isize;
+impl<'a> VirtualMosaic<'a> {
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+}
 
-    let mut bound_min_cx;
-    let mut bound_max_cx;
-    let mut bound_min_cy;
-    let mut bound_max_cy;
+impl<'a> Deref for VirtualMosaic<'a> {
+    type Target = Dataset;
 
-    loop {
-        // convert bounding pixels to coordinates
-        let pixels = vec![
-            (bound_min_px, bound_min_py, 0),
-            (bound_max_px, bound_min_py, 0),
-            (bound_min_px, bound_max_py, 0),
-            (bound_max_px, bound_max_py, 0)
-        ];
+    fn deref(&self) -> &Dataset {
+        &self.dataset
+    }
+}
 
-        let (xs, ys, _) = crate::coordinate::transform_pixels(
-            &pixels, &transform, &coord_transform)?;
+pub fn merge_virtual<'a>(datasets: &'a [Dataset])
+        -> Result<VirtualMosaic<'a>, Box<dyn Error>> {
+    // source datasets are borrowed for the lifetime of the mosaic
+    let mut c_datasets: Vec<_> = datasets.iter()
+        .map(|dataset| unsafe { dataset.c_dataset() }).collect();
 
-        bound_min_cx = xs[0].max(xs[2]);
-        bound_max_cx = xs[1].min(xs[3]);
-        bound_min_cy = ys[2].max(ys[3]);
-        bound_max_cy = ys[0].min(ys[1]);
+    let dataset = build_vrt(c_datasets.len(),
+        c_datasets.as_mut_ptr(), std::ptr::null())?;
+    Ok(VirtualMosaic { dataset, sources: PhantomData })
+}
 
-        // check if bounding box envolopes window
-        if bound_min_cx <= min_cx
-                && bound_max_cx >= max_cx
-                && bound_min_cy <= min_cy
-                && bound_max_cy >= max_cy {
-                //&& &bound_max_cy >= min_cy { TODO - validate fix?
-            break;
+pub fn merge_virtual_paths(paths: &[&Path])
+        -> Result<Dataset, Box<dyn Error>> {
+    let mut c_paths = Vec::new();
+    for path in paths.iter() {
+        let path = path.to_str().ok_or("path is not valid unicode")?;
+        c_paths.push(CString::new(path)?);
+    }
+
+    let c_path_ptrs: Vec<_> = c_paths.iter().map(|x| x.as_ptr()).collect();
+    build_vrt(c_path_ptrs.len(), std::ptr::null_mut(), c_path_ptrs.as_ptr())
+}
+
+fn build_vrt(count: usize, c_datasets: *mut gdal_sys::GDALDatasetH,
+        c_paths: *const *const c_char) -> Result<Dataset, Box<dyn Error>> {
+    // use the finest source resolution similar to merge
+    let args = vec!["-resolution".to_string(), "highest".to_string()];
+    let c_args = crate::to_argv(&args)?;
+
+    let c_filename = CString::new("")?;
+    let mut usage_error: c_int = 0;
+    let c_dataset = unsafe {
+        let options = gdal_sys::GDALBuildVRTOptionsNew(c_args,
+            std::ptr::null_mut());
+        gdal_sys::CSLDestroy(c_args);
+
+        let c_dataset = gdal_sys::GDALBuildVRT(c_filename.as_ptr(),
+            count as c_int, c_datasets, c_paths, options, &mut usage_error);
+        gdal_sys::GDALBuildVRTOptionsFree(options);
+        c_dataset
+    };
+
+    if c_dataset.is_null() || usage_error != 0 {
+        return Err("failed to build virtual mosaic".into());
+    }
+
+    Ok(unsafe { Dataset::from_c_dataset(c_dataset) })
+}
+
+struct DatasetRef<'a>(&'a Dataset);
+
+// aliased handles are dropped before reading, so each source dataset is
+// only ever accessed from a single thread
+unsafe impl<'a> Send for DatasetRef<'a> {}
+
+fn _merge_parallel<T: Copy + GdalType + Send>(datasets: &[&Dataset],
+        merge_dataset: &Dataset, options: &MergeOptions,
+        progress: &mut ProgressCallback) -> Result<(), Box<dyn Error>> {
+    let (width, height) = merge_dataset.raster_size();
+    let merge_transform = merge_dataset.geo_transform()?;
+
+    // compute raster windows on the target grid, the last dataset wins
+    // so only the final occurrence of an aliased handle is merged
+    let mut sources = Vec::new();
+    for (k, dataset) in datasets.iter().enumerate() {
+        let aliased = datasets[k+1..].iter().any(|x| unsafe {
+            x.c_dataset() == dataset.c_dataset()
+        });
+
+        if aliased {
+            continue;
         }
 
-        // increment one of the bounds
-        // TODO - need to fix this in the case where y transforms are non-negative
-        let bound_differences = vec![
-            bound_min_cx - min_cx,
-            max_cx - bound_max_cx, 
-            bound_min_cy - min_cy,
-            max_cy - bound_max_cy
-        ];
+        if let Some(window) = merge_window(dataset, &merge_transform,
+                (width as isize, height as isize))? {
+            sources.push((*dataset, window));
+        }
+    }
 
-        let (mut index, mut value) = (0, bound_differences[0]);
-        for (i, x) in bound_differences.iter().enumerate().skip(1) {
-            if x > &value {
-                value = *x;
-                index = i;
+    // each block holds rows of a batch of concurrently read sources
+    let (_, block_height) = merge_dataset.rasterband(1)?.block_size();
+    let row_size = options.threads * merge_dataset.raster_count() as usize
+        * width * std::mem::size_of::<T>();
+    let rows = crate::parallel::block_rows(block_height, row_size);
+    let windows = crate::parallel::row_windows(width, height, rows);
+
+    for (b, window) in windows.iter().enumerate() {
+        crate::report_progress(progress,
+            0.9 * b as f64 / windows.len() as f64, "copy")?;
+
+        for batch in sources.chunks(options.threads) {
+            // read batch of source rows concurrently
+            let results: Vec<Result<Vec<Vec<T>>, String>> =
+                    std::thread::scope(|scope| {
+                let handles: Vec<_> = batch.iter()
+                        .map(|(dataset, src_window)| {
+                    let dataset = DatasetRef(*dataset);
+                    let src_window = *src_window;
+                    let window = *window;
+                    let resampling = options.resampling;
+
+                    scope.spawn(move || (0..dataset.0.raster_count())
+                        .map(|i| read_block_rows::<T>(dataset.0, i+1,
+                            &src_window, &window, resampling))
+                        .collect::<Result<Vec<Vec<T>>, _>>()
+                        .map_err(|e| e.to_string()))
+                }).collect();
+
+                handles.into_iter().map(|handle| handle.join()
+                    .unwrap_or_else(|_| Err("merge thread panicked".into())))
+                    .collect()
+            });
+
+            // write source rows in dataset order
+            let ((_, block_y), (_, block_rows)) = *window;
+            for ((_, src_window), result) in
+                    batch.iter().zip(results.into_iter()) {
+                let buffers = result?;
+                let (x_offset, y_offset) = src_window.dst_offset;
+                let start = y_offset.max(block_y);
+                let end = (y_offset + src_window.dst_size.1 as isize)
+                    .min(block_y + block_rows as isize);
+                if start >= end {
+                    continue;
+                }
+
+                let size = (src_window.dst_size.0, (end - start) as usize);
+                for (i, data) in buffers.into_iter().enumerate() {
+                    merge_dataset.rasterband((i + 1) as isize)?.write::<T>(
+                        (x_offset, start), size, &Buffer::new(size, data))?;
+                }
             }
         }
+    }
 
-        match index {
-            0 => bound_min_px -= 1,
-            1 => bound_max_px += 1,
-            2 => bound_max_py += 1,
-            3 => bound_min_py -= 1,
-            _ => unreachable!(),
-        }
+    Ok(())
+}
+
+fn merge_grid(datasets: &[&Dataset], options: &MergeOptions)
+        -> Result<([f64; 6], isize, isize), Box<dyn Error>> {
+    // if defined -> use explicit target grid
+    if let Some(grid) = &options.grid {
+        let (res_x, res_y) = (grid.resolution.0.abs(),
+            grid.resolution.1.abs());
+        let merge_transform =
+            [grid.min_x, res_x, 0.0, grid.max_y, 0.0, -res_y];
+
+        let dst_width = ((grid.max_x - grid.min_x) / res_x).round();
+        let dst_height = ((grid.max_y - grid.min_y) / res_y).round();
+
+        return Ok((merge_transform, dst_width as isize,
+            dst_height as isize));
     }
 
-    //println!("  PIXEL BOUNDS: {} {} {} {}", bound_min_px, 
-    //    bound_max_px, bound_min_py, bound_max_py);
+    // find minimum and maximum coordinates
+    let mut min_cx = f64::MAX;
+    let mut max_cx = f64::MIN;
+    let mut min_cy = f64::MAX;
+    let mut max_cy = f64::MIN;
+
+    // find finest pixel resolution
+    let mut res_x = f64::MAX;
+    let mut res_y = f64::MAX;
 
-    //println!("  COORDINATE BOUNDS: {} {} {} {}", bound_min_cx, 
-    //    bound_max_cx, bound_min_cy, bound_max_cy);
+    for dataset in datasets.iter() {
+        let transform = dataset.geo_transform()?;
+        let (src_width, src_height) = dataset.raster_size();
+        let (width, height) = (src_width as f64, src_height as f64);
 
-    //println!("  IMAGE DIMENSIONS: {} {}", 
-    //    bound_max_px - bound_min_px, bound_max_py - bound_min_py);
+        // compute bounds from all four corners
+        for (px, py) in [(0.0, 0.0), (width, 0.0),
+                (0.0, height), (width, height)].iter() {
+            let cx = transform[0] + (px * transform[1])
+                + (py * transform[2]);
+            let cy = transform[3] + (px * transform[4])
+                + (py * transform[5]);
+
+            min_cx = min_cx.min(cx);
+            max_cx = max_cx.max(cx);
+            min_cy = min_cy.min(cy);
+            max_cy = max_cy.max(cy);
+        }
 
-    // skip window if the pixel boundaries don't fall within image
-    if bound_max_px < 0 || bound_min_px >= src_width as isize
-            || bound_max_py < 0 || bound_min_py >= src_height as isize {
-        return Ok(None);
+        res_x = res_x.min(transform[1].abs());
+        res_y = res_y.min(transform[5].abs());
     }
 
-    // compute raster offsets
-    let src_x_offset = bound_min_px.max(0) as isize;
-    let src_y_offset = bound_min_py.max(0) as isize;
+    if let Some((x, y)) = options.resolution {
+        res_x = x.abs();
+        res_y = y.abs();
+    }
 
-    let buf_width = (bound_max_px.min(src_width as isize) 
-        - bound_min_px.max(0)) as usize;
-    let buf_height = (bound_max_py.min(src_height as isize)
-        - bound_min_py.max(0)) as usize;
+    if let Some(origin) = options.grid_origin {
+        let bounds = crate::coordinate::snap_to_grid(
+            (min_cx, max_cx, min_cy, max_cy), origin, (res_x, res_y));
+        min_cx = bounds.0;
+        max_cx = bounds.1;
+        min_cy = bounds.2;
+        max_cy = bounds.3;
+    }
 
-    let dst_x_offset = (0 - bound_min_px).max(0) as isize;
-    let dst_y_offset = (0 - bound_min_py).max(0) as isize;
+    // compute merged image dimensions
+    let dst_width = ((max_cx - min_cx) / res_x).round() as isize;
+    let dst_height = ((max_cy - min_cy) / res_y).round() as isize;
+    log::debug!("merge bounds ({}, {}, {}, {}) dimensions {}x{}",
+        min_cx, max_cx, min_cy, max_cy, dst_width, dst_height);
 
-    let dst_width = (bound_max_px - bound_min_px) as isize;
-    let dst_height = (bound_max_py - bound_min_py) as isize;
+    // modify transform
+    let mut merge_transform = datasets[0].geo_transform()?;
+    merge_transform[0] = min_cx;
+    merge_transform[1] = res_x.copysign(merge_transform[1]);
+    merge_transform[3] = max_cy;
+    merge_transform[5] = res_y.copysign(merge_transform[5]);
 
-    //println!("  SRC OFFSET: {} {}", src_x_offset, src_y_offset);
-    //println!("  SRC DIMENSIONS: {} {}", buf_width, buf_height);
+    Ok((merge_transform, dst_width, dst_height))
+}
 
-    //println!("  DST OFFSET: {} {}", dst_x_offset, dst_y_offset);
-    //println!("  DST DIMENSIONS: {} {}", dst_width, dst_height);
+pub fn merge_to_file(datasets: &[Dataset], path: &str, driver: &Driver,
+        creation_options: &[(&str, &str)])
+        -> Result<Dataset, Box<dyn Error>> {
+    merge_to_file_with_options(datasets, path, driver,
+        creation_options, &MergeOptions::default())
+}
 
-    // open memory driver
-    let driver = Driver::get("Mem")?;
+pub fn merge_to_file_with_options(datasets: &[Dataset], path: &str,
+        driver: &Driver, creation_options: &[(&str, &str)],
+        options: &MergeOptions) -> Result<Dataset, Box<dyn Error>> {
+    match options.strategy {
+        _ if options.provenance => return Err("provenance unsupported \
+            when merging to file".into()),
+        _ if options.threads > 1 => return Err("threads unsupported \
+            when merging to file".into()),
+        MergeStrategy::Last | MergeStrategy::NodataAware => {},
+        strategy => return Err(format!("merge strategy {:?} \
+            unsupported when merging to file", strategy).into()),
+    }
 
-    // initialize split Dataset
-    let rasterband = dataset.rasterband(1)?;
+    // ensure datasets are in same spatial reference system
+    let projection = datasets[0].projection();
+    let datasets: Vec<&Dataset> = datasets.iter().collect();
+    let warped_datasets =
+        validate_projections(&datasets, &projection, options)?;
+    let datasets: Vec<&Dataset> = datasets.iter()
+        .zip(warped_datasets.iter())
+        .map(|(dataset, warped)| warped.as_ref().unwrap_or(*dataset))
+        .collect();
+
+    // if requested -> match rasterbands by description
+    let matched_datasets = match_bands(&datasets, options)?;
+    let datasets: Vec<&Dataset> = datasets.iter()
+        .zip(matched_datasets.iter())
+        .map(|(dataset, matched)| matched.as_ref().unwrap_or(*dataset))
+        .collect();
+
+    // if requested -> match histograms to the first dataset
+    let histogram_datasets = match_histograms(&datasets, options)?;
+    let datasets: Vec<&Dataset> = datasets.iter()
+        .zip(histogram_datasets.iter())
+        .map(|(dataset, matched)| matched.as_ref().unwrap_or(*dataset))
+        .collect();
+
+    // compute merged image transform and dimensions
+    let (merge_transform, dst_width, dst_height) =
+        merge_grid(&datasets, options)?;
+
+    // initialize merge Dataset on disk
+    let rasterband = datasets[0].rasterband(1)?;
     let gdal_type = rasterband.band_type();
     let no_data_value = rasterband.no_data_value();
 
-    let split_dataset = crate::init_dataset(&driver,
-        "unreachable", gdal_type, dst_width, dst_height,
-        dataset.raster_count(), no_data_value)?;
+    let merge_dataset = crate::create_dataset(driver, path, gdal_type,
+        dst_width, dst_height, datasets[0].raster_count(),
+        no_data_value, creation_options)?;
 
-    // modify transform
-    //let mut transform = dataset.geo_transform()?;
-    transform[0] = transform[0] + (bound_min_px as f64 * transform[1])
-        + (bound_min_py as f64 * transform[2]);
-    transform[3] = transform[3] + (bound_min_px as f64 * transform[4])
-        + (bound_min_py as f64 * transform[5]);
+    merge_dataset.set_geo_transform(&merge_transform)?;
+    merge_dataset.set_projection(&projection)?;
+    crate::copy_all_metadata(datasets[0], &merge_dataset)?;
 
-    split_dataset.set_geo_transform(&transform)?;
-    split_dataset.set_projection(&projection)?;
+    // compute number of rows copied per block
+    let (_, block_height) = merge_dataset.rasterband(1)?.block_size();
+    let pixel_size = unsafe {
+        gdal_sys::GDALGetDataTypeSizeBytes(gdal_type) as usize
+    };
 
-    // copy rasterband data to new image
-    for i in 0..dataset.raster_count() {
-        crate::copy_raster(dataset, i+1, 
-            (src_x_offset, src_y_offset),
-            (buf_width, buf_height),
-            &split_dataset, i+1,
-            (dst_x_offset, dst_y_offset), 
-            (buf_width, buf_height))?;
+    // copy source rasters block by block
+    for dataset in datasets.iter() {
+        let window = match merge_window(dataset, &merge_transform,
+                (dst_width, dst_height))? {
+            Some(window) => window,
+            None => continue,
+        };
+
+        let (src_width, src_height) = window.src_size;
+        let (buf_width, buf_height) = window.dst_size;
+        let scale = src_height as f64 / buf_height as f64;
+        let row_size = (src_width as f64 * scale) as usize + buf_width;
+        let block_rows = crate::parallel::block_rows(block_height,
+            row_size * pixel_size);
+        for row in (0..buf_height).step_by(block_rows) {
+            if let Some(token) = &options.cancellation {
+                token.check("copy")?;
+            }
+
+            // source rows are read through a fractional window so
+            // blocks match pixels of an in-memory merge
+            let rows = block_rows.min(buf_height - row);
+            for i in 0..dataset.raster_count() {
+                copy_block_rows(dataset, i+1, &window,
+                    &merge_dataset, (row, rows), options)?;
+            }
+        }
     }
 
-    Ok(Some(split_dataset))
+    if !options.overviews.is_empty() {
+        crate::build_overviews(&merge_dataset,
+            &options.overviews, options.resampling)?;
+    }
+
+    Ok(merge_dataset)
 }
 
-#[cfg(test)]
-mod tests {
-    //use crate::coordinate::Geocode;
+fn copy_block_rows(dataset: &Dataset, index: isize, window: &MergeWindow,
+        merge_dataset: &Dataset, rows: (usize, usize),
+        options: &MergeOptions) -> Result<(), Box<dyn Error>> {
+    match dataset.rasterband(index)?.band_type() {
+        GDALDataType::GDT_Byte => _copy_block_rows::<u8>(dataset,
+            index, window, merge_dataset, rows, options),
+        GDALDataType::GDT_Int16 => _copy_block_rows::<i16>(dataset,
+            index, window, merge_dataset, rows, options),
+        GDALDataType::GDT_UInt16 => _copy_block_rows::<u16>(dataset,
+            index, window, merge_dataset, rows, options),
+        GDALDataType::GDT_Float32 => _copy_block_rows::<f32>(dataset,
+            index, window, merge_dataset, rows, options),
+        _ => unimplemented!(),
+    }
+}
 
-    //use gdal::{Dataset, Driver};
-    //use gdal_sys::GDALDataType;
+fn _copy_block_rows<T: Copy + FromPrimitive + GdalType + PartialEq>(
+        dataset: &Dataset, index: isize, window: &MergeWindow,
+        merge_dataset: &Dataset, rows: (usize, usize),
+        options: &MergeOptions) -> Result<(), Box<dyn Error>> {
+    let rasterband = dataset.rasterband(index)?;
+    let buffer = crate::read_resampled_rows::<T>(&rasterband,
+        window.src_offset, window.src_size, window.dst_size,
+        rows, options.resampling)?;
+
+    let dst_offset = (window.dst_offset.0,
+        window.dst_offset.1 + rows.0 as isize);
+    let dst_size = (window.dst_size.0, rows.1);
+    let merge_rasterband = merge_dataset.rasterband(index)?;
+
+    // if nodata aware -> only overwrite with valid source pixels
+    let no_data_value = rasterband.no_data_value().map(T::from_f64);
+    let buffer = match no_data_value {
+        Some(no_data_value)
+                if options.strategy == MergeStrategy::NodataAware => {
+            let mut dst_buffer = merge_rasterband.read_as::<T>(dst_offset,
+                dst_size, dst_size)?;
+            for (dst_pixel, pixel) in dst_buffer.data.iter_mut()
+                    .zip(buffer.data.iter()) {
+                if *pixel != no_data_value {
+                    *dst_pixel = *pixel;
+                }
+            }
+
+            dst_buffer
+        },
+        _ => buffer,
+    };
+
+    merge_rasterband.write::<T>(dst_offset, dst_size, &buffer)?;
+    Ok(())
+}
+
+type CopyFunction = fn(&Dataset, isize, (isize, isize), (usize, usize),
+    &Dataset, isize, (isize, isize), (usize, usize), Resampling)
+    -> Result<(), Box<dyn Error>>;
+
+fn copy_function(options: &MergeOptions) -> CopyFunction {
+    match options.strategy {
+        MergeStrategy::NodataAware => crate::copy_valid_raster,
+        _ => crate::copy_raster_resampled,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct MergeWindow {
+    src_offset: (isize, isize),
+    src_size: (usize, usize),
+    dst_offset: (isize, isize),
+    dst_size: (usize, usize),
+    // unclipped dataset footprint on the target grid
+    footprint: (isize, isize, usize, usize),
+}
+
+fn merge_window(dataset: &Dataset, merge_transform: &[f64; 6],
+        merge_size: (isize, isize))
+        -> Result<Option<MergeWindow>, Box<dyn Error>> {
+    let transform = dataset.geo_transform()?;
+    let (src_width, src_height) = dataset.raster_size();
+
+    // compute dataset footprint on the target grid
+    let (x, y) = crate::coordinate::coord_to_pixel(transform[0],
+        transform[3], merge_transform)?;
+    let (x, y) = (x.round() as isize, y.round() as isize);
+    let width = (src_width as f64 * transform[1]
+        / merge_transform[1]).round() as isize;
+    let height = (src_height as f64 * transform[5]
+        / merge_transform[5]).round() as isize;
+
+    // clip footprint to the target grid
+    let (min_x, max_x) = (x.max(0), (x + width).min(merge_size.0));
+    let (min_y, max_y) = (y.max(0), (y + height).min(merge_size.1));
+    if min_x >= max_x || min_y >= max_y {
+        return Ok(None);
+    }
+
+    // compute source window covering the clipped footprint
+    let scale_x = src_width as f64 / width as f64;
+    let scale_y = src_height as f64 / height as f64;
+
+    let src_min_x = ((min_x - x) as f64 * scale_x).round() as isize;
+    let src_max_x = (((max_x - x) as f64 * scale_x).round() as isize)
+        .min(src_width as isize).max(src_min_x + 1);
+    let src_min_y = ((min_y - y) as f64 * scale_y).round() as isize;
+    let src_max_y = (((max_y - y) as f64 * scale_y).round() as isize)
+        .min(src_height as isize).max(src_min_y + 1);
+
+    Ok(Some(MergeWindow {
+        src_offset: (src_min_x, src_min_y),
+        src_size: ((src_max_x - src_min_x) as usize,
+            (src_max_y - src_min_y) as usize),
+        dst_offset: (min_x, min_y),
+        dst_size: ((max_x - min_x) as usize, (max_y - min_y) as usize),
+        footprint: (x, y, width as usize, height as usize),
+    }))
+}
+
+fn merge_band(datasets: &[&Dataset], index: isize,
+        merge_dataset: &Dataset, provenance_index: Option<isize>,
+        options: &MergeOptions) -> Result<(), Box<dyn Error>> {
+    match merge_dataset.rasterband(index)?.band_type() {
+        GDALDataType::GDT_Byte => _merge_band::<u8>(datasets,
+            index, merge_dataset, provenance_index, options),
+        GDALDataType::GDT_Int16 => _merge_band::<i16>(datasets,
+            index, merge_dataset, provenance_index, options),
+        GDALDataType::GDT_UInt16 => _merge_band::<u16>(datasets,
+            index, merge_dataset, provenance_index, options),
+        GDALDataType::GDT_Float32 => _merge_band::<f32>(datasets,
+            index, merge_dataset, provenance_index, options),
+        _ => unimplemented!(),
+    }
+}
+
+fn _merge_band<T: Copy + FromPrimitive + GdalType
+            + PartialOrd + ToPrimitive + Send + Sync>(datasets: &[&Dataset],
+        index: isize, merge_dataset: &Dataset,
+        provenance_index: Option<isize>, options: &MergeOptions)
+        -> Result<(), Box<dyn Error>> {
+    let (width, height) = merge_dataset.raster_size();
+    let merge_transform = merge_dataset.geo_transform()?;
+    let merge_rasterband = merge_dataset.rasterband(index)?;
+    let no_data_value = T::from_f64(
+        merge_rasterband.no_data_value().unwrap_or(0.0));
+
+    // compute source windows on the target grid
+    let mut sources = Vec::new();
+    for (k, dataset) in datasets.iter().enumerate() {
+        let window = match merge_window(dataset, &merge_transform,
+                (width as isize, height as isize))? {
+            Some(window) => window,
+            None => continue,
+        };
+
+        let src_no_data_value = dataset.rasterband(index)?.no_data_value()
+            .map(T::from_f64).unwrap_or(no_data_value);
+        sources.push((k, window, src_no_data_value));
+    }
+
+    // combine source pixels over blocks of merged pixels, reading only
+    // source rows intersecting each block, with written flags, sums,
+    // weights and source indices for each pixel
+    let (_, block_height) = merge_rasterband.block_size();
+    let source_width: usize = sources.iter()
+        .map(|x| (x.1).dst_size.0).sum();
+    let row_size = width * (std::mem::size_of::<T>() + 19)
+        + source_width * std::mem::size_of::<T>();
+    let rows = crate::parallel::block_rows(block_height, row_size);
+    let windows = crate::parallel::row_windows(width, height, rows);
+    let blocks = crate::parallel::par_process_blocks(&windows,
+        |_, window| {
+            let data = merge_rasterband
+                .read_as::<T>(window.0, window.1, window.1)?.data;
+
+            let mut buffers = Vec::new();
+            for (k, src_window, _) in sources.iter() {
+                buffers.push(read_block_rows::<T>(datasets[*k], index,
+                    src_window, window, options.resampling)?);
+            }
+
+            Ok((data, buffers))
+        },
+        |window, (data, buffers)| merge_block(&sources, &buffers, data,
+            window, no_data_value, options.strategy,
+            provenance_index.is_some()))?;
+
+    // write merged pixels and source indices to rasterbands
+    for (window, (data, indices)) in windows.iter().zip(blocks.into_iter()) {
+        let buffer = Buffer::new(window.1, data);
+        merge_rasterband.write::<T>(window.0, window.1, &buffer)?;
+
+        if let Some(provenance_index) = provenance_index {
+            let buffer = Buffer::new(window.1, indices);
+            merge_dataset.rasterband(provenance_index)?
+                .write::<u16>(window.0, window.1, &buffer)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_block_rows<T: Copy + GdalType>(dataset: &Dataset, index: isize,
+        src_window: &MergeWindow, window: &crate::parallel::Window,
+        resampling: Resampling) -> Result<Vec<T>, Box<dyn Error>> {
+    // compute source rows intersecting the block
+    let ((_, block_y), (_, block_height)) = *window;
+    let (y_offset, buf_height) = (src_window.dst_offset.1,
+        src_window.dst_size.1 as isize);
+    let start = y_offset.max(block_y);
+    let end = (y_offset + buf_height).min(block_y + block_height as isize);
+    if start >= end {
+        return Ok(Vec::new());
+    }
+
+    let rasterband = dataset.rasterband(index)?;
+    Ok(crate::read_resampled_rows::<T>(&rasterband,
+        src_window.src_offset, src_window.src_size, src_window.dst_size,
+        ((start - y_offset) as usize, (end - start) as usize),
+        resampling)?.data)
+}
 
-    //use std::path::Path;
+fn merge_block<T: Copy + FromPrimitive + PartialOrd + ToPrimitive>(
+        sources: &[(usize, MergeWindow, T)], buffers: &[Vec<T>],
+        mut data: Vec<T>, window: &crate::parallel::Window,
+        no_data_value: T, strategy: MergeStrategy, provenance: bool)
+        -> (Vec<T>, Vec<u16>) {
+    let ((_, block_y), (width, block_height)) = *window;
+    let block_end = block_y + block_height as isize;
+
+    let mut written = vec![false; data.len()];
+    let (mut sums, mut weights) = match strategy {
+        MergeStrategy::Mean | MergeStrategy::Feather(_) =>
+            (vec![0.0f64; data.len()], vec![0.0f64; data.len()]),
+        _ => (Vec::new(), Vec::new()),
+    };
+
+    // index of the dataset supplying each pixel
+    let mut indices = match provenance {
+        true => vec![u16::MAX; data.len()],
+        false => Vec::new(),
+    };
+
+    for ((k, src_window, src_no_data_value), buffer) in
+            sources.iter().zip(buffers.iter()) {
+        // iterate over source rows intersecting the block
+        let (x_offset, y_offset) = src_window.dst_offset;
+        let (buf_width, buf_height) = src_window.dst_size;
+        let start = y_offset.max(block_y);
+        let end = (y_offset + buf_height as isize).min(block_end);
+        for dst_y in start..end {
+            let y = (dst_y - start) as usize;
+            for x in 0..buf_width {
+                let value = buffer[y * buf_width + x];
+                let valid = value != *src_no_data_value;
+                let value = if valid { value } else { no_data_value };
+
+                let j = (dst_y - block_y) as usize * width
+                    + x + x_offset as usize;
+                match strategy {
+                    MergeStrategy::Mean if valid => {
+                        sums[j] += value.to_f64();
+                        weights[j] += 1.0;
+                    },
+                    MergeStrategy::Feather(feather_width) if valid => {
+                        // weight by distance to the dataset boundary
+                        let (fx, fy, fw, fh) = src_window.footprint;
+                        let px = x as isize + x_offset - fx;
+                        let py = dst_y - fy;
+                        let distance = px.min(py).min(fw as isize - 1 - px)
+                            .min(fh as isize - 1 - py).max(0);
+                        let weight = ((distance + 1) as f64
+                            / feather_width.max(1) as f64).min(1.0);
+
+                        sums[j] += value.to_f64() * weight;
+                        weights[j] += weight;
+                    },
+                    _ => {
+                        let supplied = match strategy {
+                            MergeStrategy::First => !written[j],
+                            MergeStrategy::Last => true,
+                            MergeStrategy::NodataAware => valid,
+                            MergeStrategy::Min => valid
+                                && (!written[j] || value < data[j]),
+                            MergeStrategy::Max => valid
+                                && (!written[j] || value > data[j]),
+                            _ => false,
+                        };
+
+                        if supplied {
+                            data[j] = value;
+                            if let Some(index) = indices.get_mut(j) {
+                                *index = *k as u16;
+                            }
+                        }
+                    },
+                }
+
+                if valid || strategy == MergeStrategy::First {
+                    written[j] = true;
+                }
+            }
+        }
+    }
+
+    // compute weighted mean of valid pixels
+    for (j, weight) in weights.iter().enumerate() {
+        if *weight != 0.0 {
+            data[j] = T::from_f64(sums[j] / *weight);
+        }
+    }
+
+    (data, indices)
+}
+
+fn match_bands(datasets: &[&Dataset], options: &MergeOptions)
+        -> Result<Vec<Option<Dataset>>, Box<dyn Error>> {
+    let mut matched_datasets = Vec::new();
+    if !options.match_bands {
+        matched_datasets.resize_with(datasets.len(), || None);
+        return Ok(matched_datasets);
+    }
+
+    // validate first dataset band descriptions are unique
+    let descriptions = crate::band_descriptions(datasets[0])?;
+    for (i, description) in descriptions.iter().enumerate() {
+        if description.is_empty() {
+            return Err(format!("band {} of the first dataset \
+                has no description", i + 1).into());
+        } else if descriptions[..i].contains(description) {
+            return Err(format!("band description '{}' is duplicated \
+                in the first dataset", description).into());
+        }
+    }
+
+    for (k, dataset) in datasets.iter().enumerate() {
+        // find rasterband index of each description
+        let dataset_descriptions = crate::band_descriptions(dataset)?;
+        let mut indices = Vec::new();
+        for description in descriptions.iter() {
+            match dataset_descriptions.iter()
+                    .position(|x| x == description) {
+                Some(i) => indices.push((i + 1) as isize),
+                None => return Err(format!("dataset {} has no band '{}' \
+                    (found: {})", k, description,
+                    dataset_descriptions.join(", ")).into()),
+            }
+        }
+
+        // if rasterbands differ -> select matched rasterbands
+        let identity = indices.len() == dataset_descriptions.len()
+            && indices.iter().enumerate()
+                .all(|(i, index)| *index == (i + 1) as isize);
+        if identity {
+            matched_datasets.push(None);
+        } else {
+            matched_datasets.push(Some(
+                crate::select_bands(dataset, &indices)?));
+        }
+    }
+
+    Ok(matched_datasets)
+}
+
+fn match_histograms(datasets: &[&Dataset], options: &MergeOptions)
+        -> Result<Vec<Option<Dataset>>, Box<dyn Error>> {
+    let mut matched_datasets = Vec::new();
+    for (k, dataset) in datasets.iter().enumerate() {
+        if options.histogram_match && k != 0 {
+            matched_datasets.push(Some(
+                crate::calc::histogram_match(dataset, datasets[0])?));
+        } else {
+            matched_datasets.push(None);
+        }
+    }
+
+    Ok(matched_datasets)
+}
+
+fn validate_projections(datasets: &[&Dataset], projection: &str,
+        options: &MergeOptions)
+        -> Result<Vec<Option<Dataset>>, Box<dyn Error>> {
+    let crs = crate::coordinate::identify_crs(projection)?;
+
+    let mut warped_datasets = Vec::new();
+    for (i, dataset) in datasets.iter().enumerate() {
+        // rotated or sheared datasets require warping to north-up
+        let transform = dataset.geo_transform()?;
+        let rotated = transform[2] != 0.0 || transform[4] != 0.0;
+
+        let dataset_crs =
+            crate::coordinate::identify_crs(&dataset.projection())?;
+        if dataset_crs == crs && !rotated {
+            warped_datasets.push(None);
+        } else if options.reproject {
+            warped_datasets.push(Some(
+                warp(dataset, projection, options.resampling)?));
+        } else if rotated {
+            return Err(format!("dataset {} has a rotated geo transform",
+                i).into());
+        } else {
+            return Err(format!("dataset {} spatial reference system \
+                differs from the target", i).into());
+        }
+    }
+
+    Ok(warped_datasets)
+}
+
+fn warp(dataset: &Dataset, projection: &str, resampling: Resampling)
+        -> Result<Dataset, Box<dyn Error>> {
+    let (transform, width, height) =
+        suggested_warp_output(dataset, projection)?;
+
+    // initialize warp Dataset
+    let driver = Driver::get("Mem")?;
+    let rasterband = dataset.rasterband(1)?;
+    let warp_dataset = crate::init_dataset(&driver, "unreachable",
+        rasterband.band_type(), width as isize, height as isize,
+        dataset.raster_count(), rasterband.no_data_value())?;
+
+    warp_dataset.set_geo_transform(&transform)?;
+    warp_dataset.set_projection(projection)?;
+
+    reproject_image(dataset, &warp_dataset, resampling)?;
+    Ok(warp_dataset)
+}
+
+fn suggested_warp_output(dataset: &Dataset, projection: &str)
+        -> Result<([f64; 6], usize, usize), Box<dyn Error>> {
+    let src_projection = CString::new(dataset.projection())?;
+    let dst_projection = CString::new(projection)?;
+
+    // compute suggested output dimensions and transform
+    let mut transform = [0.0f64; 6];
+    let (mut width, mut height): (c_int, c_int) = (0, 0);
+    unsafe {
+        let transformer = gdal_sys::GDALCreateGenImgProjTransformer(
+            dataset.c_dataset(), src_projection.as_ptr(),
+            std::ptr::null_mut(), dst_projection.as_ptr(), 0, 0.0, 0);
+        if transformer.is_null() {
+            return Err("failed to initialize warp transformer".into());
+        }
+
+        let rv = gdal_sys::GDALSuggestedWarpOutput(dataset.c_dataset(),
+            Some(gdal_sys::GDALGenImgProjTransform), transformer,
+            transform.as_mut_ptr(), &mut width, &mut height);
+        gdal_sys::GDALDestroyGenImgProjTransformer(transformer);
+
+        if rv != CPLErr::CE_None {
+            return Err("failed to compute warp output".into());
+        }
+    }
+
+    Ok((transform, width as usize, height as usize))
+}
+
+#[derive(Clone, Debug)]
+pub struct ReprojectOptions {
+    /// resampling method used when warping pixels
+    pub resampling: Resampling,
+    /// target (x, y) pixel size, defaults to the suggested warp output
+    pub resolution: Option<(f64, f64)>,
+    /// (x, y) origin the output extent is snapped to at the target
+    /// resolution, aligning outputs with an external grid
+    pub grid_origin: Option<(f64, f64)>,
+    /// output nodata value, defaults to that of the first rasterband
+    pub no_data_value: Option<f64>,
+}
+
+impl Default for ReprojectOptions {
+    fn default() -> Self {
+        ReprojectOptions {
+            resampling: Resampling::Nearest,
+            resolution: None,
+            grid_origin: None,
+            no_data_value: None,
+        }
+    }
+}
+
+pub fn reproject(dataset: &Dataset, target: &Target,
+        options: &ReprojectOptions) -> Result<Dataset, Box<dyn Error>> {
+    let projection = target.to_spatial_ref()?.to_wkt()?;
+    let (transform, width, height) =
+        suggested_warp_output(dataset, &projection)?;
+
+    // compute suggested output extent
+    let mut bounds = (transform[0], transform[0] + width as f64 * transform[1],
+        transform[3] + height as f64 * transform[5], transform[3]);
+    let resolution = options.resolution
+        .unwrap_or((transform[1], -transform[5]));
+    let resolution = (resolution.0.abs(), resolution.1.abs());
+
+    if let Some(origin) = options.grid_origin {
+        bounds = crate::coordinate::snap_to_grid(bounds, origin, resolution);
+    }
+
+    // compute output dimensions at the target resolution
+    let dst_width = (((bounds.1 - bounds.0) / resolution.0).round()
+        as usize).max(1);
+    let dst_height = (((bounds.3 - bounds.2) / resolution.1).round()
+        as usize).max(1);
+    let dst_transform = [bounds.0, resolution.0, 0.0,
+        bounds.3, 0.0, -resolution.1];
+
+    // initialize reproject Dataset filled with nodata
+    let driver = Driver::get("Mem")?;
+    let rasterband = dataset.rasterband(1)?;
+    let no_data_value = options.no_data_value
+        .or_else(|| rasterband.no_data_value());
+    let reproject_dataset = crate::init_dataset(&driver, "unreachable",
+        rasterband.band_type(), dst_width as isize, dst_height as isize,
+        dataset.raster_count(), no_data_value)?;
+
+    reproject_dataset.set_geo_transform(&dst_transform)?;
+    reproject_dataset.set_projection(&projection)?;
+    crate::copy_all_metadata(dataset, &reproject_dataset)?;
+
+    reproject_image(dataset, &reproject_dataset, options.resampling)?;
+    Ok(reproject_dataset)
+}
+
+fn reproject_image(dataset: &Dataset, warp_dataset: &Dataset,
+        resampling: Resampling) -> Result<(), Box<dyn Error>> {
+    let src_projection = CString::new(dataset.projection())?;
+    let dst_projection = CString::new(warp_dataset.projection())?;
+
+    // reproject dataset onto the warp dataset grid
+    let rv = unsafe {
+        gdal_sys::GDALReprojectImage(dataset.c_dataset(),
+            src_projection.as_ptr(), warp_dataset.c_dataset(),
+            dst_projection.as_ptr(), resampling.to_gdal_warp(),
+            0.0, 0.125, None, std::ptr::null_mut(), std::ptr::null_mut())
+    };
+
+    if rv != CPLErr::CE_None {
+        return Err("failed to reproject dataset".into());
+    }
+
+    Ok(())
+}
+
+pub fn resample(dataset: &Dataset, pixel_size: f64, resampling: Resampling)
+        -> Result<Dataset, Box<dyn Error>> {
+    let transform = dataset.geo_transform()?;
+    if transform[2] != 0.0 || transform[4] != 0.0 {
+        return Err("resampling rotated datasets is unsupported".into());
+    } else if pixel_size <= 0.0 {
+        return Err("pixel size must be positive".into());
+    }
+
+    // compute dimensions covering the same extent
+    let (width, height) = dataset.raster_size();
+    let dst_width = ((width as f64 * transform[1].abs() / pixel_size)
+        .round() as usize).max(1);
+    let dst_height = ((height as f64 * transform[5].abs() / pixel_size)
+        .round() as usize).max(1);
+
+    let dst_transform = [transform[0], pixel_size.copysign(transform[1]),
+        0.0, transform[3], 0.0, pixel_size.copysign(transform[5])];
+
+    // initialize resample Dataset
+    let driver = Driver::get("Mem")?;
+    let rasterband = dataset.rasterband(1)?;
+    let resample_dataset = crate::init_dataset(&driver, "unreachable",
+        rasterband.band_type(), dst_width as isize, dst_height as isize,
+        dataset.raster_count(), rasterband.no_data_value())?;
+
+    resample_dataset.set_geo_transform(&dst_transform)?;
+    resample_dataset.set_projection(&dataset.projection())?;
+    crate::copy_all_metadata(dataset, &resample_dataset)?;
+
+    for i in 0..dataset.raster_count() {
+        crate::copy_raster_resampled(dataset, i+1, (0, 0),
+            (width, height), &resample_dataset, i+1, (0, 0),
+            (dst_width, dst_height), resampling)?;
+    }
+
+    Ok(resample_dataset)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PansharpenMethod {
+    /// scale bands by the ratio of pan to the multispectral mean
+    Brovey,
+    /// scale bands by the ratio of pan to a weighted multispectral sum,
+    /// with one weight per multispectral rasterband
+    Weighted(Vec<f64>),
+}
+
+pub fn pansharpen(ms_dataset: &Dataset, pan_dataset: &Dataset,
+        method: &PansharpenMethod) -> Result<Dataset, Box<dyn Error>> {
+    let count = ms_dataset.raster_count();
+    let weights = match method {
+        PansharpenMethod::Brovey => vec![1.0 / count as f64; count as usize],
+        PansharpenMethod::Weighted(weights) => {
+            if weights.len() != count as usize {
+                return Err("pansharpen weights do not match \
+                    multispectral rasterbands".into());
+            }
+
+            weights.clone()
+        },
+    };
+
+    // align multispectral rasterbands with the panchromatic grid
+    let (width, height) = pan_dataset.raster_size();
+    let driver = Driver::get("Mem")?;
+    let rasterband = ms_dataset.rasterband(1)?;
+    let no_data_value = rasterband.no_data_value();
+
+    let init_aligned = || -> Result<Dataset, Box<dyn Error>> {
+        let dataset = crate::init_dataset(&driver, "unreachable",
+            rasterband.band_type(), width as isize, height as isize,
+            count, no_data_value)?;
+
+        dataset.set_geo_transform(&pan_dataset.geo_transform()?)?;
+        dataset.set_projection(&pan_dataset.projection())?;
+        crate::copy_all_metadata(ms_dataset, &dataset)?;
+        Ok(dataset)
+    };
+
+    let aligned_dataset = init_aligned()?;
+    let sharpen_dataset = init_aligned()?;
+    reproject_image(ms_dataset, &aligned_dataset, Resampling::Bilinear)?;
+
+    // read panchromatic and aligned blocks with nodata as nan
+    let pan_rasterband = pan_dataset.rasterband(1)?;
+    let row_size = width * (count as usize + 1) * 16;
+    let windows = crate::parallel::block_windows(&aligned_dataset, row_size)?;
+    let blocks = crate::parallel::par_process_blocks(&windows,
+            |_, window| {
+        let mut buffers = vec![read_nan(&pan_rasterband, window)?];
+        for i in 0..count {
+            let rasterband = aligned_dataset.rasterband(i+1)?;
+            buffers.push(read_nan(&rasterband, window)?);
+        }
+
+        Ok(buffers)
+    }, |_, mut buffers| {
+        let pan = buffers.remove(0);
+        for j in 0..pan.len() {
+            let intensity: f64 = buffers.iter().zip(weights.iter())
+                .map(|(x, w)| x[j] * w).sum();
+
+            // invalid pixels become nodata in every rasterband
+            let ratio = pan[j] / intensity;
+            for buffer in buffers.iter_mut() {
+                buffer[j] = match ratio.is_finite() {
+                    true => buffer[j] * ratio,
+                    false => f64::NAN,
+                };
+            }
+        }
+
+        buffers
+    })?;
+
+    // write sharpened rasterbands, converting nan to nodata
+    let no_data_value = no_data_value.unwrap_or(0.0);
+    for (window, buffers) in windows.iter().zip(blocks.into_iter()) {
+        for (i, mut data) in buffers.into_iter().enumerate() {
+            for value in data.iter_mut() {
+                if value.is_nan() {
+                    *value = no_data_value;
+                }
+            }
+
+            let buffer = Buffer::new(window.1, data);
+            sharpen_dataset.rasterband((i + 1) as isize)?
+                .write::<f64>(window.0, window.1, &buffer)?;
+        }
+    }
+
+    Ok(sharpen_dataset)
+}
+
+fn read_nan(rasterband: &RasterBand, window: &crate::parallel::Window)
+        -> Result<Vec<f64>, Box<dyn Error>> {
+    let no_data_value = rasterband.no_data_value();
+    let mut values = rasterband
+        .read_as::<f64>(window.0, window.1, window.1)?.data;
+    for value in values.iter_mut() {
+        if Some(*value) == no_data_value {
+            *value = f64::NAN;
+        }
+    }
+
+    Ok(values)
+}
+
+const WEB_MERCATOR_MAX_LATITUDE: f64 = 85.0511287798066;
+
+pub struct XyzTile {
+    pub z: u32,
+    pub x: u32,
+    pub y: u32,
+    pub dataset: Dataset,
+}
+
+pub fn split_xyz(dataset: &Dataset, zoom: u32, tile_size: usize,
+        resampling: Resampling) -> Result<Vec<XyzTile>, Box<dyn Error>> {
+    check_zoom(zoom)?;
+    if tile_size == 0 {
+        return Err("tile size must be positive".into());
+    }
+
+    // compute tile indices covering the dataset
+    let (min_cx, max_cx, min_cy, max_cy) =
+        crate::coordinate::get_bounds(dataset, 4326)?;
+    let (min_tx, min_ty) = xyz_tile_index(min_cx, max_cy, zoom);
+    let (max_tx, max_ty) = xyz_tile_index(max_cx, min_cy, zoom);
+
+    let projection = SpatialRef::from_epsg(3857)?.to_wkt()?;
+    let tile_extent = 2.0 * WEB_MERCATOR_EXTENT / (1u64 << zoom) as f64;
+    let resolution = tile_extent / tile_size as f64;
+
+    let driver = Driver::get("Mem")?;
+    let rasterband = dataset.rasterband(1)?;
+    let gdal_type = rasterband.band_type();
+    let no_data_value = rasterband.no_data_value();
+
+    let mut tiles = Vec::new();
+    for y in min_ty..=max_ty {
+        for x in min_tx..=max_tx {
+            // initialize tile Dataset on the web mercator grid
+            let tile_dataset = crate::init_dataset(&driver, "unreachable",
+                gdal_type, tile_size as isize, tile_size as isize,
+                dataset.raster_count(), no_data_value)?;
+
+            let transform = [
+                -WEB_MERCATOR_EXTENT + x as f64 * tile_extent, resolution, 0.0,
+                WEB_MERCATOR_EXTENT - y as f64 * tile_extent, 0.0, -resolution
+            ];
+
+            tile_dataset.set_geo_transform(&transform)?;
+            tile_dataset.set_projection(&projection)?;
+            crate::copy_all_metadata(dataset, &tile_dataset)?;
+
+            // resample dataset to the tile and skip empty tiles
+            reproject_image(dataset, &tile_dataset, resampling)?;
+            if crate::get_coverage(&tile_dataset)? == 0.0 {
+                continue;
+            }
+
+            tiles.push(XyzTile { z: zoom, x, y, dataset: tile_dataset });
+        }
+    }
+
+    Ok(tiles)
+}
+
+pub fn split_quadtile(dataset: &Dataset, zoom: u32,
+        options: &SplitOptions) -> Result<Vec<SplitResult>, Box<dyn Error>> {
+    check_zoom(zoom)?;
+
+    // compute tile indices covering the dataset
+    let (min_cx, max_cx, min_cy, max_cy) =
+        crate::coordinate::get_bounds(dataset, 4326)?;
+    let (min_tx, min_ty) = xyz_tile_index(min_cx, max_cy, zoom);
+    let (max_tx, max_ty) = xyz_tile_index(max_cx, min_cy, zoom);
+
+    let tile_extent = 2.0 * WEB_MERCATOR_EXTENT / (1u64 << zoom) as f64;
+
+    let mut split_results = Vec::new();
+    for y in min_ty..=max_ty {
+        for x in min_tx..=max_tx {
+            // compute web mercator tile bounds
+            let min_x = -WEB_MERCATOR_EXTENT + x as f64 * tile_extent;
+            let max_y = WEB_MERCATOR_EXTENT - y as f64 * tile_extent;
+            let bounds = (min_x, min_x + tile_extent,
+                max_y - tile_extent, max_y);
+
+            let quadkey = crate::coordinate::quadkey(x, y, zoom);
+            if let Some(dataset) = _split(dataset, bounds,
+                    &Target::Epsg(3857), options, Some(&quadkey),
+                    &mut |_, _| true)? {
+                split_results.push(SplitResult {
+                    dataset,
+                    geocode: quadkey,
+                    bounds,
+                });
+            }
+        }
+    }
+
+    Ok(split_results)
+}
+
+fn check_zoom(zoom: u32) -> Result<(), Box<dyn Error>> {
+    // tile counts at larger zooms overflow tile indices
+    match zoom as usize > crate::coordinate::MAX_ZOOM {
+        true => Err(format!("zoom {} too large", zoom).into()),
+        false => Ok(()),
+    }
+}
+
+fn xyz_tile_index(longitude: f64, latitude: f64, zoom: u32) -> (u32, u32) {
+    let count = (1u64 << zoom) as f64;
+    let latitude = latitude.max(-WEB_MERCATOR_MAX_LATITUDE)
+        .min(WEB_MERCATOR_MAX_LATITUDE).to_radians();
+
+    let x = (longitude + 180.0) / 360.0 * count;
+    let y = (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln()
+        / std::f64::consts::PI) / 2.0 * count;
+
+    // clamp indices to the tile grid
+    let max_index = count - 1.0;
+    (x.floor().max(0.0).min(max_index) as u32,
+        y.floor().max(0.0).min(max_index) as u32)
+}
+
+#[derive(Clone, Debug)]
+pub struct SplitOutput {
+    /// gdal driver name, for example "GTiff"
+    pub driver: String,
+    /// output path where '{geocode}', '{name}', '{min_x}', '{max_x}',
+    /// '{min_y}' and '{max_y}' are substituted for each window
+    pub path: String,
+    /// gdal creation options, for example ("COMPRESS", "DEFLATE")
+    pub creation_options: Vec<(String, String)>,
+    /// overview decimation factors built for each output, for example
+    /// [2, 4, 8], using the split resampling method
+    pub overviews: Vec<usize>,
+}
+
+impl SplitOutput {
+    fn render(&self, dataset: &Dataset, bounds: (f64, f64, f64, f64),
+            geocode: Option<&str>) -> Result<String, Box<dyn Error>> {
+        let mut path = self.path.clone();
+        if path.contains("{geocode}") {
+            let geocode = geocode.ok_or("geocode unavailable for path")?;
+            path = path.replace("{geocode}", geocode);
+        }
+
+        // use the source file stem as the dataset name
+        let description = unsafe {
+            let rv = gdal_sys::GDALGetDescription(
+                dataset.c_dataset() as gdal_sys::GDALMajorObjectH);
+            std::ffi::CStr::from_ptr(rv).to_string_lossy().into_owned()
+        };
+        let name = Path::new(&description).file_stem()
+            .and_then(|x| x.to_str()).unwrap_or("dataset").to_string();
+
+        Ok(path.replace("{name}", &name)
+            .replace("{min_x}", &bounds.0.to_string())
+            .replace("{max_x}", &bounds.1.to_string())
+            .replace("{min_y}", &bounds.2.to_string())
+            .replace("{max_y}", &bounds.3.to_string()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SplitOptions {
+    /// pixels of neighboring context padding each side of the window
+    pub overlap_pixels: usize,
+    /// skip windows where the fraction of valid pixels is lower
+    pub min_coverage: f64,
+    /// create outputs on disk rather than in memory
+    pub output: Option<SplitOutput>,
+    /// target (x, y) pixel size in dataset projection units,
+    /// defaults to the source resolution
+    pub resolution: Option<(f64, f64)>,
+    /// resampling method when a target resolution is defined
+    pub resampling: Resampling,
+    /// shrink each window to the bounding box of its valid pixels
+    pub trim_to_data: bool,
+    /// explicit grid in the split projection, window bounds are snapped
+    /// outward to its pixels and clipped to its extent
+    pub grid: Option<GridSpec>,
+    /// (x, y) origin in split epsg units that window bounds are snapped
+    /// outward to at the target resolution
+    pub grid_origin: Option<(f64, f64)>,
+    /// token checked between windows and bands to abort the split
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        SplitOptions {
+            overlap_pixels: 0,
+            min_coverage: 0.0,
+            output: None,
+            resolution: None,
+            resampling: Resampling::Nearest,
+            trim_to_data: false,
+            grid: None,
+            grid_origin: None,
+            cancellation: None,
+        }
+    }
+}
+
+const SPLIT_MAX_ITERATIONS: usize = 1 << 20;
+
+pub fn split(dataset: &Dataset, min_cx: f64, max_cx: f64,
+        min_cy : f64, max_cy: f64, epsg_code: u32)
+        -> Result<Option<Dataset>, Box<dyn Error>> {
+    split_with_options(dataset, min_cx, max_cx, min_cy,
+        max_cy, epsg_code, &SplitOptions::default())
+}
+
+pub fn split_with_options(dataset: &Dataset, min_cx: f64, max_cx: f64,
+        min_cy : f64, max_cy: f64, epsg_code: u32, options: &SplitOptions)
+        -> Result<Option<Dataset>, Box<dyn Error>> {
+    split_with_progress(dataset, min_cx, max_cx, min_cy,
+        max_cy, epsg_code, options, &mut |_, _| true)
+}
+
+pub fn split_with_progress(dataset: &Dataset, min_cx: f64, max_cx: f64,
+        min_cy : f64, max_cy: f64, epsg_code: u32, options: &SplitOptions,
+        progress: &mut ProgressCallback)
+        -> Result<Option<Dataset>, Box<dyn Error>> {
+    _split(dataset, (min_cx, max_cx, min_cy, max_cy),
+        &Target::Epsg(epsg_code), options, None, progress)
+}
+
+pub fn split_with_target(dataset: &Dataset, bounds: (f64, f64, f64, f64),
+        target: &Target, options: &SplitOptions)
+        -> Result<Option<Dataset>, Box<dyn Error>> {
+    _split(dataset, bounds, target, options, None, &mut |_, _| true)
+}
+
+fn _split(dataset: &Dataset, bounds: (f64, f64, f64, f64),
+        target: &Target, options: &SplitOptions, geocode: Option<&str>,
+        progress: &mut ProgressCallback)
+        -> Result<Option<Dataset>, Box<dyn Error>> {
+    // cancellation is checked with each progress report
+    let mut progress = |fraction: f64, stage: &str| {
+        !crate::is_cancelled(&options.cancellation)
+            && progress(fraction, stage)
+    };
+    let progress: &mut ProgressCallback = &mut progress;
+
+    // initialize CoordTransforms from dataset
+    let (transform, _, src_spatial_ref, dst_spatial_ref) =
+        crate::coordinate::get_transform_refs_with_target(dataset, target)?;
+
+    // if defined -> snap window bounds to the target grid
+    let (min_cx, max_cx, min_cy, max_cy) = match (&options.grid,
+            options.grid_origin, options.resolution) {
+        (Some(grid), _, _) => {
+            let grid_crs =
+                crate::coordinate::identify_crs(&grid.projection)?;
+            if grid_crs != crate::coordinate::identify_crs(
+                    &dst_spatial_ref.to_wkt()?)? {
+                return Err("grid projection differs from split target"
+                    .into());
+            }
+
+            let (min_cx, max_cx, min_cy, max_cy) =
+                crate::coordinate::snap_to_grid(bounds,
+                    (grid.min_x, grid.max_y), grid.resolution);
+            (min_cx.max(grid.min_x), max_cx.min(grid.max_x),
+                min_cy.max(grid.min_y), max_cy.min(grid.max_y))
+        },
+        (None, Some(origin), Some(resolution)) =>
+            crate::coordinate::snap_to_grid(bounds, origin, resolution),
+        (None, Some(_), None) =>
+            return Err("grid origin requires a resolution".into()),
+        (None, None, _) => bounds,
+    };
+
+    if min_cx >= max_cx || min_cy >= max_cy {
+        return Ok(None);
+    }
+    crate::report_progress(progress, 0.0, "bounds")?;
+    let coord_transform = CoordTransform::new(
+        &src_spatial_ref, &dst_spatial_ref)?;
+    let reverse_transform = CoordTransform::new(
+        &dst_spatial_ref, &src_spatial_ref)?;
+
+    // compute center point pixels
+    let mid_cx = (min_cx + max_cx) / 2.0;
+    let mid_cy = (min_cy + max_cy) / 2.0;
+
+    let (center_tx, center_ty, _) = crate::coordinate::transform_coord(
+        mid_cx, mid_cy, 0.0, &reverse_transform)?;
+
+    let (center_px, center_py) = crate::coordinate::coord_to_pixel(
+        center_tx, center_ty, &transform)?;
+
+    // compute window pixel bounding box
+    let mut bound_min_px = center_px as isize;
+    let mut bound_max_px = center_px as isize;
+    let mut bound_min_py = center_py as isize;
+    let mut bound_max_py = center_py as isize;
+
+    let mut bound_min_cx;
+    let mut bound_max_cx;
+    let mut bound_min_cy;
+    let mut bound_max_cy;
+
+    let mut iterations = 0;
+    loop {
+        // polar transforms may never envelope the window
+        iterations += 1;
+        if iterations > SPLIT_MAX_ITERATIONS {
+            return Err(format!("window bounds failed to converge \
+                after {} iterations", SPLIT_MAX_ITERATIONS).into());
+        }
+
+        // convert bounding pixels to coordinates
+        let pixels = vec![
+            (bound_min_px, bound_min_py, 0),
+            (bound_max_px, bound_min_py, 0),
+            (bound_min_px, bound_max_py, 0),
+            (bound_max_px, bound_max_py, 0)
+        ];
+
+        let (xs, ys, _) = crate::coordinate::transform_pixels(
+            &pixels, &transform, &coord_transform)?;
+
+        bound_min_cx = xs[0].max(xs[2]);
+        bound_max_cx = xs[1].min(xs[3]);
+        bound_min_cy = ys[2].max(ys[3]);
+        bound_max_cy = ys[0].min(ys[1]);
+
+        if xs.iter().chain(ys.iter()).any(|x| !x.is_finite()) {
+            return Err("window bounds transform to invalid coordinates"
+                .into());
+        }
+
+        // check if bounding box envolopes window
+        if bound_min_cx <= min_cx
+                && bound_max_cx >= max_cx
+                && bound_min_cy <= min_cy
+                && bound_max_cy >= max_cy {
+                //&& &bound_max_cy >= min_cy { TODO - validate fix?
+            break;
+        }
+
+        // increment one of the bounds
+        // TODO - need to fix this in the case where y transforms are non-negative
+        let bound_differences = vec![
+            bound_min_cx - min_cx,
+            max_cx - bound_max_cx, 
+            bound_min_cy - min_cy,
+            max_cy - bound_max_cy
+        ];
+
+        let (mut index, mut value) = (0, bound_differences[0]);
+        for (i, x) in bound_differences.iter().enumerate().skip(1) {
+            if x > &value {
+                value = *x;
+                index = i;
+            }
+        }
+
+        match index {
+            0 => bound_min_px -= 1,
+            1 => bound_max_px += 1,
+            2 => bound_max_py += 1,
+            3 => bound_min_py -= 1,
+            _ => unreachable!(),
+        }
+    }
+
+    log::trace!("split pixel bounds ({}, {}, {}, {}) after {} iterations",
+        bound_min_px, bound_max_px, bound_min_py, bound_max_py, iterations);
+    log::trace!("split coordinate bounds ({}, {}, {}, {})",
+        bound_min_cx, bound_max_cx, bound_min_cy, bound_max_cy);
+
+    split_pixels(dataset, (bound_min_px, bound_max_px,
+        bound_min_py, bound_max_py), bounds, options, geocode, progress)
+}
+
+fn split_pixels(dataset: &Dataset,
+        pixel_bounds: (isize, isize, isize, isize),
+        bounds: (f64, f64, f64, f64), options: &SplitOptions,
+        geocode: Option<&str>, progress: &mut ProgressCallback)
+        -> Result<Option<Dataset>, Box<dyn Error>> {
+    let (mut bound_min_px, mut bound_max_px,
+        mut bound_min_py, mut bound_max_py) = pixel_bounds;
+    let (src_width, src_height) = dataset.raster_size();
+    let (mut transform, projection) =
+        crate::coordinate::get_transform_projection(dataset)?;
+
+    // skip window if the pixel boundaries don't fall within image
+    if bound_max_px < 0 || bound_min_px >= src_width as isize
+            || bound_max_py < 0 || bound_min_py >= src_height as isize {
+        return Ok(None);
+    }
+
+    // skip window if too few pixels are valid
+    if options.min_coverage > 0.0 {
+        let coverage = window_coverage(dataset,
+            (bound_min_px, bound_min_py),
+            (bound_max_px - bound_min_px, bound_max_py - bound_min_py))?;
+        if coverage < options.min_coverage {
+            return Ok(None);
+        }
+    }
+
+    // shrink window to the extent of valid pixels
+    if options.trim_to_data {
+        match valid_extent(dataset, (bound_min_px, bound_min_py),
+                (bound_max_px, bound_max_py))? {
+            Some((min_px, max_px, min_py, max_py)) => {
+                bound_min_px = min_px;
+                bound_max_px = max_px;
+                bound_min_py = min_py;
+                bound_max_py = max_py;
+            },
+            None => return Ok(None),
+        }
+    }
+
+    // pad window with overlapping context
+    let overlap = options.overlap_pixels as isize;
+    bound_min_px -= overlap;
+    bound_max_px += overlap;
+    bound_min_py -= overlap;
+    bound_max_py += overlap;
+
+    // compute raster offsets
+    let src_x_offset = bound_min_px.max(0) as isize;
+    let src_y_offset = bound_min_py.max(0) as isize;
+
+    let buf_width = (bound_max_px.min(src_width as isize) 
+        - bound_min_px.max(0)) as usize;
+    let buf_height = (bound_max_py.min(src_height as isize)
+        - bound_min_py.max(0)) as usize;
+
+    let dst_x_offset = (0 - bound_min_px).max(0) as isize;
+    let dst_y_offset = (0 - bound_min_py).max(0) as isize;
+
+    let dst_width = (bound_max_px - bound_min_px) as isize;
+    let dst_height = (bound_max_py - bound_min_py) as isize;
+
+    // scale destination window to the target resolution
+    let (scale_x, scale_y) = match options.resolution {
+        Some((x, y)) if x <= 0.0 || y <= 0.0 =>
+            return Err("target resolution must be positive".into()),
+        Some((x, y)) => (transform[1].abs() / x, transform[5].abs() / y),
+        None => (1.0, 1.0),
+    };
+
+    let scale = |value: isize, scale: f64| {
+        (value as f64 * scale).round() as isize
+    };
+
+    let dst_buf_width = scale(buf_width as isize, scale_x).max(1) as usize;
+    let dst_buf_height = scale(buf_height as isize, scale_y).max(1) as usize;
+    let dst_x_offset = scale(dst_x_offset, scale_x);
+    let dst_y_offset = scale(dst_y_offset, scale_y);
+    let dst_width = scale(dst_width, scale_x).max(1);
+    let dst_height = scale(dst_height, scale_y).max(1);
+
+    // rounding must not push the copied window beyond the dataset
+    let dst_buf_width = dst_buf_width
+        .min((dst_width - dst_x_offset).max(1) as usize);
+    let dst_buf_height = dst_buf_height
+        .min((dst_height - dst_y_offset).max(1) as usize);
+
+    log::trace!("split source offset ({}, {}) size {}x{}",
+        src_x_offset, src_y_offset, buf_width, buf_height);
+    log::trace!("split destination offset ({}, {}) size {}x{}",
+        dst_x_offset, dst_y_offset, dst_width, dst_height);
+
+    // initialize split Dataset
+    let rasterband = dataset.rasterband(1)?;
+    let gdal_type = rasterband.band_type();
+    let no_data_value = rasterband.no_data_value();
+
+    let split_dataset = match &options.output {
+        Some(output) => {
+            // create dataset on disk with the requested driver
+            let path = output.render(dataset, bounds, geocode)?;
+            if let Some(parent) = Path::new(&path).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let driver = Driver::get(&output.driver)?;
+            let creation_options: Vec<(&str, &str)> = output
+                .creation_options.iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect();
+
+            crate::create_dataset(&driver, &path, gdal_type, dst_width,
+                dst_height, dataset.raster_count(), no_data_value,
+                &creation_options)?
+        },
+        None => {
+            // only fill with no_data if window extends beyond the image
+            let fill = dst_buf_width as isize != dst_width
+                || dst_buf_height as isize != dst_height;
+
+            let driver = Driver::get("Mem")?;
+            crate::init_dataset_with_fill(&driver, "unreachable",
+                gdal_type, dst_width, dst_height,
+                dataset.raster_count(), no_data_value, fill)?
+        },
+    };
+
+    // modify transform
+    //let mut transform = dataset.geo_transform()?;
+    transform[0] = transform[0] + (bound_min_px as f64 * transform[1])
+        + (bound_min_py as f64 * transform[2]);
+    transform[3] = transform[3] + (bound_min_px as f64 * transform[4])
+        + (bound_min_py as f64 * transform[5]);
+
+    transform[1] /= scale_x;
+    transform[2] /= scale_y;
+    transform[4] /= scale_x;
+    transform[5] /= scale_y;
+
+    split_dataset.set_geo_transform(&transform)?;
+    split_dataset.set_projection(&projection)?;
+    crate::copy_all_metadata(dataset, &split_dataset)?;
+
+    // copy rasterband data to new image
+    let count = dataset.raster_count();
+    for i in 0..count {
+        crate::copy_raster_resampled(dataset, i+1, 
+            (src_x_offset, src_y_offset),
+            (buf_width, buf_height),
+            &split_dataset, i+1,
+            (dst_x_offset, dst_y_offset), 
+            (dst_buf_width, dst_buf_height), options.resampling)?;
+        crate::report_progress(progress,
+            (i + 1) as f64 / count as f64, "copy")?;
+    }
+
+    log::debug!("split {} rasterbands into {}x{} window {:?}",
+        count, dst_width, dst_height, geocode);
+
+    if let Some(output) = &options.output {
+        if !output.overviews.is_empty() {
+            crate::build_overviews(&split_dataset,
+                &output.overviews, options.resampling)?;
+        }
+    }
+
+    Ok(Some(split_dataset))
+}
+
+fn valid_extent(dataset: &Dataset, min: (isize, isize),
+        max: (isize, isize))
+        -> Result<Option<(isize, isize, isize, isize)>, Box<dyn Error>> {
+    let (src_width, src_height) = dataset.raster_size();
+
+    // compute window intersection with the image
+    let min_x = min.0.max(0);
+    let max_x = max.0.min(src_width as isize);
+    let min_y = min.1.max(0);
+    let max_y = max.1.min(src_height as isize);
+    if max_x <= min_x || max_y <= min_y {
+        return Ok(None);
+    }
+
+    let width = (max_x - min_x) as usize;
+    let height = (max_y - min_y) as usize;
+
+    // identify pixels valid in any rasterband
+    let mut valid_pixels = vec![false; width * height];
+    for i in 0..dataset.raster_count() {
+        let rasterband = dataset.rasterband(i+1)?;
+        let no_data_value = rasterband.no_data_value().unwrap_or(0.0);
+        let buffer = rasterband.read_as::<f64>((min_x, min_y),
+            (width, height), (width, height))?;
+
+        for (j, pixel) in buffer.data.iter().enumerate() {
+            if *pixel != no_data_value {
+                valid_pixels[j] = true;
+            }
+        }
+    }
+
+    // compute bounding box of valid pixels
+    let mut extent: Option<(isize, isize, isize, isize)> = None;
+    for (j, _) in valid_pixels.iter().enumerate().filter(|(_, x)| **x) {
+        let x = min_x + (j % width) as isize;
+        let y = min_y + (j / width) as isize;
+
+        extent = Some(match extent {
+            Some((min_px, max_px, min_py, max_py)) => (min_px.min(x),
+                max_px.max(x + 1), min_py.min(y), max_py.max(y + 1)),
+            None => (x, x + 1, y, y + 1),
+        });
+    }
+
+    Ok(extent)
+}
+
+fn window_coverage(dataset: &Dataset, offset: (isize, isize),
+        size: (isize, isize)) -> Result<f64, Box<dyn Error>> {
+    let (src_width, src_height) = dataset.raster_size();
+
+    // compute window intersection with the image
+    let min_x = offset.0.max(0);
+    let max_x = (offset.0 + size.0).min(src_width as isize);
+    let min_y = offset.1.max(0);
+    let max_y = (offset.1 + size.1).min(src_height as isize);
+    if max_x <= min_x || max_y <= min_y {
+        return Ok(0.0);
+    }
+
+    // sample a decimated window to limit reads
+    let width = (max_x - min_x) as usize;
+    let height = (max_y - min_y) as usize;
+    let sample_size = (width.min(64), height.min(64));
+
+    let mut valid_pixels = vec![false; sample_size.0 * sample_size.1];
+    for i in 0..dataset.raster_count() {
+        let rasterband = dataset.rasterband(i+1)?;
+        let no_data_value = rasterband.no_data_value().unwrap_or(0.0);
+        let buffer = rasterband.read_as::<f64>((min_x, min_y),
+            (width, height), sample_size)?;
+
+        for (j, pixel) in buffer.data.iter().enumerate() {
+            if *pixel != no_data_value {
+                valid_pixels[j] = true;
+            }
+        }
+    }
+
+    // scale valid fraction by the portion of window within the image
+    let valid_count = valid_pixels.iter().filter(|x| **x).count() as f64;
+    let intersect_fraction = (width * height) as f64
+        / (size.0 * size.1) as f64;
+
+    Ok(valid_count / valid_pixels.len() as f64 * intersect_fraction)
+}
+
+pub struct SplitResult {
+    pub dataset: Dataset,
+    pub geocode: String,
+    pub bounds: (f64, f64, f64, f64),
+}
+
+pub struct SplitIter<'a> {
+    dataset: &'a Dataset,
+    geocode: Geocode,
+    precision: usize,
+    options: SplitOptions,
+    windows: crate::coordinate::WindowIter,
+}
+
+impl<'a> Iterator for SplitIter<'a> {
+    type Item = Result<SplitResult, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // split windows until one intersects the dataset
+        for (min_cx, max_cx, min_cy, max_cy) in &mut self.windows {
+            // encode the window center to identify the geocode
+            let geocode = match self.geocode.encode(
+                    (min_cx + max_cx) / 2.0, (min_cy + max_cy) / 2.0,
+                    self.precision) {
+                Ok(geocode) => geocode,
+                Err(e) => return Some(Err(e)),
+            };
+
+            let dataset = match _split(self.dataset,
+                    (min_cx, max_cx, min_cy, max_cy),
+                    &Target::Epsg(self.geocode.get_epsg_code()),
+                    &self.options,
+                    Some(&geocode), &mut |_, _| true) {
+                Ok(Some(dataset)) => dataset,
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            };
+
+            return Some(Ok(SplitResult {
+                dataset,
+                geocode,
+                bounds: (min_cx, max_cx, min_cy, max_cy),
+            }));
+        }
+
+        None
+    }
+}
+
+pub fn split_grid(dataset: &Dataset, tiles: &[GridTile],
+        options: &SplitOptions) -> Result<Vec<SplitResult>, Box<dyn Error>> {
+    let dataset_crs =
+        crate::coordinate::identify_crs(&dataset.projection())?;
+
+    let mut results = Vec::new();
+    for tile in tiles.iter() {
+        let (min_x, max_x, min_y, max_y) = tile.bounds;
+        let tile_crs = crate::coordinate::identify_crs(&tile.projection)?;
+
+        let split_dataset = if tile_crs == dataset_crs {
+            // tiles in the dataset projection are cut in pixel space
+            let (transform, _) =
+                crate::coordinate::get_transform_projection(dataset)?;
+            let pixels = crate::coordinate::coords_to_pixels(
+                &[min_x, max_x], &[max_y, min_y], &transform)?;
+
+            let (x1, x2) = (pixels[0].0.round(), pixels[1].0.round());
+            let (y1, y2) = (pixels[0].1.round(), pixels[1].1.round());
+            split_pixels(dataset, (x1.min(x2) as isize, x1.max(x2) as isize,
+                    y1.min(y2) as isize, y1.max(y2) as isize),
+                tile.bounds, options, Some(&tile.id), &mut |_, _| true)?
+        } else {
+            let target = match tile_crs.epsg_code {
+                Some(epsg_code) => Target::Epsg(epsg_code),
+                None => Target::Wkt(tile.projection.clone()),
+            };
+
+            _split(dataset, tile.bounds, &target,
+                options, Some(&tile.id), &mut |_, _| true)?
+        };
+
+        if let Some(split_dataset) = split_dataset {
+            results.push(SplitResult {
+                dataset: split_dataset,
+                geocode: tile.id.clone(),
+                bounds: tile.bounds,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+pub fn split_sentinel2(dataset: &Dataset, options: &SplitOptions)
+        -> Result<Vec<SplitResult>, Box<dyn Error>> {
+    let tiles = crate::grid::sentinel2_tiles(dataset)?;
+    split_grid(dataset, &tiles, options)
+}
+
+pub fn split_modis(dataset: &Dataset, options: &SplitOptions)
+        -> Result<Vec<SplitResult>, Box<dyn Error>> {
+    let tiles = crate::grid::modis_tiles(dataset)?;
+    split_grid(dataset, &tiles, options)
+}
+
+pub fn split_geocode(dataset: &Dataset, geocode: Geocode,
+        precision: usize) -> Result<Vec<SplitResult>, Box<dyn Error>> {
+    split_iter(dataset, geocode, precision)?.collect()
+}
+
+pub fn split_geocode_with_options(dataset: &Dataset, geocode: Geocode,
+        precision: usize, options: &SplitOptions)
+        -> Result<Vec<SplitResult>, Box<dyn Error>> {
+    split_iter_with_options(dataset, geocode, precision, options)?.collect()
+}
+
+pub fn split_iter(dataset: &Dataset, geocode: Geocode, precision: usize)
+        -> Result<SplitIter, Box<dyn Error>> {
+    split_iter_with_options(dataset, geocode,
+        precision, &SplitOptions::default())
+}
+
+pub fn split_iter_with_options(dataset: &Dataset, geocode: Geocode,
+        precision: usize, options: &SplitOptions)
+        -> Result<SplitIter, Box<dyn Error>> {
+    geocode.check_precision(precision)?;
+
+    // compute geocode windows covering the dataset
+    let epsg_code = geocode.get_epsg_code();
+    let (min_cx, max_cx, min_cy, max_cy) = geocode.clamp_bounds(
+        crate::coordinate::get_bounds(dataset, epsg_code)?);
+    let (x_interval, y_interval) = geocode.get_intervals(precision);
+
+    let windows = crate::coordinate::get_windows_iter(min_cx, max_cx,
+        min_cy, max_cy, x_interval, y_interval, false);
+    log::debug!("split {:?} precision {} over {} windows",
+        geocode, precision, windows.len());
+
+    Ok(SplitIter {
+        dataset,
+        geocode,
+        precision,
+        options: options.clone(),
+        windows,
+    })
+}
+
+pub(crate) struct Geometry(pub(crate) gdal_sys::OGRGeometryH);
+
+impl Geometry {
+    pub(crate) fn to_wkt(&self) -> Result<String, Box<dyn Error>> {
+        let mut c_wkt = std::ptr::null_mut();
+        unsafe { gdal_sys::OGR_G_ExportToWkt(self.0, &mut c_wkt) };
+        if c_wkt.is_null() {
+            return Err("failed to export geometry".into());
+        }
+
+        let wkt = unsafe {
+            let wkt = std::ffi::CStr::from_ptr(c_wkt)
+                .to_string_lossy().into_owned();
+            gdal_sys::VSIFree(c_wkt as *mut std::os::raw::c_void);
+            wkt
+        };
+
+        Ok(wkt)
+    }
+}
+
+impl Drop for Geometry {
+    fn drop(&mut self) {
+        unsafe { gdal_sys::OGR_G_DestroyGeometry(self.0) };
+    }
+}
+
+pub(crate) fn parse_geometry(geometry: &str)
+        -> Result<Geometry, Box<dyn Error>> {
+    let c_geometry = CString::new(geometry.trim())?;
+
+    // geojson objects begin with a brace, otherwise assume wkt
+    let handle = unsafe {
+        if geometry.trim_start().starts_with('{') {
+            gdal_sys::OGR_G_CreateGeometryFromJson(c_geometry.as_ptr())
+        } else {
+            let mut c_ptr = c_geometry.as_ptr() as *mut c_char;
+            let mut handle = std::ptr::null_mut();
+            gdal_sys::OGR_G_CreateFromWkt(&mut c_ptr,
+                std::ptr::null_mut(), &mut handle);
+            handle
+        }
+    };
+
+    if handle.is_null() {
+        return Err("failed to parse geometry".into());
+    }
+
+    Ok(Geometry(handle))
+}
+
+pub fn clip(dataset: &Dataset, geometry: &str, epsg_code: u32)
+        -> Result<Option<Dataset>, Box<dyn Error>> {
+    let geometry = parse_geometry(geometry)?;
+
+    // crop dataset to geometry bounding box
+    let mut envelope = gdal_sys::OGREnvelope {
+        MinX: 0.0, MaxX: 0.0, MinY: 0.0, MaxY: 0.0 };
+    unsafe { gdal_sys::OGR_G_GetEnvelope(geometry.0, &mut envelope) };
+
+    let clip_dataset = match split(dataset, envelope.MinX,
+            envelope.MaxX, envelope.MinY, envelope.MaxY, epsg_code)? {
+        Some(clip_dataset) => clip_dataset,
+        None => return Ok(None),
+    };
+
+    // transform geometry into the dataset projection
+    let (_, _, src_spatial_ref, dst_spatial_ref) =
+        crate::coordinate::get_transform_refs(&clip_dataset, epsg_code)?;
+    let error = unsafe {
+        gdal_sys::OGR_G_AssignSpatialReference(geometry.0,
+            dst_spatial_ref.to_c_hsrs());
+        gdal_sys::OGR_G_TransformTo(geometry.0, src_spatial_ref.to_c_hsrs())
+    };
+
+    if error != gdal_sys::OGRErr::OGRERR_NONE {
+        return Err("failed to transform geometry".into());
+    }
+
+    // rasterize geometry into a mask
+    let mask_dataset = crate::mask::rasterize(&clip_dataset,
+        &[geometry.0], &[1.0], GDALDataType::GDT_Byte)?;
+
+    let mask = mask_dataset.rasterband(1)?.read_band_as::<u8>()?;
+
+    // set pixels outside of the geometry to nodata
+    let masked: Vec<bool> = mask.data.iter().map(|x| *x == 0).collect();
+    crate::mask::mask_pixels(&clip_dataset, &masked)?;
+
+    Ok(Some(clip_dataset))
+}
+
+pub fn split_by_features(dataset: &Dataset, features: &Dataset,
+        layer_index: isize, id_field: &str)
+        -> Result<Vec<(String, Dataset)>, Box<dyn Error>> {
+    // feature geometries are clipped in geographic coordinates
+    let projection = SpatialRef::from_epsg(4326)?.to_wkt()?;
+    let geometries = layer_geometries(features,
+        layer_index, Some(id_field), &projection)?;
+
+    let mut feature_datasets = Vec::new();
+    for (id, geometry) in geometries.iter() {
+        if let Some(clip_dataset) = clip(dataset, &geometry.to_wkt()?, 4326)? {
+            feature_datasets.push((id.clone(), clip_dataset));
+        }
+    }
+
+    Ok(feature_datasets)
+}
+
+pub(crate) fn layer_geometries(features: &Dataset, layer_index: isize,
+        field: Option<&str>, projection: &str)
+        -> Result<Vec<(String, Geometry)>, Box<dyn Error>> {
+    let (layer, field_index) = unsafe {
+        let layer = gdal_sys::GDALDatasetGetLayer(
+            features.c_dataset(), layer_index as c_int);
+        if layer.is_null() {
+            return Err(format!("layer '{}' not found", layer_index).into());
+        }
+
+        let field_index = match field {
+            Some(field) => {
+                let c_field = CString::new(field)?;
+                let field_index = gdal_sys::OGR_FD_GetFieldIndex(
+                    gdal_sys::OGR_L_GetLayerDefn(layer), c_field.as_ptr());
+                if field_index < 0 {
+                    return Err(format!("field '{}' not found",
+                        field).into());
+                }
+
+                field_index
+            },
+            None => -1,
+        };
+
+        gdal_sys::OGR_L_ResetReading(layer);
+        (layer, field_index)
+    };
+
+    // read feature fields and geometries in the projection
+    let spatial_ref = SpatialRef::from_wkt(projection)?;
+
+    #[cfg(major_ge_3)]
+    {
+        use gdal_sys::OSRAxisMappingStrategy;
+        spatial_ref.set_axis_mapping_strategy(
+            OSRAxisMappingStrategy::OAMS_TRADITIONAL_GIS_ORDER);
+    }
+
+    let mut geometries = Vec::new();
+    loop {
+        let feature = unsafe { gdal_sys::OGR_L_GetNextFeature(layer) };
+        if feature.is_null() {
+            break;
+        }
+
+        let result = unsafe { feature_wkt(feature,
+            field_index, &spatial_ref) };
+        unsafe { gdal_sys::OGR_F_Destroy(feature) };
+
+        if let Some((id, wkt)) = result? {
+            geometries.push((id, parse_geometry(&wkt)?));
+        }
+    }
+
+    Ok(geometries)
+}
+
+pub(crate) unsafe fn feature_wkt(feature: gdal_sys::OGRFeatureH,
+        field_index: c_int, spatial_ref: &SpatialRef)
+        -> Result<Option<(String, String)>, Box<dyn Error>> {
+    // negative field indices yield empty identifiers
+    let id = match field_index >= 0 {
+        true => {
+            let rv = gdal_sys::OGR_F_GetFieldAsString(feature, field_index);
+            std::ffi::CStr::from_ptr(rv).to_string_lossy().into_owned()
+        },
+        false => String::new(),
+    };
+
+    let geometry = gdal_sys::OGR_F_GetGeometryRef(feature);
+    if geometry.is_null() {
+        return Ok(None);
+    }
+
+    // transform a copy of the geometry and export as wkt
+    let geometry = Geometry(gdal_sys::OGR_G_Clone(geometry));
+    if gdal_sys::OGR_G_TransformTo(geometry.0, spatial_ref.to_c_hsrs())
+            != gdal_sys::OGRErr::OGRERR_NONE {
+        return Err(format!("failed to transform feature '{}'", id).into());
+    }
+
+    let wkt = geometry.to_wkt()
+        .map_err(|_| format!("failed to export feature '{}'", id))?;
+
+    Ok(Some((id, wkt)))
+}
+
+pub struct Chip {
+    /// (x, y) pixel offset of the chip within the source dataset
+    pub offset: (isize, isize),
+    pub dataset: Dataset,
+}
+
+pub fn split_chips(dataset: &Dataset, chip_size: usize, stride: usize,
+        min_coverage: f64) -> Result<Vec<Chip>, Box<dyn Error>> {
+    if chip_size == 0 || stride == 0 {
+        return Err("chip size and stride must be positive".into());
+    }
+
+    let (width, height) = dataset.raster_size();
+    let transform = dataset.geo_transform()?;
+    let projection = dataset.projection();
+
+    let driver = Driver::get("Mem")?;
+    let rasterband = dataset.rasterband(1)?;
+    let gdal_type = rasterband.band_type();
+    let no_data_value = rasterband.no_data_value();
+
+    // iterate over chips lying entirely within the image
+    let mut chips = Vec::new();
+    let mut y = 0;
+    while y + chip_size <= height {
+        let mut x = 0;
+        while x + chip_size <= width {
+            let offset = (x as isize, y as isize);
+
+            // initialize chip Dataset
+            let chip_dataset = crate::init_dataset_with_fill(&driver,
+                "unreachable", gdal_type, chip_size as isize,
+                chip_size as isize, dataset.raster_count(),
+                no_data_value, false)?;
+
+            let mut chip_transform = transform;
+            chip_transform[0] = transform[0] + (x as f64 * transform[1])
+                + (y as f64 * transform[2]);
+            chip_transform[3] = transform[3] + (x as f64 * transform[4])
+                + (y as f64 * transform[5]);
+
+            chip_dataset.set_geo_transform(&chip_transform)?;
+            chip_dataset.set_projection(&projection)?;
+            crate::copy_all_metadata(dataset, &chip_dataset)?;
+
+            // copy rasterband data to chip
+            for i in 0..dataset.raster_count() {
+                crate::copy_raster(dataset, i+1, offset,
+                    (chip_size, chip_size), &chip_dataset, i+1,
+                    (0, 0), (chip_size, chip_size))?;
+            }
+
+            if min_coverage <= 0.0
+                    || crate::get_coverage(&chip_dataset)? >= min_coverage {
+                chips.push(Chip { offset, dataset: chip_dataset });
+            }
+
+            x += stride;
+        }
+
+        y += stride;
+    }
+
+    Ok(chips)
+}
+
+pub fn split_bands(dataset: &Dataset, groups: &[(&str, &[isize])])
+        -> Result<Vec<(String, Dataset)>, Box<dyn Error>> {
+    let (width, height) = dataset.raster_size();
+    let driver = Driver::get("Mem")?;
+
+    let mut band_datasets = Vec::new();
+    for (name, indices) in groups.iter() {
+        if indices.is_empty() {
+            return Err(format!("band group '{}' is empty", name).into());
+        }
+
+        // initialize band group Dataset
+        let rasterband = dataset.rasterband(indices[0])?;
+        let gdal_type = rasterband.band_type();
+        let no_data_value = rasterband.no_data_value();
+
+        let band_dataset = crate::init_dataset_with_fill(&driver,
+            "unreachable", gdal_type, width as isize, height as isize,
+            indices.len() as isize, no_data_value, false)?;
+
+        band_dataset.set_geo_transform(&dataset.geo_transform()?)?;
+        band_dataset.set_projection(&dataset.projection())?;
+        crate::copy_metadata(dataset, &band_dataset)?;
+
+        // copy rasterbands into group
+        for (i, index) in indices.iter().enumerate() {
+            let dst_index = (i + 1) as isize;
+            crate::copy_raster(dataset, *index, (0, 0), (width, height),
+                &band_dataset, dst_index, (0, 0), (width, height))?;
+
+            crate::copy_band_metadata(&dataset.rasterband(*index)?,
+                &band_dataset.rasterband(dst_index)?)?;
+        }
+
+        band_datasets.push((name.to_string(), band_dataset));
+    }
+
+    Ok(band_datasets)
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::Dataset;
+    use gdal::raster::Buffer;
+    use gdal_sys::GDALDataType;
+
+    use std::path::Path;
+
+    #[test]
+    fn split_bands() {
+        // read dataset
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // split dataset into band groups
+        let groups: Vec<(&str, &[isize])> =
+            vec![("visible", &[1, 3]), ("nir", &[2])];
+        let band_datasets = super::split_bands(&dataset, &groups)
+            .expect("split bands");
+
+        assert_eq!(band_datasets.len(), 2);
+        assert_eq!(band_datasets[0].0, "visible");
+        assert_eq!(band_datasets[0].1.raster_count(), 2);
+        assert_eq!(band_datasets[1].1.raster_count(), 1);
+        assert_eq!(band_datasets[1].1.raster_size(), dataset.raster_size());
+
+        // compare band data
+        let data = dataset.rasterband(2).expect("read raster")
+            .read_band_as::<u8>().expect("read band");
+        let data2 = band_datasets[1].1.rasterband(1).expect("read raster2")
+            .read_band_as::<u8>().expect("read band2");
+        assert_eq!(data.data, data2.data);
+    }
+
+    #[test]
+    fn split_grid_snapping() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let transform = dataset.geo_transform().expect("geo transform");
+        let (width, height) = dataset.raster_size();
+        let (res_x, res_y) = (transform[1], transform[5]);
+
+        let grid = super::GridSpec {
+            min_x: transform[0],
+            max_x: transform[0] + width as f64 * res_x,
+            min_y: transform[3] + height as f64 * res_y,
+            max_y: transform[3],
+            resolution: (res_x, res_y),
+            projection: dataset.projection(),
+        };
+
+        // bounds straddle pixel edges and extend west of the grid
+        let bounds = (transform[0] - 5.5 * res_x,
+            transform[0] + 40.6 * res_x, transform[3] + 50.2 * res_y,
+            transform[3] + 20.7 * res_y);
+        let target = crate::coordinate::Target::Wkt(dataset.projection());
+        let options = super::SplitOptions {
+            grid: Some(grid.clone()),
+            ..Default::default()
+        };
+
+        let split_dataset = super::split_with_target(&dataset, bounds,
+                &target, &options).expect("split dataset")
+            .expect("window within dataset");
+        let split_transform = split_dataset.geo_transform()
+            .expect("split geo transform");
+        assert!(split_transform[0] >= grid.min_x - 1e-6);
+        let offset = (split_transform[0] - grid.min_x) / res_x;
+        assert!((offset - offset.round()).abs() < 1e-6);
+
+        // grids in other projections and origins without a resolution
+        // are rejected
+        let options = super::SplitOptions {
+            grid: Some(super::GridSpec {
+                projection: gdal::spatial_ref::SpatialRef::from_epsg(4326)
+                    .expect("initialize SpatialRef")
+                    .to_wkt().expect("export wkt"),
+                ..grid
+            }),
+            ..Default::default()
+        };
+        assert!(super::split_with_target(&dataset, bounds,
+            &target, &options).is_err());
+
+        let options = super::SplitOptions {
+            grid_origin: Some((transform[0], transform[3])),
+            ..Default::default()
+        };
+        assert!(super::split_with_target(&dataset, bounds,
+            &target, &options).is_err());
+    }
+
+    #[test]
+    fn split_modis() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // the fixture matches its tile, so it is cut without resampling
+        let results = super::split_modis(&dataset, &Default::default())
+            .expect("split modis");
+        let result = results.iter().find(|x| x.geocode == "h10v04")
+            .expect("h10v04 tile");
+        assert_eq!(result.dataset.raster_size(), dataset.raster_size());
+
+        for i in 0..dataset.raster_count() {
+            let data = dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<u8>().expect("read band");
+            let data2 = result.dataset.rasterband(i+1)
+                .expect("read raster2")
+                .read_band_as::<u8>().expect("read band2");
+            assert_eq!(data.data, data2.data);
+        }
+    }
+
+    #[test]
+    fn reproject() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let options = super::ReprojectOptions {
+            resolution: Some((0.05, 0.05)),
+            grid_origin: Some((0.0, 0.0)),
+            ..super::ReprojectOptions::default()
+        };
+        let reproject_dataset = super::reproject(&dataset,
+            &crate::coordinate::Target::Epsg(4326), &options)
+            .expect("reproject");
+
+        // output is aligned to the requested grid
+        let transform = reproject_dataset.geo_transform()
+            .expect("get transform");
+        assert_eq!(transform[1], 0.05);
+        assert!((transform[0] / 0.05 - (transform[0] / 0.05).round())
+            .abs() < 1e-6);
+
+        let crs = crate::coordinate::identify_crs(
+            &reproject_dataset.projection()).expect("identify crs");
+        assert_eq!(crs.epsg_code, Some(4326));
+    }
+
+    #[test]
+    fn merge_cancelled() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let token = crate::CancellationToken::new();
+        token.cancel();
+
+        let options = super::MergeOptions {
+            cancellation: Some(token),
+            ..super::MergeOptions::default()
+        };
+        let error = match super::merge_with_options(&[dataset], &options) {
+            Ok(_) => panic!("merge completed after cancellation"),
+            Err(e) => e,
+        };
+
+        assert!(error.downcast_ref::<crate::Cancelled>().is_some());
+    }
+
+    #[test]
+    fn split_cancelled() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let token = crate::CancellationToken::new();
+        token.cancel();
+
+        let options = super::SplitOptions {
+            cancellation: Some(token),
+            ..super::SplitOptions::default()
+        };
+        let error = match super::split_geocode_with_options(&dataset,
+                crate::coordinate::Geocode::Geohash, 3, &options) {
+            Ok(_) => panic!("split completed after cancellation"),
+            Err(e) => e,
+        };
+
+        assert!(error.downcast_ref::<crate::Cancelled>().is_some());
+    }
+
+    #[test]
+    fn split_precision() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        assert!(super::split_geocode(&dataset,
+            crate::coordinate::Geocode::Geohash, 26).is_err());
+        assert!(super::split_geocode(&dataset,
+            crate::coordinate::Geocode::QuadTile, 64).is_err());
+    }
+
+    #[test]
+    fn merge_memory_budget() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let datasets = vec![Dataset::open(path).expect("open dataset")];
+        let transform = datasets[0].geo_transform().expect("get transform");
+
+        let options = super::MergeOptions {
+            strategy: super::MergeStrategy::Max,
+            resolution: Some((transform[1] * 2.0, transform[5] * 2.0)),
+            ..super::MergeOptions::default()
+        };
+        let merge_dataset = super::merge_with_options(&datasets, &options)
+            .expect("merge");
+
+        // blocks of a single row read matching source rows
+        let budget = crate::parallel::MemoryBudget::from_bytes(1);
+        let budget_dataset = budget.scope(||
+            super::merge_with_options(&datasets, &options))
+            .expect("merge within budget");
+
+        assert_eq!(budget_dataset.raster_size(), (128, 128));
+        for i in 0..merge_dataset.raster_count() {
+            let data = merge_dataset.rasterband(i+1).expect("rasterband")
+                .read_band_as::<u8>().expect("read band");
+            let budget_data = budget_dataset.rasterband(i+1)
+                .expect("rasterband").read_band_as::<u8>()
+                .expect("read band");
+            assert_eq!(data.data, budget_data.data);
+        }
+    }
+
+    #[test]
+    fn merge_to_file() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let datasets = vec![Dataset::open(path).expect("open dataset")];
+        let transform = datasets[0].geo_transform().expect("get transform");
+        let driver = gdal::Driver::get("GTiff").expect("get driver");
+
+        // downsample and upsample within single row blocks
+        let budget = crate::parallel::MemoryBudget::from_bytes(1);
+        for (i, scale) in [2.0, 0.5].iter().enumerate() {
+            let options = super::MergeOptions {
+                resolution: Some((transform[1] * scale,
+                    transform[5] * scale)),
+                ..super::MergeOptions::default()
+            };
+            let merge_dataset = super::merge_with_options(&datasets,
+                &options).expect("merge");
+
+            let file_path = format!("/vsimem/merge_to_file_{}.tif", i);
+            let file_dataset = budget.scope(||
+                    super::merge_to_file_with_options(&datasets,
+                        &file_path, &driver, &[], &options))
+                .expect("merge to file");
+
+            assert_eq!(file_dataset.raster_size(),
+                merge_dataset.raster_size());
+            for j in 0..merge_dataset.raster_count() {
+                let data = merge_dataset.rasterband(j+1)
+                    .expect("rasterband").read_band_as::<u8>()
+                    .expect("read band");
+                let file_data = file_dataset.rasterband(j+1)
+                    .expect("rasterband").read_band_as::<u8>()
+                    .expect("read band");
+                assert_eq!(data.data, file_data.data);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_threads() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // shift an inverted copy to partially overlap the dataset
+        let driver = gdal::Driver::get("Mem").expect("get driver");
+        let shift_dataset = dataset.create_copy(&driver, "unreachable")
+            .expect("copy dataset");
+        let mut transform = dataset.geo_transform().expect("get transform");
+        transform[0] += 64.0 * transform[1];
+        transform[3] += 32.0 * transform[5];
+        shift_dataset.set_geo_transform(&transform)
+            .expect("set transform");
+        for i in 0..shift_dataset.raster_count() {
+            let rasterband = shift_dataset.rasterband(i+1)
+                .expect("rasterband");
+            let mut buffer = rasterband.read_band_as::<u8>()
+                .expect("read band");
+            buffer.data.iter_mut().for_each(|x| *x = 255 - *x);
+            rasterband.write::<u8>((0, 0), buffer.size, &buffer)
+                .expect("write band");
+        }
+
+        // aliased datasets are read once from a single thread
+        let datasets = [&dataset, &shift_dataset, &dataset];
+        let merge_dataset = super::merge(&datasets).expect("merge");
+
+        let options = super::MergeOptions {
+            threads: 2,
+            ..super::MergeOptions::default()
+        };
+        let budget = crate::parallel::MemoryBudget::from_bytes(1);
+        let thread_dataset = budget.scope(||
+            super::merge_with_options(&datasets, &options))
+            .expect("merge with threads");
+
+        assert_eq!(thread_dataset.raster_size(), (320, 288));
+        for i in 0..merge_dataset.raster_count() {
+            let data = merge_dataset.rasterband(i+1).expect("rasterband")
+                .read_band_as::<u8>().expect("read band");
+            let thread_data = thread_dataset.rasterband(i+1)
+                .expect("rasterband").read_band_as::<u8>()
+                .expect("read band");
+            assert_eq!(data.data, thread_data.data);
+        }
+
+        let options = super::MergeOptions {
+            strategy: super::MergeStrategy::Max,
+            ..options
+        };
+        assert!(super::merge_with_options(&datasets, &options).is_err());
+    }
+
+    #[test]
+    fn merge_rotated() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // shear a copy of the dataset
+        let driver = gdal::Driver::get("Mem").expect("get driver");
+        let rotated_dataset = dataset.create_copy(&driver, "unreachable")
+            .expect("copy dataset");
+        let mut transform = dataset.geo_transform().expect("get transform");
+        transform[2] = transform[1] * 0.25;
+        transform[4] = transform[1] * 0.1;
+        rotated_dataset.set_geo_transform(&transform)
+            .expect("set transform");
+
+        let datasets = [&rotated_dataset];
+        assert!(super::merge(&datasets).is_err());
+
+        let options = super::MergeOptions {
+            reproject: true,
+            ..super::MergeOptions::default()
+        };
+        let merge_dataset = super::merge_with_options(&datasets, &options)
+            .expect("merge");
+
+        // merged extent covers every corner of the sheared dataset
+        let merge_transform = merge_dataset.geo_transform()
+            .expect("get transform");
+        let (width, height) = merge_dataset.raster_size();
+        let min_x = merge_transform[0];
+        let max_x = min_x + width as f64 * merge_transform[1];
+        let max_y = merge_transform[3];
+        let min_y = max_y + height as f64 * merge_transform[5];
+        let tolerance = merge_transform[1];
+
+        for (px, py) in [(0.0, 0.0), (256.0, 0.0),
+                (0.0, 256.0), (256.0, 256.0)].iter() {
+            let x = transform[0] + px * transform[1] + py * transform[2];
+            let y = transform[3] + px * transform[4] + py * transform[5];
+            assert!(x >= min_x - tolerance && x <= max_x + tolerance);
+            assert!(y >= min_y - tolerance && y <= max_y + tolerance);
+        }
+    }
+
+    fn constant_dataset(value: f32, x: f64, y: f64) -> Dataset {
+        // 64x32 Float32 dataset with unit pixels in EPSG:4326
+        let projection = gdal::spatial_ref::SpatialRef::from_epsg(4326)
+            .expect("spatial ref").to_wkt().expect("projection");
+        let dataset = crate::DatasetBuilder::new(64, 32)
+            .gdal_type(GDALDataType::GDT_Float32).no_data(-1.0)
+            .projection(&projection)
+            .geo_transform([x, 1.0, 0.0, y, 0.0, -1.0])
+            .create_mem().expect("create dataset");
+        dataset.rasterband(1).expect("rasterband")
+            .write::<f32>((0, 0), (64, 32),
+                &Buffer::new((64, 32), vec![value; 64 * 32]))
+            .expect("write band");
+        dataset
+    }
+
+    fn read_f32(dataset: &Dataset) -> Vec<f32> {
+        dataset.rasterband(1).expect("rasterband")
+            .read_band_as::<f32>().expect("read band").data
+    }
+
+    fn split_window(dataset: &Dataset, options: &super::SplitOptions)
+            -> Option<Dataset> {
+        // bounds within pixels (16, 48, 8, 24) of a constant dataset
+        let target = crate::coordinate::Target::Wkt(dataset.projection());
+        super::split_with_target(dataset, (16.5, 47.5, 8.5, 23.5),
+            &target, options).expect("split dataset")
+    }
+
+    #[test]
+    fn split_overlap() {
+        let dataset = constant_dataset(10.0, 0.0, 32.0);
+        let split_dataset = split_window(&dataset,
+            &super::SplitOptions::default()).expect("split window");
+        assert_eq!(split_dataset.raster_size(), (32, 16));
+
+        let options = super::SplitOptions {
+            overlap_pixels: 4,
+            ..super::SplitOptions::default()
+        };
+        let split_dataset = split_window(&dataset, &options)
+            .expect("split window");
+
+        // window is padded with context on each side
+        assert_eq!(split_dataset.raster_size(), (40, 24));
+        assert_eq!(split_dataset.geo_transform().expect("get transform"),
+            [12.0, 1.0, 0.0, 28.0, 0.0, -1.0]);
+        assert!(read_f32(&split_dataset).iter().all(|x| *x == 10.0));
+    }
+
+    #[test]
+    fn split_min_coverage() {
+        // nodata covers the western half of the window
+        let dataset = constant_dataset(10.0, 0.0, 32.0);
+        dataset.rasterband(1).expect("rasterband")
+            .write::<f32>((16, 8), (16, 16),
+                &Buffer::new((16, 16), vec![-1.0; 16 * 16]))
+            .expect("write gap");
+
+        let options = super::SplitOptions {
+            min_coverage: 0.75,
+            ..super::SplitOptions::default()
+        };
+        assert!(split_window(&dataset, &options).is_none());
+
+        let options = super::SplitOptions {
+            min_coverage: 0.25,
+            ..super::SplitOptions::default()
+        };
+        let split_dataset = split_window(&dataset, &options)
+            .expect("split window");
+        assert_eq!(split_dataset.raster_size(), (32, 16));
+    }
+
+    #[test]
+    fn split_resolution() {
+        let dataset = constant_dataset(10.0, 0.0, 32.0);
+        let options = super::SplitOptions {
+            resolution: Some((2.0, 2.0)),
+            ..super::SplitOptions::default()
+        };
+        let split_dataset = split_window(&dataset, &options)
+            .expect("split window");
+
+        // window extent is kept at half the pixel count
+        assert_eq!(split_dataset.raster_size(), (16, 8));
+        assert_eq!(split_dataset.geo_transform().expect("get transform"),
+            [16.0, 2.0, 0.0, 24.0, 0.0, -2.0]);
+        assert!(read_f32(&split_dataset).iter().all(|x| *x == 10.0));
+    }
+
+    #[test]
+    fn split_trim_to_data() {
+        // nodata covers the western half of the window
+        let dataset = constant_dataset(10.0, 0.0, 32.0);
+        dataset.rasterband(1).expect("rasterband")
+            .write::<f32>((0, 0), (32, 32),
+                &Buffer::new((32, 32), vec![-1.0; 32 * 32]))
+            .expect("write gap");
+
+        let options = super::SplitOptions {
+            trim_to_data: true,
+            ..super::SplitOptions::default()
+        };
+        let split_dataset = split_window(&dataset, &options)
+            .expect("split window");
+
+        // window shrinks to the eastern valid pixels
+        assert_eq!(split_dataset.raster_size(), (16, 16));
+        assert_eq!(split_dataset.geo_transform().expect("get transform"),
+            [32.0, 1.0, 0.0, 24.0, 0.0, -1.0]);
+        assert!(read_f32(&split_dataset).iter().all(|x| *x == 10.0));
+
+        // windows without valid pixels are skipped
+        dataset.rasterband(1).expect("rasterband")
+            .write::<f32>((0, 0), (64, 32),
+                &Buffer::new((64, 32), vec![-1.0; 64 * 32]))
+            .expect("write gap");
+        assert!(split_window(&dataset, &options).is_none());
+    }
+
+    #[test]
+    fn split_output() {
+        use gdal::Metadata;
+
+        let dataset = constant_dataset(10.0, 0.0, 32.0);
+        let directory = std::env::temp_dir().join("satmod_split_test");
+        let options = super::SplitOptions {
+            output: Some(super::SplitOutput {
+                driver: "GTiff".to_string(),
+                path: directory.join("split_{min_x}.tif")
+                    .to_string_lossy().into_owned(),
+                creation_options: vec![
+                    ("COMPRESS".to_string(), "DEFLATE".to_string())],
+                overviews: vec![2],
+            }),
+            ..super::SplitOptions::default()
+        };
+
+        // close the output to flush it to disk
+        let split_dataset = split_window(&dataset, &options)
+            .expect("split window");
+        drop(split_dataset);
+
+        let split_dataset = Dataset::open(&directory.join("split_16.5.tif"))
+            .expect("open split dataset");
+        assert_eq!(split_dataset.driver().short_name(), "GTiff");
+        assert_eq!(split_dataset.metadata_item("COMPRESSION",
+            "IMAGE_STRUCTURE"), Some("DEFLATE".to_string()));
+        assert_eq!(split_dataset.raster_size(), (32, 16));
+        assert_eq!(crate::serialize::overview_factors(&split_dataset)
+            .expect("overview factors"), vec![2]);
+        assert!(read_f32(&split_dataset).iter().all(|x| *x == 10.0));
+
+        std::fs::remove_dir_all(&directory).expect("remove directory");
+    }
+
+    #[test]
+    fn merge_feather() {
+        // datasets overlap over 32 columns
+        let datasets = [constant_dataset(10.0, 0.0, 32.0),
+            constant_dataset(20.0, 32.0, 32.0)];
+        let options = super::MergeOptions {
+            strategy: super::MergeStrategy::Feather(8),
+            ..super::MergeOptions::default()
+        };
+        let merge_dataset = super::merge_with_options(&datasets, &options)
+            .expect("merge");
+        assert_eq!(merge_dataset.raster_size(), (96, 32));
+
+        let data = read_f32(&merge_dataset);
+        let mut blended = 0;
+        for (j, value) in data.iter().enumerate() {
+            match j % 96 {
+                x if x < 32 => assert_eq!(*value, 10.0),
+                x if x >= 64 => assert_eq!(*value, 20.0),
+                _ => {
+                    // overlap pixels blend between both datasets
+                    assert!(*value >= 10.0 && *value <= 20.0);
+                    if *value > 10.0 && *value < 20.0 {
+                        blended += 1;
+                    }
+                },
+            }
+        }
+
+        assert!(blended > 0);
+    }
+
+    #[test]
+    fn merge_nodata_aware() {
+        // later dataset has nodata over its left half
+        let dataset = constant_dataset(10.0, 0.0, 32.0);
+        let gap_dataset = constant_dataset(20.0, 0.0, 32.0);
+        gap_dataset.rasterband(1).expect("rasterband")
+            .write::<f32>((0, 0), (32, 32),
+                &Buffer::new((32, 32), vec![-1.0; 32 * 32]))
+            .expect("write gap");
+
+        let datasets = [&dataset, &gap_dataset];
+        let options = super::MergeOptions {
+            strategy: super::MergeStrategy::NodataAware,
+            ..super::MergeOptions::default()
+        };
+        let merge_dataset = super::merge_with_options(&datasets, &options)
+            .expect("merge");
+
+        // nodata pixels do not overwrite earlier valid pixels
+        for (j, value) in read_f32(&merge_dataset).iter().enumerate() {
+            match j % 64 < 32 {
+                true => assert_eq!(*value, 10.0),
+                false => assert_eq!(*value, 20.0),
+            }
+        }
+    }
+
+    #[test]
+    fn merge_reproject() {
+        let dataset = constant_dataset(10.0, 0.0, 32.0);
+
+        // web mercator dataset covering roughly the same extent
+        let mercator_dataset = constant_dataset(20.0, 0.0, 32.0);
+        let projection = gdal::spatial_ref::SpatialRef::from_epsg(3857)
+            .expect("spatial ref").to_wkt().expect("projection");
+        mercator_dataset.set_projection(&projection)
+            .expect("set projection");
+        mercator_dataset.set_geo_transform(
+                &[0.0, 111320.0, 0.0, 3763310.0, 0.0, -111320.0])
+            .expect("set transform");
+
+        let datasets = [&dataset, &mercator_dataset];
+        assert!(super::merge(&datasets).is_err());
+
+        let options = super::MergeOptions {
+            reproject: true,
+            ..super::MergeOptions::default()
+        };
+        let merge_dataset = super::merge_with_options(&datasets, &options)
+            .expect("merge");
+
+        // reprojected into the first dataset spatial reference system
+        assert_eq!(merge_dataset.projection(), dataset.projection());
+        assert!(read_f32(&merge_dataset).iter().any(|x| *x == 20.0));
+    }
+
+    #[test]
+    fn merge_grid() {
+        let dataset = constant_dataset(10.0, 0.0, 32.0);
+
+        // grid within the dataset at twice the resolution
+        let options = super::MergeOptions {
+            grid: Some(super::GridSpec {
+                min_x: 16.0,
+                max_x: 48.0,
+                min_y: 8.0,
+                max_y: 24.0,
+                resolution: (0.5, 0.5),
+                projection: dataset.projection(),
+            }),
+            ..super::MergeOptions::default()
+        };
+        let merge_dataset = super::merge_with_options(&[&dataset], &options)
+            .expect("merge");
+
+        assert_eq!(merge_dataset.raster_size(), (64, 32));
+        assert_eq!(merge_dataset.geo_transform().expect("get transform"),
+            [16.0, 0.5, 0.0, 24.0, 0.0, -0.5]);
+        assert!(read_f32(&merge_dataset).iter().all(|x| *x == 10.0));
+    }
+
+    #[test]
+    fn clip() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // transform the western half polygon into geographic coordinates
+        let geometry = super::parse_geometry(crate::testing::WEST_POLYGON)
+            .expect("parse geometry");
+        let src_spatial_ref = gdal::spatial_ref::SpatialRef::from_wkt(
+            &dataset.projection()).expect("spatial ref");
+        let dst_spatial_ref = gdal::spatial_ref::SpatialRef::from_epsg(4326)
+            .expect("spatial ref");
+        #[cfg(major_ge_3)]
+        dst_spatial_ref.set_axis_mapping_strategy(gdal_sys::
+            OSRAxisMappingStrategy::OAMS_TRADITIONAL_GIS_ORDER);
+        let error = unsafe {
+            gdal_sys::OGR_G_AssignSpatialReference(geometry.0,
+                src_spatial_ref.to_c_hsrs());
+            gdal_sys::OGR_G_TransformTo(geometry.0,
+                dst_spatial_ref.to_c_hsrs())
+        };
+        assert_eq!(error, gdal_sys::OGRErr::OGRERR_NONE);
+        let wkt = geometry.to_wkt().expect("export geometry");
+
+        let clip_dataset = super::clip(&dataset, &wkt, 4326)
+            .expect("clip").expect("clip dataset");
+
+        // crop covers the polygon and lies within the dataset
+        let transform = dataset.geo_transform().expect("get transform");
+        let clip_transform = clip_dataset.geo_transform()
+            .expect("get transform");
+        let (width, height) = clip_dataset.raster_size();
+        let tolerance = transform[1].abs();
+        assert!(width <= 256 && height <= 256);
+        assert!(clip_transform[0] >= transform[0] - tolerance);
+        assert!(clip_transform[0] <= -8895604.0 + tolerance);
+        assert!(clip_transform[0] + width as f64 * clip_transform[1]
+            >= -8339628.0 - tolerance);
+        assert!(clip_transform[3] <= transform[3] + tolerance);
+        assert!(clip_transform[3] + height as f64 * clip_transform[5]
+            <= 4447801.0 + tolerance);
+
+        // read the clipped pixel covering a source pixel center
+        let read_pixel = |x: isize, y: isize| {
+            let cx = transform[0] + (x as f64 + 0.5) * transform[1];
+            let cy = transform[3] + (y as f64 + 0.5) * transform[5];
+            let px = ((cx - clip_transform[0]) / clip_transform[1]) as isize;
+            let py = ((cy - clip_transform[3]) / clip_transform[5]) as isize;
+            clip_dataset.rasterband(1).expect("rasterband")
+                .read_as::<f64>((px, py), (1, 1), (1, 1))
+                .expect("read pixel").data[0]
+        };
+
+        let source_pixel = dataset.rasterband(1).expect("rasterband")
+            .read_as::<f64>((10, 100), (1, 1), (1, 1))
+            .expect("read pixel").data[0];
+        let no_data_value = clip_dataset.rasterband(1).expect("rasterband")
+            .no_data_value().expect("no data value");
+        assert_eq!(read_pixel(10, 100), source_pixel);
+        assert_eq!(read_pixel(200, 100), no_data_value);
+    }
+
+    #[test]
+    fn split_by_features() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        let polygon = crate::testing::pixel_polygon(&dataset,
+            (160, 224, 32, 96)).expect("pixel polygon");
+        let features = crate::testing::feature_layer(&dataset.projection(),
+                &[("west", crate::testing::WEST_POLYGON), ("east", &polygon)])
+            .expect("feature layer");
+
+        let feature_datasets = super::split_by_features(&dataset,
+            &features, 0, "id").expect("split by features");
+        let ids: Vec<&str> = feature_datasets.iter()
+            .map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["west", "east"]);
+
+        // each clip holds source pixels within its feature
+        let transform = dataset.geo_transform().expect("get transform");
+        for ((_, clip_dataset), (x, y)) in feature_datasets.iter()
+                .zip([(10, 100), (190, 60)].iter()) {
+            let clip_transform = clip_dataset.geo_transform()
+                .expect("get transform");
+            let cx = transform[0] + (*x as f64 + 0.5) * transform[1];
+            let cy = transform[3] + (*y as f64 + 0.5) * transform[5];
+            let px = ((cx - clip_transform[0]) / clip_transform[1]) as isize;
+            let py = ((cy - clip_transform[3]) / clip_transform[5]) as isize;
+
+            let pixel = clip_dataset.rasterband(1).expect("rasterband")
+                .read_as::<u8>((px, py), (1, 1), (1, 1))
+                .expect("read pixel").data[0];
+            let source_pixel = dataset.rasterband(1).expect("rasterband")
+                .read_as::<u8>((*x, *y), (1, 1), (1, 1))
+                .expect("read pixel").data[0];
+            assert_eq!(pixel, source_pixel);
+        }
+
+        assert!(super::split_by_features(&dataset,
+            &features, 0, "name").is_err());
+        assert!(super::split_by_features(&dataset,
+            &features, 1, "id").is_err());
+    }
+
+    fn describe_bands(dataset: &Dataset, descriptions: &[&str]) {
+        for (i, description) in descriptions.iter().enumerate() {
+            let rasterband = dataset.rasterband(i as isize + 1)
+                .expect("rasterband");
+            unsafe {
+                crate::set_object_description(rasterband.c_rasterband()
+                    as gdal_sys::GDALMajorObjectH, description)
+            }.expect("set description");
+        }
+    }
+
+    #[test]
+    fn merge_match_bands() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let driver = gdal::Driver::get("Mem").expect("get driver");
+
+        let first_dataset = dataset.create_copy(&driver, "unreachable")
+            .expect("copy dataset");
+        describe_bands(&first_dataset, &["red", "nir", "blue"]);
+
+        // reorder and invert rasterbands of the second dataset
+        let select_dataset = crate::select_bands(&dataset, &[3, 1, 2])
+            .expect("select bands");
+        let second_dataset = select_dataset
+            .create_copy(&driver, "unreachable").expect("copy dataset");
+        describe_bands(&second_dataset, &["blue", "red", "nir"]);
+        for i in 0..second_dataset.raster_count() {
+            let rasterband = second_dataset.rasterband(i+1)
+                .expect("rasterband");
+            let mut buffer = rasterband.read_band_as::<u8>()
+                .expect("read band");
+            buffer.data.iter_mut().for_each(|x| *x = 255 - *x);
+            rasterband.write::<u8>((0, 0), buffer.size, &buffer)
+                .expect("write band");
+        }
+
+        let datasets = [&first_dataset, &second_dataset];
+        let options = super::MergeOptions {
+            match_bands: true,
+            ..super::MergeOptions::default()
+        };
+        let merge_dataset = super::merge_with_options(&datasets, &options)
+            .expect("merge");
+
+        // rasterbands are aligned by description with the first dataset
+        assert_eq!(merge_dataset.raster_count(), 3);
+        for i in 0..merge_dataset.raster_count() {
+            let data = dataset.rasterband(i+1).expect("rasterband")
+                .read_band_as::<u8>().expect("read band");
+            let merge_data = merge_dataset.rasterband(i+1)
+                .expect("rasterband").read_band_as::<u8>()
+                .expect("read band");
+            let expected: Vec<u8> =
+                data.data.iter().map(|x| 255 - *x).collect();
+            assert_eq!(merge_data.data, expected);
+        }
+
+        // missing descriptions fail to match
+        describe_bands(&second_dataset, &["blue", "red", "swir"]);
+        assert!(super::merge_with_options(&datasets, &options).is_err());
+    }
+
+    #[test]
+    fn merge_provenance() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let datasets = vec![Dataset::open(path).expect("open dataset"),
+            Dataset::open(path).expect("open dataset")];
+
+        for (strategy, index) in [(super::MergeStrategy::First, 0),
+                (super::MergeStrategy::Last, 1),
+                (super::MergeStrategy::Max, 0)].iter() {
+            let options = super::MergeOptions {
+                strategy: *strategy,
+                provenance: true,
+                ..super::MergeOptions::default()
+            };
+            let merge_dataset = super::merge_with_options(&datasets,
+                &options).expect("merge");
+            assert_eq!(merge_dataset.raster_count(),
+                datasets[0].raster_count() + 1);
+
+            let indices = merge_dataset
+                .rasterband(merge_dataset.raster_count())
+                .expect("rasterband").read_band_as::<u16>()
+                .expect("read band");
+            assert!(indices.data.iter().all(|x| *x == *index));
+        }
+
+        let options = super::MergeOptions {
+            strategy: super::MergeStrategy::Mean,
+            provenance: true,
+            ..super::MergeOptions::default()
+        };
+        assert!(super::merge_with_options(&datasets, &options).is_err());
+    }
+
+    #[test]
+    fn merge_virtual() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let datasets = vec![Dataset::open(path).expect("open dataset")];
+
+        let mosaic = super::merge_virtual(&datasets).expect("merge virtual");
+        assert_eq!(mosaic.raster_size(), datasets[0].raster_size());
+        assert_eq!(mosaic.raster_count(), datasets[0].raster_count());
+
+        let data = datasets[0].rasterband(1).expect("rasterband")
+            .read_band_as::<u8>().expect("read band");
+        let mosaic_data = mosaic.rasterband(1).expect("rasterband")
+            .read_band_as::<u8>().expect("read band");
+        assert_eq!(data.data, mosaic_data.data);
+    }
+
+    #[test]
+    fn pansharpen() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let transform = dataset.geo_transform().expect("get transform");
+
+        // simulate a panchromatic band at twice the resolution
+        let pan_dataset = super::resample(&dataset,
+            transform[1] / 2.0, crate::Resampling::Nearest)
+            .expect("resample");
+
+        let sharpen_dataset = super::pansharpen(&dataset, &pan_dataset,
+            &super::PansharpenMethod::Brovey).expect("pansharpen");
+        assert_eq!(sharpen_dataset.raster_size(), (512, 512));
+        assert_eq!(sharpen_dataset.raster_count(), dataset.raster_count());
+
+        let method = super::PansharpenMethod::Weighted(vec![1.0]);
+        assert!(super::pansharpen(&dataset, &pan_dataset, &method).is_err());
+
+        // copy bands to Float32 with nodata so outputs are not rounded
+        let (width, height) = dataset.raster_size();
+        let ms_dataset = crate::DatasetBuilder::new(width, height)
+            .bands(dataset.raster_count())
+            .gdal_type(GDALDataType::GDT_Float32).no_data(-1.0)
+            .like(&dataset).expect("like dataset")
+            .create_mem().expect("create dataset");
+        for i in 0..dataset.raster_count() {
+            let buffer = dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<f64>().expect("read band");
+            ms_dataset.rasterband(i+1).expect("read raster")
+                .write::<f64>((0, 0), (width, height), &buffer)
+                .expect("write band");
+        }
+
+        // punch a nodata pixel into the panchromatic band
+        let pan_dataset = super::resample(&ms_dataset,
+            transform[1] / 2.0, crate::Resampling::Nearest)
+            .expect("resample");
+        let nodata_pixel = (37, 101);
+        pan_dataset.rasterband(1).expect("read raster")
+            .write::<f64>(nodata_pixel, (1, 1), &Buffer::new((1, 1),
+                vec![-1.0])).expect("write pixel");
+
+        let sharpen_dataset = super::pansharpen(&ms_dataset, &pan_dataset,
+            &super::PansharpenMethod::Brovey).expect("pansharpen");
+        let pan = pan_dataset.rasterband(1).expect("read raster")
+            .read_band_as::<f64>().expect("read band").data;
+        let bands: Vec<Vec<f64>> = (0..sharpen_dataset.raster_count())
+            .map(|i| sharpen_dataset.rasterband(i+1).expect("read raster")
+                .read_band_as::<f64>().expect("read band").data)
+            .collect();
+
+        // nodata propagates to every rasterband
+        let j = nodata_pixel.1 as usize * 512 + nodata_pixel.0 as usize;
+        assert!(bands.iter().all(|x| x[j] == -1.0));
+
+        // brovey bands sum to the pan value scaled by the band count
+        let mut checked = 0;
+        for j in (0..pan.len()).step_by(997) {
+            if pan[j] == -1.0 || bands.iter().any(|x| x[j] == -1.0) {
+                continue;
+            }
+
+            let sum: f64 = bands.iter().map(|x| x[j]).sum();
+            assert!((sum - 3.0 * pan[j]).abs() < 1e-3 * pan[j].max(1.0));
+            checked += 1;
+        }
+
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn resample() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        let transform = dataset.geo_transform().expect("get transform");
+
+        let resample_dataset = super::resample(&dataset,
+            transform[1] * 2.0, crate::Resampling::Average)
+            .expect("resample");
+        let resample_transform = resample_dataset.geo_transform()
+            .expect("get transform");
+
+        assert_eq!(resample_dataset.raster_size(), (128, 128));
+        assert_eq!(resample_dataset.raster_count(), dataset.raster_count());
+        assert_eq!(resample_transform[1], transform[1] * 2.0);
+        assert_eq!(resample_transform[5], transform[5] * 2.0);
+    }
+
+    #[test]
+    fn split_quadtile() {
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+
+        // tile bounds match the decoded quadkey of each tile
+        let results = super::split_quadtile(&dataset, 4,
+            &super::SplitOptions::default()).expect("split quadtile");
+        assert!(!results.is_empty());
+        for result in results.iter() {
+            assert_eq!(result.geocode.len(), 4);
+            assert_eq!(result.bounds, crate::coordinate::quadkey_decode(
+                &result.geocode).expect("decode quadkey"));
+        }
+
+        assert!(super::split_quadtile(&dataset, 32,
+            &super::SplitOptions::default()).is_err());
+    }
+
+    #[test]
+    fn xyz_tile_index() {
+        assert_eq!(super::xyz_tile_index(0.0, 0.0, 1), (1, 1));
+        assert_eq!(super::xyz_tile_index(-180.0, 89.0, 1), (0, 0));
+        assert_eq!(super::xyz_tile_index(180.0, -89.0, 2), (3, 3));
+        assert_eq!(super::xyz_tile_index(-105.08, 40.56, 10), (213, 385));
+
+        let path = Path::new("fixtures/MCD43A4.h10v04.006.tif");
+        let dataset = Dataset::open(path).expect("open dataset");
+        assert!(super::split_xyz(&dataset, 32, 256,
+            crate::Resampling::Nearest).is_err());
+    }
+
+    //use crate::coordinate::Geocode;
+
+    //use gdal::Driver;
+    //use gdal_sys::GDALDataType;
 
     /*#[test]
     fn transform_merge() {